@@ -0,0 +1,80 @@
+#![feature(test)]
+extern crate test;
+
+extern crate reform;
+
+use test::Bencher;
+
+use reform::number::Number;
+use reform::poly::raw::MultivariatePolynomial;
+
+/// Creates `(1+x+y+z+t)^n` and the same polynomial plus one, following Fateman's benchmark.
+fn fateman_setup(
+    n: usize,
+) -> (
+    MultivariatePolynomial<Number, u32>,
+    MultivariatePolynomial<Number, u32>,
+) {
+    let mut a = MultivariatePolynomial::from_monomial(Number::SmallInt(1), vec![0, 0, 0, 0]);
+    for i in 0..4 {
+        let mut e = vec![0; 4];
+        e[i] = 1;
+        a.append_monomial(Number::SmallInt(1), &e);
+    }
+
+    let mut p = a.clone();
+    for _ in 1..n {
+        p = p * &a;
+    }
+    let q = p.clone() + Number::SmallInt(1);
+    (p, q)
+}
+
+/// Creates two sparse polynomials `(1+x+y^2+z^3+t^5)^n` and `(1+t+z^2+y^3+x^5)^n`.
+fn sparse_setup(
+    n: usize,
+) -> (
+    MultivariatePolynomial<Number, u32>,
+    MultivariatePolynomial<Number, u32>,
+) {
+    let mut a = MultivariatePolynomial::from_monomial(Number::SmallInt(1), vec![0, 0, 0, 0]);
+    let mut b = a.clone();
+    for (i, d) in [1, 2, 3, 5].iter().enumerate() {
+        let mut e = vec![0; 4];
+        e[i] = *d;
+        a.append_monomial(Number::SmallInt(1), &e);
+        e.reverse();
+        b.append_monomial(Number::SmallInt(1), &e);
+    }
+
+    let (mut p, mut q) = (a.clone(), b.clone());
+    for _ in 1..n {
+        p = p * &a;
+        q = q * &b;
+    }
+    (p, q)
+}
+
+#[bench]
+fn poly_mul_fateman10(b: &mut Bencher) {
+    let (p, q) = fateman_setup(10);
+    b.iter(|| p.heap_mul(&q));
+}
+
+#[bench]
+fn poly_mul_fateman10_parallel(b: &mut Bencher) {
+    let (p, q) = fateman_setup(10);
+    b.iter(|| p.mul_parallel(&q, 4));
+}
+
+#[bench]
+fn poly_mul_sparse8(b: &mut Bencher) {
+    let (p, q) = sparse_setup(8);
+    b.iter(|| p.heap_mul(&q));
+}
+
+#[bench]
+fn poly_mul_sparse8_parallel(b: &mut Bencher) {
+    let (p, q) = sparse_setup(8);
+    b.iter(|| p.mul_parallel(&q, 4));
+}
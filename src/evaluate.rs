@@ -0,0 +1,137 @@
+use num_traits::{One, Zero};
+use number::Number;
+use rug::Float;
+use std::collections::HashMap;
+use structure::{
+    Element, VarName, FUNCTION_COS, FUNCTION_EXP, FUNCTION_LOG, FUNCTION_SIN, FUNCTION_SQRT,
+};
+
+impl Element {
+    /// Numerically evaluate the element, using the values of the variables in `values`.
+    /// Functions are evaluated by `functions`, which gets the name and the values of
+    /// the arguments and returns `None` if it cannot evaluate the function.
+    ///
+    /// The result is exact if possible and a float with a precision of `prec` bits
+    /// otherwise.
+    pub fn evaluate<F>(
+        &self,
+        values: &HashMap<VarName, Number>,
+        functions: &F,
+        prec: u32,
+    ) -> Result<Number, String>
+    where
+        F: Fn(VarName, &[Number]) -> Option<Number>,
+    {
+        match *self {
+            Element::Num(_, ref n) => Ok(n.clone()),
+            Element::Var(x, ref e) => match values.get(&x) {
+                Some(v) => power(v, e, prec),
+                None => Err(format!("No value for {}", self)),
+            },
+            Element::Pow(_, ref be) => {
+                let b = be.0.evaluate(values, functions, prec)?;
+                let e = be.1.evaluate(values, functions, prec)?;
+                power(&b, &e, prec)
+            }
+            Element::Term(_, ref fs) => {
+                let mut r = Number::one();
+                for f in fs {
+                    r *= f.evaluate(values, functions, prec)?;
+                }
+                Ok(r)
+            }
+            Element::SubExpr(_, ref ts) => {
+                let mut r = Number::zero();
+                for t in ts {
+                    r += t.evaluate(values, functions, prec)?;
+                }
+                Ok(r)
+            }
+            Element::Fn(_, name, ref args) => {
+                let mut vals = Vec::with_capacity(args.len());
+                for a in args {
+                    vals.push(a.evaluate(values, functions, prec)?);
+                }
+                match name {
+                    FUNCTION_EXP | FUNCTION_LOG | FUNCTION_SIN | FUNCTION_COS | FUNCTION_SQRT
+                        if vals.len() == 1 =>
+                    {
+                        evaluate_elementary(name, &vals[0], prec, true)
+                            .ok_or_else(|| format!("{} is undefined or not real", self))
+                    }
+                    _ => functions(name, &vals).ok_or_else(|| format!("Cannot evaluate {}", self)),
+                }
+            }
+            Element::RationalPolynomialCoefficient(_, ref p) => {
+                let (ref num, ref den) = **p;
+                let num = num.clone().to_expression();
+                let den = den.clone().to_expression();
+                let num = num.evaluate(values, functions, prec)?;
+                let den = den.evaluate(values, functions, prec)?;
+                if den.is_zero() {
+                    Err(format!("Division by zero in {}", self))
+                } else {
+                    Ok(num / den)
+                }
+            }
+            _ => Err(format!("Cannot evaluate {}", self)),
+        }
+    }
+}
+
+fn power(b: &Number, e: &Number, prec: u32) -> Result<Number, String> {
+    b.pow_prec(e, prec)
+        .ok_or_else(|| format!("{}^{} is undefined, not real or too large", b, e))
+}
+
+/// Evaluate the elementary function `name`, such as `exp_`, at `x`. Special values
+/// like `sqrt_(4) = 2` are exact. Other values are floats with a precision of `prec`
+/// bits if `approximate` is true, and `None` otherwise. An exact argument stays
+/// exact if possible, but a float argument always gives a float.
+/// Returns `None` if the result is undefined or not real.
+pub fn evaluate_elementary(
+    name: VarName,
+    x: &Number,
+    prec: u32,
+    approximate: bool,
+) -> Option<Number> {
+    let f: fn(Float) -> Float = match name {
+        FUNCTION_EXP => Float::exp,
+        FUNCTION_LOG => Float::ln,
+        FUNCTION_SIN => Float::sin,
+        FUNCTION_COS => Float::cos,
+        FUNCTION_SQRT => Float::sqrt,
+        _ => return None,
+    };
+
+    let exact = match *x {
+        Number::Float(..) | Number::Complex(..) => None,
+        _ => match name {
+            FUNCTION_EXP | FUNCTION_COS if x.is_zero() => Some(Number::one()),
+            FUNCTION_SIN if x.is_zero() => Some(Number::zero()),
+            FUNCTION_LOG if x.is_one() => Some(Number::zero()),
+            FUNCTION_SQRT => exact_sqrt(x, prec),
+            _ => None,
+        },
+    };
+
+    if exact.is_some() || !approximate {
+        return exact;
+    }
+
+    x.apply_float(prec, f)
+}
+
+/// Take the square root of an exact number, if it is exact.
+/// The square root of a negative number is imaginary.
+fn exact_sqrt(x: &Number, prec: u32) -> Option<Number> {
+    if *x < Number::zero() {
+        return exact_sqrt(&-x.clone(), prec)
+            .map(|r| r * Number::complex(Number::zero(), Number::one()));
+    }
+
+    match x.pow_prec(&Number::SmallRat(1, 2), prec) {
+        Some(Number::Float(..)) | None => None,
+        r => r,
+    }
+}
@@ -1303,6 +1303,8 @@ impl<'a> MatchIterator<'a> {
                 }
             }
             (Element::Pow(_, be), Element::Pow(dirty, be1)) => {
+                // a power with a symbolic exponent, such as x^n matched
+                // by x^n?, is removed completely
                 if let Element::Num(_, ref p) = be.1 {
                     if let Element::Num(_, ref p1) = be1.1 {
                         let newpow =
@@ -1313,11 +1315,7 @@ impl<'a> MatchIterator<'a> {
                                 Box::new((be1.0.clone(), Element::Num(false, newpow))),
                             ));
                         }
-                    } else {
-                        unreachable!()
                     }
-                } else {
-                    unreachable!()
                 }
             }
             (_, Element::Pow(dirty, be)) => {
@@ -1329,8 +1327,6 @@ impl<'a> MatchIterator<'a> {
                             Box::new((be.0.clone(), Element::Num(false, newpow))),
                         ));
                     }
-                } else {
-                    unreachable!()
                 }
             }
             (Element::Var(_, pow), Element::Var(x1, pow1)) => {
@@ -12,9 +12,13 @@ use std::time::{Duration, Instant};
 
 use crossbeam;
 use crossbeam::queue::MsQueue;
+use rug::float;
 
 use expand::ExpandIterator;
 use id::{MatchIterator, MatchKind, MatchObject, MatchOpt};
+use poly::polynomial::{
+    rationalpolynomial_add, rationalpolynomial_mul, rationalpolynomial_solve, Polynomial,
+};
 use streaming::MAXTERMMEM;
 use streaming::{InputTermStreamer, OutputTermStreamer};
 use structure::*;
@@ -376,6 +380,28 @@ impl Statement {
                 res.normalize_inplace(&var_info.global_info);
                 StatementIter::Simple(res, true)
             }
+            Statement::Differentiate(ref args) => {
+                let mut a = Vec::with_capacity(args.len() + 1);
+                a.push(mem::replace(input, Element::default()));
+                a.extend(args.iter().cloned());
+
+                let mut res = Element::Fn(true, FUNCTION_DIFF, a);
+                res.replace_dollar(&var_info.local_info.variables);
+                res.normalize_inplace(&var_info.global_info);
+
+                // the product rule may create several terms
+                match res {
+                    Element::SubExpr(_, mut f) => {
+                        f.reverse(); // the multiple iterator pops from the back
+                        StatementIter::Multiple(f, true)
+                    }
+                    // the derivative could not be computed: keep the term
+                    Element::Fn(_, FUNCTION_DIFF, mut a) => {
+                        StatementIter::Simple(a.swap_remove(0), false)
+                    }
+                    x => StatementIter::Simple(x, true),
+                }
+            }
             // TODO: use visitor pattern? this is almost a copy of splitarg
             Statement::Symmetrize(ref name) => {
                 // sort function arguments at the ground level
@@ -426,6 +452,11 @@ fn do_module_rec(
             return; // drop 0
         }
     }
+    if let Some((x, order)) = global_var_info.series {
+        if input.truncate_series(x, order) {
+            return; // drop terms beyond the order of the series expansion
+        }
+    }
     if current_index == statements.len() {
         output.add_term(input, global_var_info);
         return;
@@ -1294,6 +1325,18 @@ impl Module {
         verbosity: u64,
         num_threads: usize,
     ) {
+        // a series statement applies to the whole module
+        let global_series = var_info.global_info.series;
+        let mut series = global_series;
+        self.statements.retain(|s| match *s {
+            Statement::Series(ref args) => {
+                series = series_setting(args, &var_info.global_info);
+                false
+            }
+            _ => true,
+        });
+        var_info.global_info.series = series;
+
         // normalize the module
         let mut old_statements = mem::replace(&mut self.statements, vec![]);
         Module::statements_to_control_flow_stat(
@@ -1430,6 +1473,8 @@ impl Module {
             );
         }
 
+        var_info.global_info.series = global_series;
+
         // update the variables by their global values
         // TODO: global variables can also have indices?
         let gi = mem::replace(&mut var_info.local_info.global_variables, HashMap::new());
@@ -1441,6 +1486,103 @@ impl Module {
     }
 }
 
+/// Get the variable and the order of a series statement. A series
+/// statement without arguments disables the series expansion.
+/// The grammar only allows a variable and an integer as arguments.
+fn series_setting(args: &[Element], var_info: &GlobalVarInfo) -> Option<(VarName, isize)> {
+    if args.len() != 2 {
+        return None;
+    }
+
+    let mut order = args[1].clone();
+    order.normalize_inplace(var_info);
+    match (&args[0], order) {
+        (&Element::Var(x, _), Element::Num(_, Number::SmallInt(n))) => Some((x, n)),
+        // no term has a power of x beyond the range of i32
+        (&Element::Var(x, _), Element::Num(_, Number::BigInt(ref n))) => {
+            Some((x, if *n < 0 { i32::MIN as isize } else { i32::MAX as isize }))
+        }
+        _ => None,
+    }
+}
+
+/// Extract the linear system `a * x = b` from a list of equations `eq = 0`,
+/// where every equation is given by its terms. Returns an error if an equation
+/// is not linear in the unknowns or has an invalid coefficient.
+fn linear_system(
+    equations: &[Vec<Element>],
+    unknowns: &[Element],
+) -> Result<
+    (
+        Vec<Vec<(Polynomial, Polynomial)>>,
+        Vec<(Polynomial, Polynomial)>,
+    ),
+    String,
+> {
+    let zero = || (Polynomial::new(), Polynomial::new().cloned_one());
+
+    let mut a = vec![];
+    let mut b = vec![];
+    for eq in equations {
+        let mut row: Vec<_> = unknowns.iter().map(|_| zero()).collect();
+        let mut constant = zero();
+
+        for t in eq {
+            let factors = match *t {
+                Element::Term(_, ref f) => f.clone(),
+                ref x => vec![x.clone()],
+            };
+
+            let mut unknown = None;
+            let mut coeffs = vec![];
+            let mut rat_coeffs = vec![];
+            for f in factors {
+                if let Some(i) = unknowns.iter().position(|u| *u == f) {
+                    if unknown.is_some() {
+                        return Err(format!("Equation is not linear in the unknowns: {}", t));
+                    }
+                    unknown = Some(i);
+                    continue;
+                }
+
+                let nonlinear = match f {
+                    Element::Var(ref name, _) => unknowns.iter().any(|u| match *u {
+                        Element::Var(ref n, _) => n == name,
+                        _ => false,
+                    }),
+                    Element::Pow(_, ref be) => unknowns.contains(&be.0),
+                    _ => false,
+                };
+                if nonlinear {
+                    return Err(format!("Equation is not linear in the unknowns: {}", t));
+                }
+
+                match f {
+                    Element::RationalPolynomialCoefficient(_, p) => rat_coeffs.push(*p),
+                    x => coeffs.push(x),
+                }
+            }
+
+            let (mut num, mut den) = Polynomial::from_laurent(&Element::Term(true, coeffs))
+                .map_err(|e| format!("Invalid coefficient in equation: {}", e))?;
+            for (mut num1, mut den1) in rat_coeffs {
+                rationalpolynomial_mul(&mut num, &mut den, &mut num1, &mut den1);
+            }
+
+            let entry = match unknown {
+                Some(i) => &mut row[i],
+                None => &mut constant,
+            };
+            rationalpolynomial_add(&mut entry.0, &mut entry.1, &mut num, &mut den);
+        }
+
+        a.push(row);
+        b.push((-constant.0, constant.1));
+    }
+
+    Ok((a, b))
+}
+
 impl Program {
     pub fn do_program(&mut self, write_log: bool, verbosity: u64, num_threads: usize) {
         // set the log level
@@ -1471,6 +1613,11 @@ impl Program {
                     {
                         e.normalize_inplace(&self.var_info.global_info);
                     }
+                    if let Some((x, order)) = self.var_info.global_info.series {
+                        if e.truncate_series(x, order) {
+                            e = Element::SubExpr(false, vec![]);
+                        }
+                    }
 
                     match e {
                         Element::SubExpr(_, t) => {
@@ -1583,6 +1730,40 @@ impl Program {
                         }
                     }
                 }
+                Statement::Series(ref args) => {
+                    self.var_info.global_info.series =
+                        series_setting(args, &self.var_info.global_info);
+                }
+                Statement::Precision(ref bits) => match *bits {
+                    Element::Num(_, Number::SmallInt(n))
+                        if n > 0 && (n as u64) <= u64::from(float::prec_max()) =>
+                    {
+                        self.var_info.global_info.float_precision = n as u32;
+                    }
+                    _ => panic!("Precision should be a positive number of bits: {}", bits),
+                },
+                Statement::Algebraic(x, m) => {
+                    let coeffs = match x {
+                        Element::Var(name, _) => Polynomial::from(&m)
+                            .ok()
+                            .and_then(|p| p.to_univariate_coefficients(name))
+                            .map(|c| (name, c)),
+                        _ => None,
+                    };
+
+                    match coeffs {
+                        Some((name, c)) if c.len() > 1 => {
+                            // make the minimal polynomial monic
+                            let inv = Number::one() / c.last().unwrap().clone();
+                            let c = c.into_iter().map(|n| n * inv.clone()).collect();
+                            self.var_info.global_info.algebraic = Some((name, Arc::new(c)));
+                        }
+                        _ => panic!(
+                            "The minimal polynomial should be a polynomial in {}: {}",
+                            x, m
+                        ),
+                    }
+                }
                 Statement::Attrib(f, attribs) => match f {
                     Element::Var(name, _) | Element::Dollar(name, _) => {
                         self.var_info.global_info.func_attribs.insert(name, attribs);
@@ -1721,6 +1902,82 @@ impl Program {
                     }
                 }
                 Statement::Collect(ref id) => sort_statements.push(Statement::Collect(id.clone())),
+                Statement::Solve(ref eqs, ref unknowns) => {
+                    // collect the terms of every equation
+                    let mut equations = vec![];
+                    for e in eqs {
+                        match *e {
+                            Element::Dollar(..) => {
+                                let d = self
+                                    .var_info
+                                    .local_info
+                                    .get_dollar(e)
+                                    .expect("Dollar variable is uninitialized");
+                                equations.push(match *d {
+                                    Element::SubExpr(_, ref ts) => ts.clone(),
+                                    ref x => vec![x.clone()],
+                                });
+                            }
+                            Element::Var(ref name, _) => {
+                                let expr = &mut self
+                                    .expressions
+                                    .iter_mut()
+                                    .find(|(n, ..)| *n == *name)
+                                    .expect("Unknown expression in solve statement")
+                                    .1;
+
+                                let mut terms = vec![];
+                                while let Some(t) = expr.read_term() {
+                                    terms.push(t);
+                                }
+                                for t in &terms {
+                                    expr.add_term_input(t.clone());
+                                }
+                                equations.push(terms);
+                            }
+                            _ => panic!("Equations should be expressions or dollar variables"),
+                        }
+                    }
+
+                    let solution = linear_system(&equations, unknowns).and_then(|(mut a, mut b)| {
+                        rationalpolynomial_solve(&mut a, &mut b).map_err(|e| format!("{:?}", e))
+                    });
+
+                    // a system without a unique solution leaves the expressions unchanged
+                    let solution = match solution {
+                        Ok(s) => s,
+                        Err(e) => {
+                            println!("Warning: could not solve the system: {}", e);
+                            continue;
+                        }
+                    };
+
+                    // replace the unknowns in all expressions
+                    let mut ids = vec![];
+                    for (x, (num, den)) in unknowns.iter().zip(solution) {
+                        let mut rhs = if den.poly.is_constant() {
+                            let c = den.poly.coefficients()[0].clone();
+                            (num * (Number::one() / c)).to_expression()
+                        } else {
+                            Element::RationalPolynomialCoefficient(true, Box::new((num, den)))
+                        };
+                        rhs.normalize_inplace(&self.var_info.global_info);
+
+                        ids.push(Statement::IdentityStatement(IdentityStatement {
+                            mode: IdentityStatementMode::Once,
+                            contains_dollar: false,
+                            lhs: x.clone(),
+                            rhs,
+                        }));
+                    }
+
+                    statements.push_front(Statement::Module(Module {
+                        name: "solve".to_owned(),
+                        active_exprs: vec![],
+                        exclude_exprs: vec![],
+                        statements: ids,
+                    }));
+                }
                 Statement::MatchAssign(..) => {
                     panic!("Match assignment cannot be performed in the global scope.")
                 }
@@ -1730,6 +1987,9 @@ impl Program {
                 Statement::ReplaceBy(..) => {
                     panic!("ReplaceBy statement cannot be performed in the global scope.")
                 }
+                Statement::Differentiate(..) => {
+                    panic!("Differentiate statement cannot be performed in the global scope.")
+                }
                 Statement::SplitArg(..) => {
                     panic!("Splitarg statement cannot be performed in the global scope.")
                 }
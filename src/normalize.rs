@@ -1,15 +1,23 @@
+use evaluate::evaluate_elementary;
 use num_traits::{One, Pow, Zero};
 use number::Number;
 use poly::polynomial::{
-    rationalpolynomial_add, rationalpolynomial_mul, rationalpolynomial_normalize, Polynomial,
+    rationalpolynomial_add, rationalpolynomial_algebraic, rationalpolynomial_derivative,
+    rationalpolynomial_mul, rationalpolynomial_normalize, rationalpolynomial_series,
+    rationalpolynomial_truncate, Polynomial,
 };
 use sort::split_merge;
 use std::collections::HashMap;
 use std::mem;
+use std::slice;
 use structure::{
-    Element, FunctionAttributes, GlobalVarInfo, Ordering, FUNCTION_DELTA, FUNCTION_GCD,
-    FUNCTION_IFELSE, FUNCTION_LIST, FUNCTION_NARGS, FUNCTION_PROD, FUNCTION_RAT, FUNCTION_SUM,
-    FUNCTION_TAKEARG, FUNCTION_TERM,
+    Element, FunctionAttributes, GlobalVarInfo, Ordering, VarName, FUNCTION_ABS, FUNCTION_BINOM,
+    FUNCTION_CONJ, FUNCTION_COS, FUNCTION_DELTA, FUNCTION_DEN, FUNCTION_DER, FUNCTION_DIFF,
+    FUNCTION_DIV, FUNCTION_EVAL, FUNCTION_EXP, FUNCTION_FAC, FUNCTION_FACTORINT, FUNCTION_GCD,
+    FUNCTION_IFELSE, FUNCTION_INVMOD, FUNCTION_ISPRIME, FUNCTION_LIST, FUNCTION_LOG, FUNCTION_MAX,
+    FUNCTION_MIN, FUNCTION_MOD, FUNCTION_N, FUNCTION_NARGS, FUNCTION_NEXTPRIME, FUNCTION_NUM,
+    FUNCTION_POWMOD, FUNCTION_PROD, FUNCTION_RAT, FUNCTION_ROOTS, FUNCTION_SERIES, FUNCTION_SIGN,
+    FUNCTION_SIN, FUNCTION_SQRT, FUNCTION_SUM, FUNCTION_TAKEARG, FUNCTION_TERM, SYMBOL_I,
 };
 use tools::add_num_poly;
 
@@ -145,20 +153,27 @@ impl Element {
                         }
 
                         // convert to polyratfun if possible
+                        // negative powers of variables are moved to the denominator
                         if a.len() == 1 {
-                            if let Ok(mut prf) = Polynomial::from(&a[0]) {
-                                let mut one = prf.cloned_one();
-
-                                rationalpolynomial_normalize(&mut prf, &mut one);
-                                Element::RationalPolynomialCoefficient(false, Box::new((prf, one)))
+                            if let Ok((mut num, mut den)) = Polynomial::from_laurent(&a[0]) {
+                                rationalpolynomial_normalize(&mut num, &mut den);
+                                if !algebraic_reduce(&mut num, &mut den, var_info) {
+                                    return false;
+                                }
+                                Element::RationalPolynomialCoefficient(false, Box::new((num, den)))
                             } else {
                                 return false;
                             }
                         } else {
-                            match Polynomial::from(&a[0]) {
-                                Ok(mut num) => match Polynomial::from(&a[1]) {
-                                    Ok(mut den) => {
+                            match Polynomial::from_laurent(&a[0]) {
+                                Ok((num0, den0)) => match Polynomial::from_laurent(&a[1]) {
+                                    Ok((num1, den1)) => {
+                                        let mut num = num0 * den1;
+                                        let mut den = den0 * num1;
                                         rationalpolynomial_normalize(&mut num, &mut den);
+                                        if !algebraic_reduce(&mut num, &mut den, var_info) {
+                                            return false;
+                                        }
                                         Element::RationalPolynomialCoefficient(
                                             false,
                                             Box::new((num, den)),
@@ -180,7 +195,7 @@ impl Element {
 
                         if let (Ok(mut a1), Ok(mut a2)) = (ar, br) {
                             // check if the polynomials have integer coefficients
-                            for x in a1.poly.coefficients.iter().chain(&a2.poly.coefficients) {
+                            for x in a1.poly.coefficients().iter().chain(a2.poly.coefficients()) {
                                 match x {
                                     Number::SmallRat(..) | Number::BigRat(..) => return false,
                                     _ => {}
@@ -197,6 +212,394 @@ impl Element {
                             return false;
                         }
                     }
+                    FUNCTION_ROOTS => {
+                        if a.is_empty() || a.len() > 2 {
+                            return false;
+                        }
+
+                        // the optional second argument is the maximal width of the intervals
+                        let eps = if a.len() == 2 {
+                            match a[1] {
+                                Element::Num(_, ref n) if *n > Number::zero() => Some(n.clone()),
+                                _ => return false,
+                            }
+                        } else {
+                            None
+                        };
+
+                        let roots = match Polynomial::from(&a[0]) {
+                            Ok(p) => match p.poly.to_wide().real_roots(eps.as_ref()) {
+                                Some(roots) => roots,
+                                None => return false,
+                            },
+                            Err(_) => return false,
+                        };
+
+                        Element::Fn(
+                            false,
+                            FUNCTION_LIST,
+                            roots
+                                .into_iter()
+                                .map(|(l, u)| {
+                                    Element::Fn(
+                                        false,
+                                        FUNCTION_LIST,
+                                        vec![Element::Num(false, l), Element::Num(false, u)],
+                                    )
+                                })
+                                .collect(),
+                        )
+                    }
+                    FUNCTION_N => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        let mut res = a[0].clone();
+                        res.to_float(var_info.float_precision);
+                        res.normalize_inplace(var_info);
+                        res
+                    }
+                    FUNCTION_EXP | FUNCTION_LOG | FUNCTION_SIN | FUNCTION_COS | FUNCTION_SQRT => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        match a[0] {
+                            Element::Num(_, ref x) => {
+                                // only a float argument gives a float
+                                let approximate = match *x {
+                                    Number::Float(..) => true,
+                                    _ => false,
+                                };
+                                match evaluate_elementary(
+                                    *n,
+                                    x,
+                                    var_info.float_precision,
+                                    approximate,
+                                ) {
+                                    Some(r) => Element::Num(false, r),
+                                    None => return false,
+                                }
+                            }
+                            // exp_(log_(x)) = x
+                            Element::Fn(_, FUNCTION_LOG, ref b)
+                                if *n == FUNCTION_EXP && b.len() == 1 =>
+                            {
+                                b[0].clone()
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_FAC => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        match a[0] {
+                            Element::Num(_, Number::SmallInt(k)) if k >= 0 => {
+                                Element::Num(false, Number::SmallInt(k).factorial())
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_BINOM => {
+                        if a.len() != 2 {
+                            return false;
+                        }
+
+                        match (&a[0], &a[1]) {
+                            (&Element::Num(_, ref x), &Element::Num(_, Number::SmallInt(k)))
+                                if is_integer(x) && k as i64 <= u32::max_value() as i64 =>
+                            {
+                                if k < 0 {
+                                    Element::Num(false, Number::zero())
+                                } else {
+                                    match x.binomial(k as u32) {
+                                        Some(r) => Element::Num(false, r),
+                                        None => return false,
+                                    }
+                                }
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_MOD | FUNCTION_DIV => {
+                        if a.len() != 2 {
+                            return false;
+                        }
+
+                        // the quotient is rounded towards minus infinity
+                        match (&a[0], &a[1]) {
+                            (&Element::Num(_, ref x), &Element::Num(_, ref y)) => {
+                                match x.div_mod_floor(y) {
+                                    Some((q, r)) => {
+                                        Element::Num(false, if *n == FUNCTION_DIV { q } else { r })
+                                    }
+                                    None => return false,
+                                }
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_ABS | FUNCTION_SIGN => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        match a[0] {
+                            Element::Num(_, Number::Complex(..)) => return false,
+                            Element::Num(_, ref x) if *n == FUNCTION_ABS => {
+                                Element::Num(false, x.abs())
+                            }
+                            Element::Num(_, ref x) => {
+                                let sign = if x.is_zero() {
+                                    0
+                                } else if *x < Number::zero() {
+                                    -1
+                                } else {
+                                    1
+                                };
+                                Element::Num(false, Number::SmallInt(sign))
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_MIN | FUNCTION_MAX => {
+                        let mut nums = Vec::with_capacity(a.len());
+                        for x in a.iter() {
+                            match *x {
+                                Element::Num(_, Number::Complex(..)) => return false,
+                                Element::Num(_, ref y) => nums.push(y),
+                                _ => return false,
+                            }
+                        }
+
+                        let r = if *n == FUNCTION_MIN {
+                            nums.into_iter().min()
+                        } else {
+                            nums.into_iter().max()
+                        };
+
+                        match r {
+                            Some(r) => Element::Num(false, r.clone()),
+                            None => return false,
+                        }
+                    }
+                    FUNCTION_NUM | FUNCTION_DEN => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        match a[0] {
+                            Element::Num(_, ref x) => match x.numer_denom() {
+                                Some((p, q)) => {
+                                    Element::Num(false, if *n == FUNCTION_NUM { p } else { q })
+                                }
+                                None => return false,
+                            },
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_ISPRIME | FUNCTION_NEXTPRIME | FUNCTION_FACTORINT => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        let x = match a[0] {
+                            Element::Num(_, ref x) => x,
+                            _ => return false,
+                        };
+
+                        match *n {
+                            FUNCTION_ISPRIME => match x.is_prime() {
+                                Some(true) => Element::Num(false, Number::one()),
+                                Some(false) => Element::Num(false, Number::zero()),
+                                None => return false,
+                            },
+                            FUNCTION_NEXTPRIME => match x.next_prime() {
+                                Some(p) => Element::Num(false, p),
+                                None => return false,
+                            },
+                            _ => match x.factor() {
+                                // a list of prime and exponent pairs
+                                Some(factors) => Element::Fn(
+                                    false,
+                                    FUNCTION_LIST,
+                                    factors
+                                        .into_iter()
+                                        .map(|(p, k)| {
+                                            Element::Fn(
+                                                false,
+                                                FUNCTION_LIST,
+                                                vec![
+                                                    Element::Num(false, p),
+                                                    Element::Num(false, k),
+                                                ],
+                                            )
+                                        })
+                                        .collect(),
+                                ),
+                                None => return false,
+                            },
+                        }
+                    }
+                    FUNCTION_POWMOD => {
+                        if a.len() != 3 {
+                            return false;
+                        }
+
+                        match (&a[0], &a[1], &a[2]) {
+                            (
+                                &Element::Num(_, ref x),
+                                &Element::Num(_, ref e),
+                                &Element::Num(_, ref m),
+                            ) => match x.pow_mod(e, m) {
+                                Some(r) => Element::Num(false, r),
+                                None => return false,
+                            },
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_INVMOD => {
+                        if a.len() != 2 {
+                            return false;
+                        }
+
+                        match (&a[0], &a[1]) {
+                            (&Element::Num(_, ref x), &Element::Num(_, ref m)) => {
+                                match x.inv_mod(m) {
+                                    Some(r) => Element::Num(false, r),
+                                    None => return false,
+                                }
+                            }
+                            _ => return false,
+                        }
+                    }
+                    FUNCTION_CONJ => {
+                        if a.len() != 1 {
+                            return false;
+                        }
+
+                        // keep conj_(x) if the argument cannot be conjugated any further
+                        let mut res = a[0].conjugate(var_info);
+                        if let Element::Fn(_, FUNCTION_CONJ, ref args) = res {
+                            if args[..] == a[..] {
+                                return false;
+                            }
+                        }
+                        res.normalize_inplace(var_info);
+                        res
+                    }
+                    FUNCTION_EVAL => {
+                        if a.is_empty() {
+                            return false;
+                        }
+
+                        // the values are given as x=v
+                        let mut values = HashMap::new();
+                        for v in &a[1..] {
+                            if let Element::Comparison(_, ref xv, Ordering::Equal) = *v {
+                                if let (Element::Var(x, ref e), Element::Num(_, ref n)) = **xv {
+                                    if e.is_one() {
+                                        values.insert(x, n.clone());
+                                        continue;
+                                    }
+                                }
+                            }
+                            return false;
+                        }
+
+                        match a[0].evaluate(&values, &|_, _| None, var_info.float_precision) {
+                            Ok(n) => Element::Num(false, n),
+                            Err(_) => return false,
+                        }
+                    }
+                    FUNCTION_SERIES => {
+                        if a.len() != 3 {
+                            return false;
+                        }
+
+                        let x = match a[1] {
+                            Element::Var(x, ref e) if *e == Number::one() => x,
+                            _ => return false,
+                        };
+                        let order = match a[2] {
+                            Element::Num(_, Number::SmallInt(n)) => n,
+                            _ => return false,
+                        };
+
+                        let (num, den) = match Polynomial::from_rational(&a[0]) {
+                            Ok(r) => r,
+                            Err(_) => return false,
+                        };
+
+                        let mut terms = vec![];
+                        for (k, cnum, cden) in rationalpolynomial_series(&num, &den, x, order) {
+                            let c = if cden.poly.is_constant() {
+                                let c = cden.poly.coefficients()[0].clone();
+                                (cnum * (Number::one() / c)).to_expression()
+                            } else {
+                                Element::RationalPolynomialCoefficient(true, Box::new((cnum, cden)))
+                            };
+
+                            let xk = Element::Var(x, Number::SmallInt(k));
+                            match c {
+                                Element::SubExpr(_, ts) => {
+                                    for t in ts {
+                                        terms.push(Element::Term(true, vec![t, xk.clone()]));
+                                    }
+                                }
+                                c => terms.push(Element::Term(true, vec![c, xk])),
+                            }
+                        }
+
+                        let mut res = Element::SubExpr(true, terms);
+                        res.normalize_inplace(var_info);
+                        res
+                    }
+                    FUNCTION_DIFF => {
+                        if a.len() < 2 || a.len() > 4 {
+                            return false;
+                        }
+
+                        let x = match a[1] {
+                            Element::Var(x, ref e) if *e == Number::one() => x,
+                            _ => return false,
+                        };
+
+                        // the optional order of the derivative
+                        let order = if a.len() > 2 {
+                            match a[2] {
+                                Element::Num(_, Number::SmallInt(n)) if n >= 0 => n,
+                                _ => return false,
+                            }
+                        } else {
+                            1
+                        };
+
+                        // the optional function that represents partial derivatives
+                        let d = if a.len() > 3 {
+                            match a[3] {
+                                Element::Var(d, ref e) if *e == Number::one() => d,
+                                _ => return false,
+                            }
+                        } else {
+                            FUNCTION_DER
+                        };
+
+                        let mut res = a[0].clone();
+                        for _ in 0..order {
+                            match res.derivative(x, d) {
+                                Some(mut r) => {
+                                    r.normalize_inplace(var_info);
+                                    res = r;
+                                }
+                                None => return false,
+                            }
+                        }
+                        res
+                    }
                     nn => {
                         // process custom functions
                         if let Some((argvar, e)) = var_info.user_functions.get(&nn) {
@@ -227,10 +630,349 @@ impl Element {
         true
     }
 
+    /// Check if the variable `x` may occur in the element.
+    pub fn depends_on(&self, x: VarName) -> bool {
+        match *self {
+            Element::Num(..) => false,
+            Element::Var(ref y, _) => *y == x,
+            Element::Fn(_, _, ref args) => args.iter().any(|a| a.depends_on(x)),
+            Element::Pow(_, ref be) => be.0.depends_on(x) || be.1.depends_on(x),
+            Element::Term(_, ref ts) | Element::SubExpr(_, ref ts) => {
+                ts.iter().any(|t| t.depends_on(x))
+            }
+            _ => true,
+        }
+    }
+
+    /// Convert all exact numbers in the element to floats with a precision
+    /// of `prec` bits. The exponents of variables are kept exact.
+    pub fn to_float(&mut self, prec: u32) {
+        match *self {
+            Element::Num(ref mut dirty, ref mut n) => {
+                *dirty = true;
+                *n = n.to_float(prec);
+            }
+            Element::Pow(ref mut dirty, ref mut be) => {
+                *dirty = true;
+                be.0.to_float(prec);
+            }
+            Element::Fn(ref mut dirty, _, ref mut args)
+            | Element::Term(ref mut dirty, ref mut args)
+            | Element::SubExpr(ref mut dirty, ref mut args) => {
+                *dirty = true;
+                for a in args {
+                    a.to_float(prec);
+                }
+            }
+            Element::RationalPolynomialCoefficient(..) => {
+                let (num, den) = match mem::replace(self, Element::default()) {
+                    Element::RationalPolynomialCoefficient(_, p) => *p,
+                    _ => unreachable!(),
+                };
+                let mut e = Element::Term(
+                    true,
+                    vec![
+                        num.to_expression(),
+                        Element::Pow(
+                            true,
+                            Box::new((den.to_expression(), Element::Num(false, -Number::one()))),
+                        ),
+                    ],
+                );
+                e.to_float(prec);
+                *self = e;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns true if the element is known to be real. This is the case
+    /// for real numbers, and for symbols and functions with the `real`
+    /// attribute raised to integer powers.
+    pub fn is_real(&self, var_info: &GlobalVarInfo) -> bool {
+        match *self {
+            Element::Num(_, Number::Complex(..)) => false,
+            Element::Num(..) => true,
+            Element::Var(ref x, ref e) => is_real_name(*x, var_info) && is_integer(e),
+            Element::Fn(_, ref name, ref args) => {
+                is_real_name(*name, var_info) && args.iter().all(|a| a.is_real(var_info))
+            }
+            Element::Pow(_, ref be) => match be.1 {
+                Element::Num(_, ref e) if is_integer(e) => be.0.is_real(var_info),
+                _ => false,
+            },
+            Element::Term(_, ref ts) | Element::SubExpr(_, ref ts) => {
+                ts.iter().all(|t| t.is_real(var_info))
+            }
+            Element::RationalPolynomialCoefficient(_, ref p) => {
+                p.0.clone().to_expression().is_real(var_info)
+                    && p.1.clone().to_expression().is_real(var_info)
+            }
+            _ => false,
+        }
+    }
+
+    /// Take the complex conjugate of the element. Symbols that are not real
+    /// are conjugated as `conj_(x)`. A function with the `real` attribute
+    /// is conjugated by conjugating its arguments.
+    pub fn conjugate(&self, var_info: &GlobalVarInfo) -> Element {
+        let wrap = |e: &Element| Element::Fn(true, FUNCTION_CONJ, vec![e.clone()]);
+
+        match *self {
+            Element::Num(_, ref n) => Element::Num(true, n.conj()),
+            Element::Var(x, ref e) => {
+                if is_real_name(x, var_info) && is_integer(e) {
+                    self.clone()
+                } else if is_integer(e) {
+                    // conj_(x^n) = conj_(x)^n
+                    let c = wrap(&Element::Var(x, Number::one()));
+                    if e.is_one() {
+                        c
+                    } else {
+                        Element::Pow(true, Box::new((c, Element::Num(false, e.clone()))))
+                    }
+                } else {
+                    wrap(self)
+                }
+            }
+            Element::Fn(_, FUNCTION_CONJ, ref args) if args.len() == 1 => args[0].clone(),
+            Element::Fn(_, name, ref args) if is_real_name(name, var_info) => Element::Fn(
+                true,
+                name,
+                args.iter().map(|a| a.conjugate(var_info)).collect(),
+            ),
+            Element::Pow(_, ref be) => match be.1 {
+                Element::Num(_, ref e) if is_integer(e) => {
+                    Element::Pow(true, Box::new((be.0.conjugate(var_info), be.1.clone())))
+                }
+                _ if self.is_real(var_info) => self.clone(),
+                _ => wrap(self),
+            },
+            Element::Term(_, ref ts) => {
+                Element::Term(true, ts.iter().map(|t| t.conjugate(var_info)).collect())
+            }
+            Element::SubExpr(_, ref ts) => {
+                Element::SubExpr(true, ts.iter().map(|t| t.conjugate(var_info)).collect())
+            }
+            _ if self.is_real(var_info) => self.clone(),
+            _ => wrap(self),
+        }
+    }
+
+    /// Differentiate a normalized element with respect to the variable `x`.
+    /// The partial derivative of an unknown function `f(a1,...,an)` with respect
+    /// to its `i`th argument is represented as `d(f(a1,...,an),i)`. Higher partial
+    /// derivatives add more indices, in ascending order.
+    /// Returns `None` if the element cannot be differentiated.
+    pub fn derivative(&self, x: VarName, d: VarName) -> Option<Element> {
+        if !self.depends_on(x) {
+            return Some(Element::Num(false, Number::zero()));
+        }
+
+        Some(match *self {
+            Element::Var(_, ref e) => Element::Term(
+                true,
+                vec![
+                    Element::Num(false, e.clone()),
+                    Element::Var(x, e.clone() - Number::one()),
+                ],
+            ),
+            Element::Pow(_, ref be) => {
+                let (ref b, ref e) = **be;
+                if e.depends_on(x) {
+                    // b^e*(e'*log_(b)+e*b'/b)
+                    let mut terms = vec![Element::Term(
+                        true,
+                        vec![
+                            e.derivative(x, d)?,
+                            Element::Fn(true, FUNCTION_LOG, vec![b.clone()]),
+                        ],
+                    )];
+                    if b.depends_on(x) {
+                        terms.push(Element::Term(
+                            true,
+                            vec![
+                                e.clone(),
+                                b.derivative(x, d)?,
+                                Element::Pow(
+                                    true,
+                                    Box::new((
+                                        b.clone(),
+                                        Element::Num(false, Number::SmallInt(-1)),
+                                    )),
+                                ),
+                            ],
+                        ));
+                    }
+                    return Some(Element::Term(
+                        true,
+                        vec![self.clone(), Element::SubExpr(true, terms)],
+                    ));
+                }
+
+                // e*b^(e-1)*b'
+                Element::Term(
+                    true,
+                    vec![
+                        e.clone(),
+                        Element::Pow(
+                            true,
+                            Box::new((
+                                b.clone(),
+                                Element::SubExpr(
+                                    true,
+                                    vec![e.clone(), Element::Num(false, Number::SmallInt(-1))],
+                                ),
+                            )),
+                        ),
+                        b.derivative(x, d)?,
+                    ],
+                )
+            }
+            Element::Term(_, ref fs) => {
+                let mut terms = vec![];
+                for (i, f) in fs.iter().enumerate() {
+                    if f.depends_on(x) {
+                        let mut t = fs.clone();
+                        t[i] = f.derivative(x, d)?;
+                        terms.push(Element::Term(true, t));
+                    }
+                }
+                Element::SubExpr(true, terms)
+            }
+            Element::SubExpr(_, ref ts) => {
+                let mut terms = Vec::with_capacity(ts.len());
+                for t in ts {
+                    terms.push(t.derivative(x, d)?);
+                }
+                Element::SubExpr(true, terms)
+            }
+            Element::RationalPolynomialCoefficient(_, ref p) => {
+                let (mut num, mut den) = (p.0.clone(), p.1.clone());
+                if rationalpolynomial_derivative(&mut num, &mut den, x) {
+                    Element::Num(false, Number::zero())
+                } else {
+                    Element::RationalPolynomialCoefficient(true, Box::new((num, den)))
+                }
+            }
+            Element::Fn(_, n, ref args) => {
+                // chain rule for the elementary functions: f(a)' = f'(a)*a'
+                if args.len() == 1 {
+                    let a = &args[0];
+                    let df = match n {
+                        FUNCTION_EXP => Some(self.clone()),
+                        FUNCTION_LOG => Some(Element::Pow(
+                            true,
+                            Box::new((a.clone(), Element::Num(false, Number::SmallInt(-1)))),
+                        )),
+                        FUNCTION_SIN => Some(Element::Fn(true, FUNCTION_COS, vec![a.clone()])),
+                        FUNCTION_COS => Some(Element::Term(
+                            true,
+                            vec![
+                                Element::Fn(true, FUNCTION_SIN, vec![a.clone()]),
+                                Element::Num(false, Number::SmallInt(-1)),
+                            ],
+                        )),
+                        FUNCTION_SQRT => Some(Element::Term(
+                            true,
+                            vec![
+                                Element::Pow(
+                                    true,
+                                    Box::new((
+                                        self.clone(),
+                                        Element::Num(false, Number::SmallInt(-1)),
+                                    )),
+                                ),
+                                Element::Num(false, Number::SmallRat(1, 2)),
+                            ],
+                        )),
+                        _ => None,
+                    };
+
+                    if let Some(df) = df {
+                        return Some(Element::Term(true, vec![df, a.derivative(x, d)?]));
+                    }
+                }
+
+                // a partial derivative d(f(...),i,...) gets an extra index
+                let (f, indices) = if n == d && !args.is_empty() {
+                    (&args[0], &args[1..])
+                } else {
+                    (self, &[][..])
+                };
+
+                let fargs = match *f {
+                    Element::Fn(_, _, ref fargs) => fargs,
+                    _ => return None,
+                };
+
+                let mut terms = vec![];
+                for (i, a) in fargs.iter().enumerate() {
+                    if !a.depends_on(x) {
+                        continue;
+                    }
+
+                    let i = i as isize + 1;
+                    let pos = indices
+                        .iter()
+                        .take_while(|e| match **e {
+                            Element::Num(_, Number::SmallInt(j)) => j <= i,
+                            _ => true,
+                        })
+                        .count();
+
+                    let mut dargs = Vec::with_capacity(indices.len() + 2);
+                    dargs.push(f.clone());
+                    dargs.extend_from_slice(&indices[..pos]);
+                    dargs.push(Element::Num(false, Number::SmallInt(i)));
+                    dargs.extend_from_slice(&indices[pos..]);
+
+                    terms.push(Element::Term(
+                        true,
+                        vec![Element::Fn(true, d, dargs), a.derivative(x, d)?],
+                    ));
+                }
+                Element::SubExpr(true, terms)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Truncate a normalized term or expression, seen as a Laurent series in `x`,
+    /// beyond the power `order`. Only terms in which a lower bound of the power
+    /// of `x` is known are truncated, so `x` should not occur in functions or
+    /// in negative powers. This is only valid at the ground level.
+    /// Returns `true` if the result is 0.
+    pub fn truncate_series(&mut self, x: VarName, order: isize) -> bool {
+        match *self {
+            Element::SubExpr(_, ref mut ts) => {
+                for mut t in mem::replace(ts, vec![]) {
+                    if !t.truncate_series(x, order) {
+                        ts.push(t);
+                    }
+                }
+
+                if ts.len() != 1 {
+                    return ts.is_empty();
+                }
+            }
+            Element::Term(_, ref mut fs) => return truncate_series(fs, x, order),
+            _ => return truncate_series(slice::from_mut(self), x, order),
+        }
+
+        // downgrade an expression with a single term
+        let t = match *self {
+            Element::SubExpr(_, ref mut ts) => ts.pop().unwrap(),
+            _ => unreachable!(),
+        };
+        *self = t;
+        false
+    }
+
     #[inline]
     pub fn should_normalize(&self) -> bool {
         match *self {
-            Element::Var(_, ref e) => e.is_zero(),
+            Element::Var(x, ref e) => e.is_zero() || x == SYMBOL_I,
             Element::Num(dirty, ..)
             | Element::Comparison(dirty, ..)
             | Element::Pow(dirty, ..)
@@ -256,46 +998,61 @@ impl Element {
                     // normalize factors and flatten
                     // TODO: check for 0 here
                     let mut restructure = false;
-                    let mut newlen = ts.len();
                     for x in ts.iter_mut() {
                         if x.should_normalize() {
                             changed |= x.normalize_inplace(var_info);
                         }
-                        if let Element::Term(_, ref a) = *x {
-                            newlen += a.len();
+                        if let Element::Term(..) = *x {
                             restructure = true;
                             changed = true;
                         }
                     }
 
-                    // flatten the term
-                    if restructure {
-                        let mut tmp = Vec::with_capacity(newlen);
-                        for x in ts.drain(..) {
-                            match x {
-                                Element::Term(_, tss) => tmp.extend(tss),
-                                _ => tmp.push(x),
+                    // merging factors can change their type, for example
+                    // sqrt_(x)*sqrt_(x) = x, so then we sort and merge again
+                    let mut resort = true;
+                    while resort {
+                        resort = false;
+
+                        // flatten the term
+                        if restructure {
+                            let mut tmp = Vec::with_capacity(ts.len());
+                            for x in ts.drain(..) {
+                                match x {
+                                    Element::Term(_, tss) => tmp.extend(tss),
+                                    _ => tmp.push(x),
+                                }
                             }
+                            *ts = tmp;
                         }
-                        *ts = tmp;
-                    }
 
-                    ts.sort_unstable_by(|l, r| l.partial_cmp_factor(r, var_info).unwrap());
+                        ts.sort_unstable_by(|l, r| l.partial_cmp_factor(r, var_info).unwrap());
 
-                    // now merge pows: x^a*x^b = x^(a*b)
-                    // x*x^a and x*x, all should be side by side now
-                    let mut lastindex = 0;
+                        // now merge pows: x^a*x^b = x^(a*b)
+                        // x*x^a and x*x, all should be side by side now
+                        let mut lastindex = 0;
 
-                    for i in 1..ts.len() {
-                        let (a, b) = ts.split_at_mut(i);
-                        if !merge_factors(&mut a[lastindex], &mut b[0], var_info) {
-                            if lastindex + 1 < i {
-                                a[lastindex + 1] = mem::replace(&mut b[0], Element::default());
+                        for i in 1..ts.len() {
+                            let (a, b) = ts.split_at_mut(i);
+                            let kind = mem::discriminant(&a[lastindex]);
+                            if !merge_factors(&mut a[lastindex], &mut b[0], var_info) {
+                                if lastindex + 1 < i {
+                                    a[lastindex + 1] = mem::replace(&mut b[0], Element::default());
+                                }
+                                lastindex += 1;
+                            } else if mem::discriminant(&a[lastindex]) != kind {
+                                resort = true;
                             }
-                            lastindex += 1;
+                        }
+                        ts.truncate(lastindex + 1);
+
+                        if resort {
+                            restructure = ts.iter().any(|x| match *x {
+                                Element::Term(..) => true,
+                                _ => false,
+                            });
                         }
                     }
-                    ts.truncate(lastindex + 1);
 
                     if let Some(Element::Num(..)) = ts.last() {
                         if let Some(Element::Num(_, num)) = ts.last().cloned() {
@@ -339,6 +1096,14 @@ impl Element {
                     *self = Element::Num(false, Number::one());
                     return true;
                 }
+
+                // i_^n is a number, using i_^2 = -1
+                if let Element::Var(SYMBOL_I, Number::SmallInt(n)) = *self {
+                    let i = Number::complex(Number::zero(), Number::one());
+                    let r = i.pow((((n % 4) + 4) % 4) as u32);
+                    *self = Element::Num(false, r);
+                    return true;
+                }
             }
             Element::Dollar(ref _d, ref mut inds) => {
                 // note that the dollar variable cannot be applied here, since
@@ -375,6 +1140,11 @@ impl Element {
                                 // exponent is a positive integer
                                 // check if some simplification can be made
 
+                                if let Some(mut r) = elementary_pow(b, *n) {
+                                    r.normalize_inplace(var_info);
+                                    break r;
+                                }
+
                                 if let Element::Num(_, ref mut num) = *b {
                                     // base is a rational number: (p/q)^n = p^n/q^n
                                     break Element::Num(false, num.clone().pow(*n as u32));
@@ -390,28 +1160,17 @@ impl Element {
                                     break mem::replace(b, Element::default());
                                 }
 
-                                // simplify x^a^b = x^(a*b) where x is a variable
-                                // and a and b are positive integers
-                                // In general, this may be mathematically wrong, e.g.,
-                                //   for x = (-1+i), a = 2, b = 3/2,
-                                //   (x^a)^b = - x^(a*b).
-                                // We need to add more detailed conditions for such a reduction.
-                                let mut newbase = Element::default();
-                                if let Element::Pow(_, ref mut be1) = *b {
-                                    if let Element::Var(_, Number::SmallInt(ee1)) = be1.0 {
-                                        if let Element::Num(_, Number::SmallInt(n1)) = be1.1 {
-                                            newbase = be1.0.clone();
-                                            *n *= n1 * ee1;
-                                            changed = true;
-                                        }
-                                    }
-                                }
-
-                                if newbase != Element::default() {
-                                    *b = newbase;
+                                if let Some(mut r) = power_of_power(b, *n) {
+                                    r.normalize_inplace(var_info);
+                                    break r;
                                 }
                             }
                             Element::Num(_, Number::SmallInt(ref n)) if *n < 0 => {
+                                if let Some(mut r) = elementary_pow(b, *n) {
+                                    r.normalize_inplace(var_info);
+                                    break r;
+                                }
+
                                 if let Element::Num(_, ref mut num) = *b {
                                     // base is a rational number: (p/q)^(-n) = q^n/p^n
                                     break Element::Num(
@@ -429,19 +1188,46 @@ impl Element {
                                 if downgrade {
                                     break mem::replace(b, Element::default());
                                 }
+
+                                if let Some(mut r) = power_of_power(b, *n) {
+                                    r.normalize_inplace(var_info);
+                                    break r;
+                                }
                             }
                             Element::Num(_, ref mut n) => {
+                                if let Element::Num(_, ref num) = *b {
+                                    // a float base or exponent gives a float
+                                    if let Some(r) = num.float_pow(n) {
+                                        break Element::Num(false, r);
+                                    }
+
+                                    if let Some(mut r) =
+                                        rational_number_power(num, n, var_info.float_precision)
+                                    {
+                                        r.normalize_inplace(var_info);
+                                        break r;
+                                    }
+                                }
+
                                 // downgrade to variable with power
+                                // (x^2)^(1/2) is not x, so only x^1 is downgraded
                                 let mut downgrade = false;
                                 if let Element::Var(_, ref mut mutexp) = b {
-                                    downgrade = true;
-                                    *mutexp *= mem::replace(n, Number::zero());
+                                    if mutexp.is_one() {
+                                        downgrade = true;
+                                        *mutexp = mem::replace(n, Number::zero());
+                                    }
                                 }
                                 if downgrade {
                                     break mem::replace(b, Element::default());
                                 }
                             }
-                            _ => {}
+                            _ => {
+                                if let Some(mut r) = split_number_power(b, e, var_info) {
+                                    r.normalize_inplace(var_info);
+                                    break r;
+                                }
+                            }
                         };
                         return changed;
                     }
@@ -680,6 +1466,136 @@ impl Element {
     }
 }
 
+/// Simplify `b^n` for an integer `n` if `b` is an elementary function:
+/// `exp_(a)^n = exp_(n*a)` and `sqrt_(a)^n = a^(n/2)*sqrt_(a)^(n%2)`.
+fn elementary_pow(b: &Element, n: isize) -> Option<Element> {
+    match *b {
+        Element::Fn(_, FUNCTION_EXP, ref args) if args.len() == 1 => Some(Element::Fn(
+            true,
+            FUNCTION_EXP,
+            vec![Element::Term(
+                true,
+                vec![args[0].clone(), Element::Num(false, Number::SmallInt(n))],
+            )],
+        )),
+        Element::Fn(_, FUNCTION_SQRT, ref args) if args.len() == 1 && (n >= 2 || n <= -2) => {
+            let r = ((n % 2) + 2) % 2;
+            let mut fs = vec![Element::Pow(
+                true,
+                Box::new((
+                    args[0].clone(),
+                    Element::Num(false, Number::SmallInt((n - r) / 2)),
+                )),
+            )];
+            if r == 1 {
+                fs.push(b.clone());
+            }
+            Some(Element::Term(true, fs))
+        }
+        _ => None,
+    }
+}
+
+/// Simplify `(b^a)^n = b^(a*n)`. This only holds in general for an integer `n`,
+/// for example `((-1)^2)^(1/2) = 1` but `(-1)^(2*1/2) = -1`.
+fn power_of_power(b: &mut Element, n: isize) -> Option<Element> {
+    match *b {
+        Element::Pow(_, ref mut be) => {
+            let (b1, a) = mem::replace(&mut **be, (Element::default(), Element::default()));
+            Some(Element::Pow(
+                true,
+                Box::new((
+                    b1,
+                    Element::Term(true, vec![a, Element::Num(false, Number::SmallInt(n))]),
+                )),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Pull the integer part out of the exponent of a number: `2^(n+1) = 2*2^n`.
+fn split_number_power(b: &Element, e: &mut Element, var_info: &GlobalVarInfo) -> Option<Element> {
+    let num = match *b {
+        Element::Num(_, ref num) if !num.is_zero() => num,
+        _ => return None,
+    };
+
+    let p = match *e {
+        Element::SubExpr(ref mut dirty, ref mut ts) => {
+            let i = ts.iter().position(|t| match *t {
+                Element::Num(_, Number::SmallInt(_)) => true,
+                _ => false,
+            })?;
+
+            // only change the exponent if the power can be computed
+            let p = match ts[i] {
+                Element::Num(_, ref k) => num.pow_prec(k, var_info.float_precision)?,
+                _ => unreachable!(),
+            };
+            *dirty = true;
+            ts.remove(i);
+            p
+        }
+        _ => return None,
+    };
+
+    Some(Element::Term(
+        true,
+        vec![
+            Element::Pow(true, Box::new((b.clone(), e.clone()))),
+            Element::Num(false, p),
+        ],
+    ))
+}
+
+/// Simplify a rational power of a number. Perfect powers are evaluated,
+/// `4^(3/2) = 8`, and otherwise the integer part of the exponent is pulled
+/// out, `2^(3/2) = 2*2^(1/2)`, so that the remaining exponent is between 0 and 1.
+fn rational_number_power(b: &Number, e: &Number, prec: u32) -> Option<Element> {
+    match b.pow_prec(e, prec) {
+        Some(Number::Float(..)) | None => {}
+        Some(r) => return Some(Element::Num(false, r)),
+    }
+
+    let (k, r) = e.floor_fract()?;
+    if k.is_zero() || b.is_zero() {
+        return None;
+    }
+
+    // an exact power should not become a float
+    let p = match b.pow_prec(&k, prec)? {
+        Number::Float(..) => return None,
+        p => p,
+    };
+
+    Some(Element::Term(
+        true,
+        vec![
+            Element::Pow(
+                true,
+                Box::new((Element::Num(false, b.clone()), Element::Num(false, r))),
+            ),
+            Element::Num(false, p),
+        ],
+    ))
+}
+
+/// Returns true if the symbol or function `x` has the `real` attribute.
+fn is_real_name(x: VarName, var_info: &GlobalVarInfo) -> bool {
+    match var_info.func_attribs.get(&x) {
+        Some(attribs) => attribs.contains(&FunctionAttributes::Real),
+        None => false,
+    }
+}
+
+fn is_integer(n: &Number) -> bool {
+    match *n {
+        Number::SmallInt(_) | Number::BigInt(_) => true,
+        _ => false,
+    }
+}
+
 /// Merge factor `sec` into `first` if possible. Returns true if merged.
 pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVarInfo) -> bool {
     let mut changed = false;
@@ -708,8 +1624,18 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
             return true;
         }
 
-        // TODO: merge x^n with x^(...) ?
-        return false;
+        // x^n*x^a, where the exponent a is not a number, is merged below
+        let mut swap = false;
+        if let Element::Var(n1, _) = *first {
+            if let Element::Pow(_, ref be) = *sec {
+                swap = be.0 == Element::Var(n1, Number::one());
+            }
+        }
+
+        if !swap {
+            return false;
+        }
+        mem::swap(first, sec);
     }
 
     let mut swap = false;
@@ -719,7 +1645,16 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
             return true;
         }
 
-        if let Element::RationalPolynomialCoefficient(..) = *sec {
+        if let Element::RationalPolynomialCoefficient(_, ref mut p) = *sec {
+            // floats and complex numbers stay a separate factor in front of the
+            // polyratfun, with their sign moved into it so that opposite terms cancel
+            if !num.is_polynomial_coefficient() {
+                if *num < Number::zero() {
+                    *num = -mem::replace(num, DUMMY_NUM!());
+                    p.0 = -mem::replace(&mut p.0, Polynomial::new());
+                }
+                return false;
+            }
             swap = true;
         }
     }
@@ -746,6 +1681,10 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
                     *den =
                         mem::replace(den, Polynomial::new()) * Number::BigInt(nd.denom().clone());
                 }
+                Number::Float(_) | Number::Complex(_) => {
+                    // the factors are sorted such that the number comes first
+                    unreachable!("{} cannot be merged into a rational polynomial", n)
+                }
             }
 
             return true;
@@ -757,10 +1696,29 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
             let (ref mut num1, ref mut den1) = &mut **p1;
 
             rationalpolynomial_mul(num, den, num1, den1);
+            // the product of non-zero denominators is non-zero in a field
+            algebraic_reduce(num, den, var_info);
             return true;
         }
     }
 
+    // exp_(a)*exp_(b) => exp_(a+b)
+    if let Element::Fn(ref mut dirty, FUNCTION_EXP, ref mut args1) = *first {
+        if let Element::Fn(_, FUNCTION_EXP, ref mut args2) = *sec {
+            if args1.len() == 1 && args2.len() == 1 {
+                let a = Element::SubExpr(true, vec![args1.pop().unwrap(), args2.pop().unwrap()]);
+                args1.push(a);
+                *dirty = true;
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        first.normalize_inplace(var_info);
+        return true;
+    }
+
     // x*x => x^2
     if first == sec {
         *first = Element::Pow(
@@ -774,6 +1732,19 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
         return true;
     }
 
+    // f(x)*f(x)^a => f(x)^a*f(x), so that it is merged below
+    let mut swap = false;
+    if let Element::Pow(_, ref be) = *sec {
+        match *first {
+            // a number base would pull the number out again: 2*2^n
+            Element::Num(..) => {}
+            ref b => swap = be.0 == *b,
+        }
+    }
+    if swap {
+        mem::swap(first, sec);
+    }
+
     // x^a*x^b = x^(a+b)
     if let &mut Element::Pow(ref mut dirty, ref mut be2) = first {
         let (ref mut b2, ref mut e2) = *&mut **be2;
@@ -806,26 +1777,36 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
                 *dirty = true;
                 changed = true;
             }
-        } else if sec == b2 {
-            // e2 should become e2 + 1
-            // avoid borrow checker error
-            let mut addone = true;
-            if let Element::Num(_, ref mut num) = *e2 {
-                *num += Number::one();
-                addone = false;
-            }
-            if addone {
-                *e2 = Element::SubExpr(
-                    true,
-                    vec![
-                        mem::replace(e2, Element::default()),
-                        Element::Num(false, Number::one()),
-                    ],
-                );
-            }
+        } else {
+            // x^a*x^n = x^(a+n)
+            let n = match *sec {
+                Element::Var(n1, ref p1) if *b2 == Element::Var(n1, Number::one()) => {
+                    Some(p1.clone())
+                }
+                // a number base would pull the number out again: 2*2^n
+                Element::Num(..) => None,
+                _ if *sec == *b2 => Some(Number::one()),
+                _ => None,
+            };
+
+            if let Some(n) = n {
+                // e2 should become e2 + n
+                // avoid borrow checker error
+                let mut addn = true;
+                if let Element::Num(_, ref mut num) = *e2 {
+                    *num += n.clone();
+                    addn = false;
+                }
+                if addn {
+                    *e2 = Element::SubExpr(
+                        true,
+                        vec![mem::replace(e2, Element::default()), Element::Num(false, n)],
+                    );
+                }
 
-            *dirty = true;
-            changed = true;
+                *dirty = true;
+                changed = true;
+            }
         }
     };
 
@@ -835,8 +1816,77 @@ pub fn merge_factors(first: &mut Element, sec: &mut Element, var_info: &GlobalVa
     changed
 }
 
+/// Reduce a rational polynomial with coefficients in the algebraic number field
+/// set by the `algebraic` statement, if any.
+/// The function returns false iff the denominator is zero in the field.
+fn algebraic_reduce(num: &mut Polynomial, den: &mut Polynomial, var_info: &GlobalVarInfo) -> bool {
+    match var_info.algebraic {
+        Some((x, ref minimal_polynomial)) => {
+            rationalpolynomial_algebraic(num, den, x, minimal_polynomial)
+        }
+        None => true,
+    }
+}
+
+/// Get a lower bound of the power of `x` in a normalized element, if it is known.
+fn valuation(e: &Element, x: VarName) -> Option<isize> {
+    match *e {
+        Element::Var(y, Number::SmallInt(k)) if y == x => Some(k),
+        Element::Num(..) => Some(0),
+        Element::RationalPolynomialCoefficient(_, ref p) => {
+            Some(p.0.valuation(x) as isize - p.1.valuation(x) as isize)
+        }
+        Element::Term(_, ref fs) => {
+            let mut v = 0;
+            for f in fs {
+                v += valuation(f, x)?;
+            }
+            Some(v)
+        }
+        Element::SubExpr(_, ref ts) => {
+            let mut v = None;
+            for t in ts {
+                let vt = valuation(t, x)?;
+                v = Some(v.map_or(vt, |v: isize| v.min(vt)));
+            }
+            v
+        }
+        Element::Pow(_, ref be) => match be.1 {
+            Element::Num(_, Number::SmallInt(n)) if n > 0 => Some(valuation(&be.0, x)? * n),
+            _ if !e.depends_on(x) => Some(0),
+            _ => None,
+        },
+        _ if !e.depends_on(x) => Some(0),
+        _ => None,
+    }
+}
+
+/// Truncate a term with factors `factors` beyond the power `order` of `x`.
+/// Returns `true` if the resulting term is 0.
+fn truncate_series(factors: &mut [Element], x: VarName, order: isize) -> bool {
+    // the lowest power of x in all factors except for the coefficient
+    let mut power = 0;
+    for f in factors.iter() {
+        if let Element::RationalPolynomialCoefficient(..) = *f {
+            continue;
+        }
+        match valuation(f, x) {
+            Some(v) => power += v,
+            None => return false,
+        }
+    }
+
+    match factors.last_mut() {
+        Some(&mut Element::RationalPolynomialCoefficient(_, ref mut p)) => {
+            let (ref mut num, ref mut den) = **p;
+            rationalpolynomial_truncate(num, den, x, order - power)
+        }
+        _ => power > order,
+    }
+}
+
 /// Merge `sec` into `first`. Returns `true` if the resulting term is 0.
-pub fn merge_terms(mut first: &mut Element, sec: &mut Element, _var_info: &GlobalVarInfo) -> bool {
+pub fn merge_terms(mut first: &mut Element, sec: &mut Element, var_info: &GlobalVarInfo) -> bool {
     // make sure a term is always first
     if let Element::Term(..) = first {
     } else {
@@ -883,6 +1933,10 @@ pub fn merge_terms(mut first: &mut Element, sec: &mut Element, _var_info: &Globa
                     if rationalpolynomial_add(num, den, num1, den1) {
                         return true;
                     }
+                    algebraic_reduce(num, den, var_info);
+                    if num.is_zero() {
+                        return true;
+                    }
                 }
                 (
                     Element::Num(_, ref mut n),
@@ -965,6 +2019,10 @@ pub fn merge_terms(mut first: &mut Element, sec: &mut Element, _var_info: &Globa
             if rationalpolynomial_add(num, den, num1, den1) {
                 return true;
             }
+            algebraic_reduce(num, den, var_info);
+            if num.is_zero() {
+                return true;
+            }
         }
         (ref a1, ref mut a2) if a1 == *a2 => {
             ***a2 = Element::Term(
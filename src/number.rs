@@ -1,12 +1,14 @@
 use num_traits;
 use num_traits::{checked_pow, Inv, One, Zero};
+use rug::integer::IsPrime;
 use rug::ops::Pow;
-use rug::{Integer, Rational};
+use rug::{Float, Integer, Rational};
 use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::mem;
 use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Rem, Sub};
-use tools::GCD;
+use tools::{factor_integer, GCD};
 
 #[macro_export]
 macro_rules! DUMMY_NUM {
@@ -17,24 +19,36 @@ macro_rules! DUMMY_NUM {
 
 const DOWNGRADE_LIMIT: isize = 4294967296; // if a bigint is smaller than this number, we downgrade
 
+/// The default precision in bits of floating-point numbers.
+pub const DEFAULT_FLOAT_PRECISION: u32 = 53;
+
+/// The maximum number of bits of an exact power, such as `2^n`, that is evaluated.
+pub const MAX_POWER_BITS: u64 = 1 << 24;
+
 /// A number is either a small number consisting of machine sized
-/// integers or a big number, using GMP.
+/// integers or a big number, using GMP. Approximate numbers are
+/// stored as floats with arbitrary precision, using MPFR. Complex
+/// numbers are stored as a pair of a real and an imaginary part.
 ///
 /// The mathematical operations on a number automatically upgrade
-/// and downgrade to bigint/smallint etc.
+/// and downgrade to bigint/smallint etc. If one of the operands
+/// is a float, the result is a float as well. A complex number
+/// with a zero imaginary part is downgraded to a real number.
 /// TODO: hash should take into account that some bigints can be equal to smallint?
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone)]
 pub enum Number {
     SmallInt(isize),
     BigInt(Integer),
     SmallRat(isize, isize),
     BigRat(Box<Rational>),
+    Float(Box<Float>),
+    Complex(Box<(Number, Number)>),
 }
 
 impl Number {
     pub fn normalize_inplace(&mut self) -> bool {
         *self = match *self {
-            Number::SmallInt(_) => return false,
+            Number::SmallInt(_) | Number::Float(_) => return false,
             Number::SmallRat(ref mut n, ref mut d) => {
                 if *d == 1 {
                     Number::SmallInt(*n)
@@ -68,6 +82,13 @@ impl Number {
                     Number::BigInt(mem::replace(&mut **r, Rational::new()).into_numer_denom().0)
                 }
             }
+            Number::Complex(ref mut c) => {
+                let changed = c.0.normalize_inplace() | c.1.normalize_inplace();
+                if !c.1.is_zero() {
+                    return changed;
+                }
+                mem::replace(&mut c.0, Number::zero())
+            }
         };
         true
     }
@@ -111,7 +132,342 @@ impl Number {
             Number::SmallRat(..) => panic!("Cannot take factorial of fraction"),
             Number::BigInt(..) => unimplemented!(), // rug does not support it?
             Number::BigRat(..) => panic!("Cannot take factorial of fraction"),
+            Number::Float(..) => panic!("Cannot take factorial of float"),
+            Number::Complex(..) => panic!("Cannot take factorial of complex number"),
+        }
+    }
+
+    /// Convert the number to the closest floating point number.
+    pub fn to_f64(&self) -> f64 {
+        match *self {
+            Number::SmallInt(i) => i as f64,
+            Number::SmallRat(n, d) => n as f64 / d as f64,
+            Number::BigInt(ref i) => i.to_f64(),
+            Number::BigRat(ref r) => r.to_f64(),
+            Number::Float(ref f) => f.to_f64(),
+            Number::Complex(..) => panic!("Cannot convert complex number {} to f64", self),
+        }
+    }
+
+    /// Create the complex number `re + im*i_`. If the imaginary part
+    /// is zero, the real part is returned.
+    pub fn complex(re: Number, im: Number) -> Number {
+        if im.is_zero() {
+            re
+        } else {
+            Number::Complex(Box::new((re, im)))
+        }
+    }
+
+    /// Returns the complex conjugate of the number.
+    pub fn conj(&self) -> Number {
+        match *self {
+            Number::Complex(ref c) => Number::Complex(Box::new((c.0.clone(), -c.1.clone()))),
+            _ => self.clone(),
+        }
+    }
+
+    /// Split the number into its real and imaginary part.
+    fn parts(self) -> (Number, Number) {
+        match self {
+            Number::Complex(c) => *c,
+            x => (x, Number::zero()),
+        }
+    }
+
+    /// Convert the number to a float with a precision of `prec` bits.
+    pub fn to_float(&self, prec: u32) -> Number {
+        match *self {
+            Number::Complex(ref c) => {
+                Number::Complex(Box::new((c.0.to_float(prec), c.1.to_float(prec))))
+            }
+            _ => Number::Float(Box::new(self.float_value(prec))),
+        }
+    }
+
+    /// The number of bits of the largest integer in an exact number, or `None`
+    /// for floats. A power `x^k` has about `k` times as many bits.
+    fn exact_bits(&self) -> Option<u64> {
+        match *self {
+            Number::SmallInt(i) => Some(u64::from(Integer::from(i).significant_bits())),
+            Number::BigInt(ref i) => Some(u64::from(i.significant_bits())),
+            Number::SmallRat(..) | Number::BigRat(..) => {
+                let (n, d) = self.rational_value().into_numer_denom();
+                Some(u64::from(n.significant_bits().max(d.significant_bits())))
+            }
+            Number::Float(..) => None,
+            Number::Complex(ref c) => match (c.0.exact_bits()?, c.1.exact_bits()?) {
+                (0, b) | (b, 0) => Some(b),
+                (a, b) => Some(a.max(b) + 1),
+            },
+        }
+    }
+
+    /// Returns true if the number can be merged into the coefficients of a
+    /// rational polynomial. Floats and complex numbers are kept as a separate
+    /// factor instead.
+    pub fn is_polynomial_coefficient(&self) -> bool {
+        match *self {
+            Number::Float(..) | Number::Complex(..) => false,
+            _ => true,
+        }
+    }
+
+    fn is_nan(&self) -> bool {
+        match *self {
+            Number::Float(ref f) => f.is_nan(),
+            Number::Complex(ref c) => c.0.is_nan() || c.1.is_nan(),
+            _ => false,
+        }
+    }
+
+    fn float_value(&self, prec: u32) -> Float {
+        match *self {
+            Number::SmallInt(i) => Float::with_val(prec, &Integer::from(i)),
+            Number::BigInt(ref i) => Float::with_val(prec, i),
+            Number::SmallRat(n, d) => Float::with_val(prec, &Rational::from((n, d))),
+            Number::BigRat(ref r) => Float::with_val(prec, &**r),
+            Number::Float(ref f) => Float::with_val(prec, &**f),
+            Number::Complex(..) => panic!("Complex number {} has no real value", self),
+        }
+    }
+
+    fn rational_value(&self) -> Rational {
+        match *self {
+            Number::SmallInt(i) => Rational::from(i),
+            Number::BigInt(ref i) => Rational::from(i),
+            Number::SmallRat(n, d) => Rational::from((n, d)),
+            Number::BigRat(ref r) => (**r).clone(),
+            Number::Float(ref f) => f
+                .to_rational()
+                .expect("Cannot convert an infinite or undefined float"),
+            Number::Complex(..) => panic!("Complex number {} has no real value", self),
+        }
+    }
+
+    /// Convert a float and another number to floats of the same precision,
+    /// which is the highest precision of the two.
+    fn float_operands(f: Box<Float>, x: Number) -> (Float, Float) {
+        let prec = match x {
+            Number::Float(ref g) => f.prec().max(g.prec()),
+            _ => f.prec(),
+        };
+        let mut f = *f;
+        f.set_prec(prec);
+        (f, x.float_value(prec))
+    }
+
+    /// Apply the floating-point function `f` to the number. The precision is
+    /// that of the number if it is a float and `prec` bits otherwise.
+    /// Returns `None` if the result is undefined or not real.
+    pub fn apply_float(&self, prec: u32, f: fn(Float) -> Float) -> Option<Number> {
+        let prec = match *self {
+            Number::Float(ref g) => g.prec(),
+            Number::Complex(..) => return None,
+            _ => prec,
+        };
+
+        let r = f(self.float_value(prec));
+        if r.is_finite() {
+            Some(Number::Float(Box::new(r)))
+        } else {
+            None
+        }
+    }
+
+    /// Raise the number to the power `e`, where one of the two is a float.
+    /// Returns `None` if both numbers are exact, or if the result is not real.
+    pub fn float_pow(&self, e: &Number) -> Option<Number> {
+        let prec = match (self, e) {
+            (&Number::Complex(..), _) | (_, &Number::Complex(..)) => return None,
+            (&Number::Float(ref f1), &Number::Float(ref f2)) => f1.prec().max(f2.prec()),
+            (&Number::Float(ref f), _) | (_, &Number::Float(ref f)) => f.prec(),
+            _ => return None,
+        };
+
+        if *self < Number::zero() && e.float_value(prec).fract() != 0 {
+            return None;
+        }
+
+        Some(Number::Float(Box::new(
+            self.float_value(prec).pow(e.float_value(prec)),
+        )))
+    }
+
+    /// Raise the number to the power `e`. The result is exact if possible
+    /// and a float with a precision of `prec` bits otherwise.
+    /// Returns `None` if the result is undefined or not real, if the
+    /// exponent is an integer that does not fit in 32 bits, or if an exact
+    /// result would have more than `MAX_POWER_BITS` bits.
+    pub fn pow_prec(&self, e: &Number, prec: u32) -> Option<Number> {
+        match *e {
+            Number::SmallInt(n) => {
+                // exponents that do not fit in 32 bits are left unevaluated
+                let k = match n.checked_abs() {
+                    Some(k) if k as u64 <= u64::from(u32::MAX) => k as u32,
+                    _ => return None,
+                };
+                if let Some(bits) = self.exact_bits() {
+                    if bits.saturating_sub(1) * u64::from(k) > MAX_POWER_BITS {
+                        return None;
+                    }
+                }
+                if n >= 0 {
+                    return Some(num_traits::Pow::pow(self.clone(), k));
+                }
+                if self.is_zero() {
+                    return None;
+                }
+                return Some(Number::one() / num_traits::Pow::pow(self.clone(), k));
+            }
+            Number::BigInt(..) => return None,
+            Number::Float(..) => return self.float_pow(e),
+            Number::Complex(..) => return None,
+            _ => {}
+        }
+
+        if let Number::Complex(..) = *self {
+            return None; // only integer powers of complex numbers are supported
+        }
+
+        if let Number::Float(..) = *self {
+            return self.float_pow(e);
+        }
+
+        if *self < Number::zero() {
+            return None; // the principal root is complex
+        }
+
+        // try to take the root exactly, for example 4^(3/2) = 8
+        let (p, q) = e.rational_value().into_numer_denom();
+        if let (Some(p), Some(q)) = (p.to_isize(), q.to_u32()) {
+            let r = self.rational_value();
+            let num = r.numer().clone().root(q);
+            let den = r.denom().clone().root(q);
+            if num.clone().pow(q) == *r.numer() && den.clone().pow(q) == *r.denom() {
+                return Number::BigRat(Box::new(Rational::from((num, den))))
+                    .normalized()
+                    .pow_prec(&Number::SmallInt(p), prec);
+            }
+        }
+
+        self.to_float(prec).float_pow(e)
+    }
+
+    /// Split an exact number into its integer part, rounded towards minus infinity,
+    /// and a remainder between 0 and 1: `7/2 = 3 + 1/2` and `-1/2 = -1 + 1/2`.
+    pub fn floor_fract(&self) -> Option<(Number, Number)> {
+        match *self {
+            Number::SmallInt(_) | Number::BigInt(_) => Some((self.clone(), Number::zero())),
+            Number::SmallRat(..) | Number::BigRat(..) => {
+                let (p, q) = self.rational_value().into_numer_denom();
+                let (k, r) = p.div_rem_floor(q.clone());
+                Some((
+                    Number::BigInt(k).normalized(),
+                    Number::BigRat(Box::new(Rational::from((r, q)))).normalized(),
+                ))
+            }
+            Number::Float(..) | Number::Complex(..) => None,
+        }
+    }
+
+    /// Returns the numerator and the positive denominator of an exact number.
+    pub fn numer_denom(&self) -> Option<(Number, Number)> {
+        match *self {
+            Number::Float(..) | Number::Complex(..) => None,
+            _ => {
+                let (p, q) = self.rational_value().into_numer_denom();
+                Some((Number::BigInt(p).normalized(), Number::BigInt(q).normalized()))
+            }
+        }
+    }
+
+    /// Returns the binomial coefficient `n` choose `k`, where `n` is an integer.
+    pub fn binomial(&self, k: u32) -> Option<Number> {
+        match *self {
+            Number::SmallInt(n) => Some(Number::BigInt(Integer::from(n).binomial(k)).normalized()),
+            Number::BigInt(ref n) => Some(Number::BigInt(n.clone().binomial(k)).normalized()),
+            _ => None,
+        }
+    }
+
+    /// Divide two exact numbers, rounding the quotient towards minus infinity.
+    /// Returns the quotient and the remainder, which has the sign of `b`.
+    /// Returns `None` if `b` is zero or if one of the numbers is not exact.
+    pub fn div_mod_floor(&self, b: &Number) -> Option<(Number, Number)> {
+        match (self, b) {
+            (&Number::Float(..), _) | (&Number::Complex(..), _) => return None,
+            (_, &Number::Float(..)) | (_, &Number::Complex(..)) => return None,
+            _ if b.is_zero() => return None,
+            _ => {}
+        }
+
+        let (q, _) = (self.clone() / b.clone()).floor_fract()?;
+        let r = self.clone() - b.clone() * q.clone();
+        Some((q, r))
+    }
+
+    /// Returns the value of an integer as a big integer.
+    fn integer_value(&self) -> Option<Integer> {
+        match *self {
+            Number::SmallInt(n) => Some(Integer::from(n)),
+            Number::BigInt(ref n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the integer is a prime. Integers smaller than 2 are not
+    /// prime. Big integers are tested probabilistically, with a negligible
+    /// chance of a false positive.
+    pub fn is_prime(&self) -> Option<bool> {
+        // rug tests the absolute value, so -7 would be a prime
+        self.integer_value().map(|n| n >= 2 && n.is_probably_prime(30) != IsPrime::No)
+    }
+
+    /// Returns the smallest prime larger than the integer.
+    pub fn next_prime(&self) -> Option<Number> {
+        let n = self.integer_value()?;
+        if n < 2 {
+            return Some(Number::SmallInt(2));
+        }
+        Some(Number::BigInt(n.next_prime()).normalized())
+    }
+
+    /// Returns the prime factors of a non-zero integer with their multiplicities.
+    /// A negative integer has the factor -1.
+    pub fn factor(&self) -> Option<Vec<(Number, Number)>> {
+        let n = self.integer_value()?;
+        if n == 0 {
+            return None;
         }
+
+        let mut factors = vec![];
+        if n < 0 {
+            factors.push((Number::SmallInt(-1), Number::one()));
+        }
+        for (p, k) in factor_integer(&n.abs()) {
+            factors.push((Number::BigInt(p).normalized(), Number::SmallInt(k as isize)));
+        }
+        Some(factors)
+    }
+
+    /// Returns `self^e mod m` in the range `[0, m)`, for integers and a positive
+    /// modulus `m`. A negative exponent requires the inverse of `self` modulo `m`.
+    pub fn pow_mod(&self, e: &Number, m: &Number) -> Option<Number> {
+        let (n, e, m) = (self.integer_value()?, e.integer_value()?, m.integer_value()?);
+        if m <= 0 {
+            return None;
+        }
+        n.pow_mod(&e, &m).ok().map(|r| Number::BigInt(r).normalized())
+    }
+
+    /// Returns the inverse of the integer modulo a positive `m`, if it exists.
+    pub fn inv_mod(&self, m: &Number) -> Option<Number> {
+        let (n, m) = (self.integer_value()?, m.integer_value()?);
+        if m <= 0 {
+            return None;
+        }
+        n.invert(&m).ok().map(|r| Number::BigInt(r).normalized())
     }
 
     pub fn abs(&self) -> Number {
@@ -129,6 +485,33 @@ impl Number {
             },
             Number::BigInt(ref i) => Number::BigInt(i.clone().abs()),
             Number::BigRat(ref r) => Number::BigRat(Box::new(r.clone().abs())),
+            Number::Float(ref f) => Number::Float(Box::new((**f).clone().abs())),
+            Number::Complex(..) => panic!("Cannot take the absolute value of {}", self),
+        }
+    }
+}
+
+impl Hash for Number {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match *self {
+            Number::SmallInt(i) => i.hash(state),
+            Number::BigInt(ref i) => i.hash(state),
+            Number::SmallRat(n, d) => {
+                n.hash(state);
+                d.hash(state);
+            }
+            Number::BigRat(ref r) => r.hash(state),
+            // floats of different precision can be equal, and so are 0 and -0
+            Number::Float(ref f) => if f.is_zero() {
+                0u64.hash(state)
+            } else {
+                f.to_f64().to_bits().hash(state)
+            },
+            Number::Complex(ref c) => {
+                c.0.hash(state);
+                c.1.hash(state);
+            }
         }
     }
 }
@@ -143,6 +526,9 @@ impl PartialEq for Number {
             (Number::BigInt(i1), Number::SmallInt(i2)) => i1 == i2,
             (Number::SmallRat(i1, d1), Number::SmallRat(i2, d2)) => i1 == i2 && d1 == d2,
             (Number::BigRat(i1), Number::BigRat(i2)) => i1 == i2,
+            // NaN is equal to itself, as required by `Eq`
+            (Number::Float(f1), Number::Float(f2)) => f1 == f2 || f1.is_nan() && f2.is_nan(),
+            (Number::Complex(c1), Number::Complex(c2)) => c1 == c2,
             _ => false,
         }
     }
@@ -150,6 +536,35 @@ impl PartialEq for Number {
 
 impl Eq for Number {}
 
+impl Number {
+    /// Print the number, using `i` as the name of the imaginary unit.
+    pub fn fmt_complex(&self, f: &mut fmt::Formatter, i: &str) -> fmt::Result {
+        match *self {
+            Number::Complex(ref c) => {
+                let (ref re, ref im) = **c;
+                if !re.is_zero() {
+                    write!(f, "({}", re)?;
+                    if *im > Number::zero() {
+                        write!(f, "+")?;
+                    }
+                }
+                if im.is_one() {
+                    write!(f, "{}", i)?;
+                } else if *im == Number::SmallInt(-1) {
+                    write!(f, "-{}", i)?;
+                } else {
+                    write!(f, "{}*{}", im, i)?;
+                }
+                if !re.is_zero() {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
 impl fmt::Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -157,6 +572,8 @@ impl fmt::Display for Number {
             Number::SmallRat(n, d) => write!(f, "{}/{}", n, d),
             Number::BigInt(ref i) => write!(f, "{}", i.to_string_radix(10)),
             Number::BigRat(ref r) => write!(f, "{}", r.to_string_radix(10)),
+            Number::Float(ref fl) => write!(f, "{}", fl.to_string_radix(10, None)),
+            Number::Complex(..) => self.fmt_complex(f, "i_"),
         }
     }
 }
@@ -174,6 +591,8 @@ impl Zero for Number {
             Number::BigInt(ref i) => *i == 0, // TODO: is this fast?
             Number::SmallRat(n, _d) => n == 0,
             Number::BigRat(ref f) => *f.numer() == 0 && *f.denom() == 1,
+            Number::Float(ref f) => f.is_zero(),
+            Number::Complex(ref c) => c.0.is_zero() && c.1.is_zero(),
         }
     }
 }
@@ -205,6 +624,8 @@ impl One for Number {
             Number::BigInt(ref i) => *i == 1,
             Number::SmallRat(n, _d) => n == 1,
             Number::BigRat(ref f) => *f.numer() == 1 && *f.denom() == 1,
+            Number::Float(..) => false, // keep the float to mark the coefficient as approximate
+            Number::Complex(ref c) => c.0.is_one() && c.1.is_zero(),
         }
     }
 }
@@ -231,6 +652,11 @@ impl Neg for Number {
                 Number::SmallRat(-n, d)
             },
             Number::BigRat(f) => Number::BigRat(Box::new(-*f)),
+            Number::Float(f) => Number::Float(Box::new(-*f)),
+            Number::Complex(c) => {
+                let (re, im) = *c;
+                Number::Complex(Box::new((-re, -im)))
+            }
         }
     }
 }
@@ -244,6 +670,13 @@ impl Inv for Number {
             Number::BigInt(i) => Number::BigRat(Box::new(Rational::from((1, i)))),
             Number::SmallRat(n, d) => Number::SmallRat(d, n),
             Number::BigRat(f) => Number::BigRat(Box::new((*f).recip())),
+            Number::Float(f) => Number::Float(Box::new((*f).recip())),
+            Number::Complex(c) => {
+                // 1/(a+b*i) = (a-b*i)/(a^2+b^2)
+                let (re, im) = *c;
+                let n = re.clone() * re.clone() + im.clone() * im.clone();
+                Number::complex(re / n.clone(), -im / n)
+            }
         }
     }
 }
@@ -280,14 +713,60 @@ impl PartialOrd for Number {
             (&BigRat(ref f1), BigInt(ref i2)) => (**f1).partial_cmp(&Rational::from(i2)),
             (&BigRat(ref f1), SmallRat(n2, d2)) => (**f1).partial_cmp(&Rational::from((*n2, *d2))),
             (&BigRat(ref f1), BigRat(ref f2)) => f1.partial_cmp(f2),
+            // complex numbers are ordered by their real part first
+            (&Complex(ref c1), Complex(ref c2)) => c1.partial_cmp(c2),
+            (&Complex(ref c1), x) => (**c1).partial_cmp(&(x.clone(), Number::zero())),
+            (x, Complex(ref c2)) => (x.clone(), Number::zero()).partial_cmp(&**c2),
+            (&Float(ref f1), Float(ref f2)) => f1.partial_cmp(f2),
+            (&Float(ref f1), x) => (**f1).partial_cmp(&x.rational_value()),
+            (x, Float(ref f2)) => x.rational_value().partial_cmp(&**f2),
+        }
+    }
+}
+
+impl Number {
+    /// Compare two real numbers by value. NaN is not ordered, so it is sorted
+    /// after all other numbers.
+    fn cmp_real(&self, other: &Number) -> Ordering {
+        match self.partial_cmp(other) {
+            Some(o) => o,
+            None => self.is_nan().cmp(&other.is_nan()),
+        }
+    }
+
+    /// The kind of the number and of its real and imaginary parts:
+    /// exact numbers come first, then floats, then complex numbers.
+    fn kind(&self) -> (u8, u8, u8) {
+        let rank = |n: &Number| match *n {
+            Number::Float(..) => 1,
+            Number::Complex(..) => 2,
+            _ => 0,
+        };
+        match *self {
+            Number::Complex(ref c) => (2, rank(&c.0), rank(&c.1)),
+            ref x => (rank(x), 0, 0),
         }
     }
 }
 
 impl Ord for Number {
-    #[inline]
+    /// Compare numbers by value, with the real part first. Numbers with the
+    /// same value but of a different kind, such as `0.5` and `1/2`, are
+    /// ordered by their kind, so that only equal numbers compare as equal.
     fn cmp(&self, other: &Number) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let zero = Number::zero();
+        let (re1, im1) = match *self {
+            Number::Complex(ref c) => (&c.0, &c.1),
+            ref x => (x, &zero),
+        };
+        let (re2, im2) = match *other {
+            Number::Complex(ref c) => (&c.0, &c.1),
+            ref x => (x, &zero),
+        };
+
+        re1.cmp_real(re2)
+            .then_with(|| im1.cmp_real(im2))
+            .then_with(|| self.kind().cmp(&other.kind()))
     }
 }
 
@@ -354,6 +833,15 @@ impl Number {
                 Number::BigRat(Box::new(*f1 + Rational::from((n2, d2))))
             }
             (BigRat(f1), BigRat(f2)) => BigRat(Box::new(*f1 + *f2)),
+            (Complex(c1), x) | (x, Complex(c1)) => {
+                let (re1, im1) = *c1;
+                let (re2, im2) = x.parts();
+                Number::complex(re1 + re2, im1 + im2)
+            }
+            (Float(f1), x) | (x, Float(f1)) => {
+                let (f1, f2) = Number::float_operands(f1, x);
+                Float(Box::new(f1 + f2))
+            }
         }
     }
 }
@@ -448,6 +936,19 @@ impl Mul for Number {
                 Number::BigRat(Box::new(*f1 * Rational::from((n2, d2)))).normalized()
             }
             (BigRat(f1), BigRat(f2)) => BigRat(Box::new(*f1 * *f2)).normalized(),
+            (Complex(c1), x) | (x, Complex(c1)) => {
+                // (a+b*i)*(c+d*i) = (a*c-b*d)+(a*d+b*c)*i
+                let (re1, im1) = *c1;
+                let (re2, im2) = x.parts();
+                Number::complex(
+                    re1.clone() * re2.clone() - im1.clone() * im2.clone(),
+                    re1 * im2 + im1 * re2,
+                )
+            }
+            (Float(f1), x) | (x, Float(f1)) => {
+                let (f1, f2) = Number::float_operands(f1, x);
+                Float(Box::new(f1 * f2))
+            }
         }
     }
 }
@@ -495,6 +996,23 @@ impl num_traits::Pow<u32> for Number {
                 None => BigRat(Box::new(Rational::from((n, d)).pow(rhs))),
             },
             BigRat(f) => BigRat(Box::new(f.pow(rhs))),
+            Float(f) => Float(Box::new(f.pow(rhs))),
+            Complex(c) => {
+                // exponentiation by squaring
+                let mut base = Complex(c);
+                let mut r = Number::one();
+                let mut e = rhs;
+                while e > 0 {
+                    if e & 1 == 1 {
+                        r = r * base.clone();
+                    }
+                    e >>= 1;
+                    if e > 0 {
+                        base = base.clone() * base;
+                    }
+                }
+                r
+            }
         }
     }
 }
@@ -623,3 +1141,37 @@ pub fn chinese_remainder(n1: Number, n2: Number, p1: Number, p2: Number) -> Numb
         r
     }
 }
+
+/// Use Wang's algorithm to reconstruct a rational number `r/t` from its image
+/// `n` modulo `m`, with `|r|, |t| <= sqrt(m/2)`. Returns `None` if no such
+/// rational number exists.
+pub fn rational_reconstruction(n: &Number, m: &Number) -> Option<Number> {
+    let to_integer = |x: &Number| match *x {
+        Number::SmallInt(i) => Integer::from(i),
+        Number::BigInt(ref i) => i.clone(),
+        _ => unreachable!("Rational reconstruction requires integers"),
+    };
+
+    let m = to_integer(m);
+    let mut n = to_integer(n) % &m;
+    if n < 0 {
+        n += &m;
+    }
+
+    let bound = Integer::from(&m >> 1u32).sqrt();
+
+    let (mut r0, mut r1) = (m.clone(), n);
+    let (mut t0, mut t1) = (Integer::new(), Integer::from(1));
+    while r1 > bound {
+        let (q, r) = r0.div_rem_floor(r1.clone());
+        r0 = mem::replace(&mut r1, r);
+        let t = t0 - Integer::from(&q * &t1);
+        t0 = mem::replace(&mut t1, t);
+    }
+
+    if t1.clone().abs() > bound || r1.clone().gcd(&t1) != 1 {
+        return None;
+    }
+
+    Some(Number::BigInt(r1).normalized() / Number::BigInt(t1).normalized())
+}
@@ -1,7 +1,8 @@
 extern crate pest;
 
-use number::Number;
-use rug::{Integer, Rational};
+use number::{Number, DEFAULT_FLOAT_PRECISION, MAX_POWER_BITS};
+use rug::ops::Pow;
+use rug::{Float, Integer, Rational};
 use std::fs::File;
 use std::io::prelude::*;
 use std::str::FromStr;
@@ -35,7 +36,7 @@ fn parse_number(e: pest::iterators::Pair<Rule>) -> Number {
         Rule::op_unary_minus => {
             sign = -1;
         }
-        Rule::integer => {
+        Rule::integer | Rule::float => {
             num = sign_or_num.into_span().as_str().to_string();
         }
         x => unreachable!("Unexpected {:#?}", x),
@@ -46,6 +47,10 @@ fn parse_number(e: pest::iterators::Pair<Rule>) -> Number {
         num = eee.into_span().as_str().to_string();
     }
 
+    if num.contains(|c: char| c == '.' || c == 'e' || c == 'E') {
+        return parse_float(sign, &num);
+    }
+
     match ee.next() {
         Some(x) => {
             let den = x.into_span().as_str().to_string();
@@ -58,6 +63,44 @@ fn parse_number(e: pest::iterators::Pair<Rule>) -> Number {
     }
 }
 
+/// Parse a float literal such as `1.5e-3` to the rational number
+/// with exactly the same value. If the exact value would be too large,
+/// as for `1e2000000000`, the literal is parsed to a float instead.
+fn parse_float(sign: i32, s: &str) -> Number {
+    let (mantissa, exp) = match s.find(|c: char| c == 'e' || c == 'E') {
+        Some(i) => (&s[..i], s[i + 1..].parse::<Integer>().unwrap()),
+        None => (s, Integer::new()),
+    };
+    let (int, frac) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+
+    let digits = sign * (int.to_string() + frac).parse::<Integer>().unwrap();
+    if digits == 0 {
+        return Number::SmallInt(0);
+    }
+
+    // 10^k has fewer than 4k bits
+    let exp = exp - Integer::from(frac.len());
+    let exp = match exp.to_i64() {
+        Some(e) if e.abs() as u64 <= MAX_POWER_BITS / 4 => e,
+        _ => {
+            let f = Float::with_val(DEFAULT_FLOAT_PRECISION, Float::parse(s).unwrap());
+            return Number::Float(Box::new(sign * f));
+        }
+    };
+
+    if exp >= 0 {
+        Number::BigInt(digits * Integer::from(10).pow(exp as u32))
+    } else {
+        Number::BigRat(Box::new(Rational::from((
+            digits,
+            Integer::from(10).pow(-exp as u32),
+        ))))
+    }
+}
+
 fn parse_dollar(dollar: pest::iterators::Pair<Rule>) -> Element<String> {
     let mut ee = dollar.into_inner();
     let name = ee.next().unwrap().into_span().as_str().to_string();
@@ -105,6 +148,15 @@ fn parse_function(e: pest::iterators::Pair<Rule>) -> Element<String> {
     let mut args = vec![];
     // func args
     for arg in ee.next().unwrap().into_inner() {
+        if arg.as_rule() == Rule::func_assign {
+            // x=v, only allowed in eval_ and N_, is stored as x==v
+            let mut ee = arg.into_inner();
+            let lhs = parse_identity(ee.next().unwrap());
+            let rhs = parse_expr(ee.next().unwrap());
+            args.push(Element::Comparison(true, Box::new((lhs, rhs)), Ordering::Equal));
+            continue;
+        }
+
         // funcargs
         let a = arg.into_inner().next().unwrap();
         match a.as_rule() {
@@ -148,7 +200,13 @@ fn parse_primary(e: pest::iterators::Pair<Rule>) -> Element<String> {
     let p = ee.next().unwrap();
     match p.as_rule() {
         Rule::number => {
-            return Element::Num(true, parse_number(p));
+            // float literals are converted at the current precision
+            let is_float = p.clone().into_inner().any(|x| x.as_rule() == Rule::float);
+            let num = Element::Num(true, parse_number(p));
+            if is_float {
+                return Element::Fn(true, "N_".to_owned(), vec![num]);
+            }
+            return num;
         }
         Rule::identity => {
             let mut ee = p.into_span().as_str().to_string();
@@ -321,6 +379,7 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
                     "symmetric" => attribs.push(FunctionAttributes::Symmetric),
                     "noncommutative" => attribs.push(FunctionAttributes::NonCommutative),
                     "nonlocal" => attribs.push(FunctionAttributes::NonLocal),
+                    "real" => attribs.push(FunctionAttributes::Real),
                     x => unreachable!("Unexpected option {:?}", x),
                 }
             }
@@ -438,10 +497,32 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
             Statement::MatchAssign(m, sts)
         }
         Rule::multiply_statement => Statement::Multiply(parse_expr(e.into_inner().next().unwrap())),
+        Rule::differentiate_statement => {
+            Statement::Differentiate(e.into_inner().map(parse_expr).collect())
+        }
         Rule::maximum_statement => Statement::Maximum(parse_dollar(e.into_inner().next().unwrap())),
         Rule::replaceby_statement => {
             Statement::ReplaceBy(parse_dollar(e.into_inner().next().unwrap()))
         }
+        Rule::series_statement => Statement::Series(
+            e.into_inner()
+                .map(|x| match x.as_rule() {
+                    Rule::identity => parse_identity(x),
+                    _ => Element::Num(
+                        true,
+                        Number::BigInt(x.into_span().as_str().parse::<Integer>().unwrap()),
+                    ),
+                })
+                .collect(),
+        ),
+        Rule::precision_statement => {
+            Statement::Precision(parse_expr(e.into_inner().next().unwrap()))
+        }
+        Rule::algebraic_statement => {
+            let mut r = e.into_inner();
+            let x = parse_identity(r.next().unwrap());
+            Statement::Algebraic(x, parse_expr(r.next().unwrap()))
+        }
         Rule::collect_statement => Statement::Collect(
             e.into_inner()
                 .next()
@@ -450,6 +531,20 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
                 .as_str()
                 .to_string(),
         ),
+        Rule::solve_statement => {
+            let mut eqs = vec![];
+            let mut unknowns = vec![];
+            for x in e.into_inner() {
+                match x.as_rule() {
+                    Rule::dollar => eqs.push(parse_dollar(x)),
+                    Rule::identity => eqs.push(parse_identity(x)),
+                    Rule::expression => unknowns.push(parse_expr(x)),
+                    _ => unreachable!(),
+                }
+            }
+
+            Statement::Solve(eqs, unknowns)
+        }
         Rule::splitarg_statement => Statement::SplitArg(
             e.into_inner()
                 .next()
@@ -477,6 +572,7 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
                     }
                     Rule::print_opt => match d.into_span().as_str().to_lowercase().as_str() {
                         "mathematica" => print_opt = PrintMode::Mathematica,
+                        "latex" => print_opt = PrintMode::Latex,
                         _ => {}
                     },
                     _ => unreachable!(),
@@ -510,6 +606,7 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
                     }
                     Rule::print_opt => match d.into_span().as_str().to_lowercase().as_str() {
                         "mathematica" => print_opt = PrintMode::Mathematica,
+                        "latex" => print_opt = PrintMode::Latex,
                         _ => {}
                     },
                     _ => unreachable!(),
@@ -541,8 +638,11 @@ fn parse_statement(e: pest::iterators::Pair<Rule>) -> Statement<String> {
             }
 
             let mut statements = vec![];
-            for exec_statement in r {
-                let child = exec_statement.into_inner().next().unwrap();
+            for module_statement in r {
+                let child = match module_statement.as_rule() {
+                    Rule::exec_statement => module_statement.into_inner().next().unwrap(),
+                    _ => module_statement,
+                };
                 statements.push(parse_statement(child));
             }
 
@@ -1,11 +1,25 @@
 use libc::c_char;
+use number::Number;
 use poly::polynomial;
 use poly::polynomial::PolyPrinter;
+use poly::raw::zp::ufield;
 use std::ffi::{CStr, CString};
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ptr;
+use std::slice;
 use std::str;
 use std::str::FromStr;
-use structure::{Element, VarInfo};
+use structure::{Element, VarInfo, VarName};
+
+/// Convert a C string to a string slice.
+fn to_str<'b>(s: *const c_char) -> &'b str {
+    unsafe {
+        assert!(!s.is_null());
+        CStr::from_ptr(s)
+    }
+    .to_str()
+    .unwrap()
+}
 
 #[derive(Clone)]
 pub struct Polynomial<'a> {
@@ -92,6 +106,20 @@ impl<'a> RationalPolynomial<'a> {
         );
         l
     }
+
+    fn derivative(&self, x: &str) -> RationalPolynomial<'a> {
+        let mut l = self.clone();
+        match self.num.var_info.global_info.find_name(x) {
+            Some(name) => {
+                polynomial::rationalpolynomial_derivative(&mut l.num.poly, &mut l.den.poly, name);
+            }
+            None => {
+                l.num.poly = polynomial::Polynomial::new();
+                l.den.poly = self.den.poly.cloned_one();
+            }
+        }
+        l
+    }
 }
 
 impl<'a> Polynomial<'a> {
@@ -155,6 +183,59 @@ impl<'a> Polynomial<'a> {
         }
     }
 
+    fn substitute(&self, x: &str, v: &Polynomial) -> Polynomial<'a> {
+        let r = match self.var_info.global_info.find_name(x) {
+            Some(name) => self.poly.clone().substitute(&mut [(name, v.poly.clone())]),
+            None => self.poly.clone(),
+        };
+
+        Polynomial {
+            poly: r,
+            var_info: self.var_info,
+        }
+    }
+
+    fn taylor_shift(&self, x: &str, a: i64) -> Polynomial<'a> {
+        let r = match self.var_info.global_info.find_name(x) {
+            Some(name) => self.poly.taylor_shift(name, &Number::SmallInt(a as isize)),
+            None => self.poly.clone(),
+        };
+
+        Polynomial {
+            poly: r,
+            var_info: self.var_info,
+        }
+    }
+
+    fn derivative(&self, x: &str) -> Polynomial<'a> {
+        let r = match self.var_info.global_info.find_name(x) {
+            Some(name) => self.poly.derivative(name),
+            None => polynomial::Polynomial::new(),
+        };
+
+        Polynomial {
+            poly: r,
+            var_info: self.var_info,
+        }
+    }
+
+    fn integrate(&self, x: &str) -> Option<Polynomial<'a>> {
+        self.var_info
+            .global_info
+            .find_name(x)
+            .map(|name| Polynomial {
+                poly: self.poly.integrate(name),
+                var_info: self.var_info,
+            })
+    }
+
+    fn point<T: Copy>(&self, vars: &[&str], values: &[T]) -> Vec<(VarName, T)> {
+        vars.iter()
+            .zip(values)
+            .filter_map(|(x, v)| self.var_info.global_info.find_name(x).map(|n| (n, *v)))
+            .collect()
+    }
+
     fn to_string(&self) -> String {
         format!(
             "{}",
@@ -341,6 +422,171 @@ pub extern "C" fn polynomial_gcd<'a>(
     Box::into_raw(Box::new(lhsp.gcd(rhsp)))
 }
 
+#[no_mangle]
+pub extern "C" fn polynomial_substitute<'a>(
+    poly: *const Polynomial<'a>,
+    var: *const c_char,
+    value: *const Polynomial<'a>,
+) -> *mut Polynomial<'a> {
+    let polyp = unsafe {
+        assert!(!poly.is_null());
+        &*poly
+    };
+
+    let valuep = unsafe {
+        assert!(!value.is_null());
+        &*value
+    };
+
+    Box::into_raw(Box::new(polyp.substitute(to_str(var), valuep)))
+}
+
+#[no_mangle]
+pub extern "C" fn polynomial_taylor_shift<'a>(
+    poly: *const Polynomial<'a>,
+    var: *const c_char,
+    a: i64,
+) -> *mut Polynomial<'a> {
+    let polyp = unsafe {
+        assert!(!poly.is_null());
+        &*poly
+    };
+
+    Box::into_raw(Box::new(polyp.taylor_shift(to_str(var), a)))
+}
+
+#[no_mangle]
+pub extern "C" fn polynomial_derivative<'a>(
+    poly: *const Polynomial<'a>,
+    var: *const c_char,
+) -> *mut Polynomial<'a> {
+    let polyp = unsafe {
+        assert!(!poly.is_null());
+        &*poly
+    };
+
+    Box::into_raw(Box::new(polyp.derivative(to_str(var))))
+}
+
+/// Integrate the polynomial with respect to `var`. Returns a null pointer
+/// if the variable is unknown to the variable info of the polynomial.
+#[no_mangle]
+pub extern "C" fn polynomial_integrate<'a>(
+    poly: *const Polynomial<'a>,
+    var: *const c_char,
+) -> *mut Polynomial<'a> {
+    let polyp = unsafe {
+        assert!(!poly.is_null());
+        &*poly
+    };
+
+    match polyp.integrate(to_str(var)) {
+        Some(r) => Box::into_raw(Box::new(r)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Evaluate the polynomial modulo `p`, with the value of the variable `vars[i]`
+/// given by `values[i]`. Returns `false` if a variable has no value or if the
+/// polynomial cannot be evaluated modulo `p`.
+#[no_mangle]
+pub extern "C" fn polynomial_evaluate_mod(
+    poly: *const Polynomial,
+    vars: *const *const c_char,
+    values: *const ufield,
+    n: usize,
+    p: ufield,
+    result: *mut ufield,
+) -> bool {
+    let (polyp, vars, values) = unsafe {
+        assert!(!poly.is_null() && !vars.is_null() && !values.is_null() && !result.is_null());
+        (
+            &*poly,
+            slice::from_raw_parts(vars, n),
+            slice::from_raw_parts(values, n),
+        )
+    };
+
+    let vars: Vec<_> = vars.iter().map(|x| to_str(*x)).collect();
+    match polyp
+        .poly
+        .evaluate_finite_field(&polyp.point(&vars, values), p)
+    {
+        Ok(r) => {
+            unsafe { *result = r };
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Evaluate the polynomial in floating point arithmetic, with the value of the
+/// variable `vars[i]` given by `values[i]`. Returns `false` if a variable has no value.
+#[no_mangle]
+pub extern "C" fn polynomial_evaluate_float(
+    poly: *const Polynomial,
+    vars: *const *const c_char,
+    values: *const f64,
+    n: usize,
+    result: *mut f64,
+) -> bool {
+    let (polyp, vars, values) = unsafe {
+        assert!(!poly.is_null() && !vars.is_null() && !values.is_null() && !result.is_null());
+        (
+            &*poly,
+            slice::from_raw_parts(vars, n),
+            slice::from_raw_parts(values, n),
+        )
+    };
+
+    let vars: Vec<_> = vars.iter().map(|x| to_str(*x)).collect();
+    match polyp.poly.evaluate_float(&polyp.point(&vars, values)) {
+        Ok(r) => {
+            unsafe { *result = r };
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Evaluate a univariate polynomial at `n` points. The values are written
+/// to `results` as constant polynomials. Returns `false` if the polynomial is
+/// not univariate.
+#[no_mangle]
+pub extern "C" fn polynomial_evaluate_multipoint<'a>(
+    poly: *const Polynomial<'a>,
+    points: *const i64,
+    n: usize,
+    results: *mut *mut Polynomial<'a>,
+) -> bool {
+    let (polyp, points, results) = unsafe {
+        assert!(!poly.is_null() && !points.is_null() && !results.is_null());
+        (
+            &*poly,
+            slice::from_raw_parts(points, n),
+            slice::from_raw_parts_mut(results, n),
+        )
+    };
+
+    let points: Vec<_> = points
+        .iter()
+        .map(|x| Number::SmallInt(*x as isize))
+        .collect();
+    match polyp.poly.evaluate_multipoint(&points) {
+        Ok(values) => {
+            let one = polyp.poly.cloned_one();
+            for (r, v) in results.iter_mut().zip(values) {
+                *r = Box::into_raw(Box::new(Polynomial {
+                    poly: one.clone() * v,
+                    var_info: polyp.var_info,
+                }));
+            }
+            true
+        }
+        Err(_) => false,
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn rationalpolynomial_new<'a>(
     num: *const Polynomial<'a>,
@@ -478,3 +724,74 @@ pub extern "C" fn rationalpolynomial_div<'a>(
 
     Box::into_raw(Box::new(lhsp.div(rhsp)))
 }
+
+#[no_mangle]
+pub extern "C" fn rationalpolynomial_derivative<'a>(
+    poly: *const RationalPolynomial<'a>,
+    var: *const c_char,
+) -> *mut RationalPolynomial<'a> {
+    let polyp = unsafe {
+        assert!(!poly.is_null());
+        &*poly
+    };
+
+    Box::into_raw(Box::new(polyp.derivative(to_str(var))))
+}
+
+#[test]
+fn taylor_shift_and_evaluate_multipoint() {
+    use rug::Integer;
+
+    let var_info = polynomial_varinfo();
+    let new = |s: &str| polynomial_new(CString::new(s).unwrap().as_ptr(), var_info);
+    let to_string = |p: *const Polynomial| {
+        let s = polynomial_to_string(p);
+        let r = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_owned();
+        polynomial_string_free(s);
+        r
+    };
+    let x = CString::new("x").unwrap();
+
+    // (x+2)^2+3*(x+2)+1 = x^2+7*x+11
+    let a = new("x^2+3*x+1");
+    let shifted = polynomial_taylor_shift(a, x.as_ptr(), 2);
+    let expected = new("x^2+7*x+11");
+    assert_eq!(to_string(shifted), to_string(expected));
+
+    // more points than the threshold of the subproduct tree, compared with the
+    // evaluation term by term
+    let coefficients: Vec<i64> = (0..41).map(|i| i * i + 1).collect();
+    let terms: Vec<String> = coefficients
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{}*x^{}", c, i))
+        .collect();
+    let b = new(&terms.join("+"));
+
+    let points: Vec<i64> = (-25..26).collect();
+    let mut results = vec![ptr::null_mut(); points.len()];
+    assert!(polynomial_evaluate_multipoint(
+        b,
+        points.as_ptr(),
+        points.len(),
+        results.as_mut_ptr()
+    ));
+
+    for (x, r) in points.iter().zip(results) {
+        let mut naive = Integer::new();
+        let mut xi = Integer::from(1);
+        for c in &coefficients {
+            naive += Integer::from(*c) * &xi;
+            xi *= *x;
+        }
+        let expected = new(&naive.to_string());
+        assert_eq!(to_string(r), to_string(expected));
+        polynomial_free(r);
+        polynomial_free(expected);
+    }
+
+    for p in &[a, shifted, expected, b] {
+        polynomial_free(*p);
+    }
+    polynomial_varinfo_free(var_info);
+}
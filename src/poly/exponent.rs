@@ -1,6 +1,6 @@
 use std::ops::Sub;
 use num_traits::{CheckedAdd, One, Zero};
-use num_traits::cast::{FromPrimitive, AsPrimitive};
+use num_traits::cast::{FromPrimitive, AsPrimitive, ToPrimitive};
 use std::hash::Hash;
 use std::fmt::{Debug, Display};
 
@@ -13,6 +13,7 @@ pub trait Exponent
     + One
     + FromPrimitive
     + AsPrimitive<u32>
+    + ToPrimitive
     + CheckedAdd
     + Sub<Output = Self>
     + Ord
@@ -27,6 +28,7 @@ impl<
         + One
         + FromPrimitive
         + AsPrimitive<u32>
+        + ToPrimitive
         + CheckedAdd
         + Sub<Output = Self>
         + Ord
@@ -1,6 +1,16 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use num_traits::{One, Zero};
 use number::Number;
+use poly::raw::adaptive::AdaptivePolynomial;
+use poly::raw::algebraicnumber::AlgebraicNumber;
+use poly::raw::finitefield::FiniteField;
+use poly::raw::laurent::LaurentPolynomial;
+use poly::raw::rational_solve::{
+    number_mod_p, solve_rational, solve_rational_polynomial, RationalSolverError,
+};
+use poly::raw::reconstruct::reconstruct;
+use poly::raw::univariate::UnivariatePolynomial;
+use poly::raw::zp::ufield;
 use poly::raw::MultivariatePolynomial;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -9,6 +19,7 @@ use std::hash::{Hash, Hasher};
 use std::io::{Error, Read, Write};
 use std::mem;
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::sync::Arc;
 use structure::{fmt_varname, Element, GlobalVarInfo, VarName};
 use tools::GCD;
 
@@ -17,13 +28,13 @@ pub struct Polynomial {
     varcount: usize,
     varmap: HashMap<VarName, usize>, // map from names to internal name
     inv_varmap: Vec<VarName>,
-    pub poly: MultivariatePolynomial<Number, u32>,
+    pub poly: AdaptivePolynomial<Number>,
 }
 
 impl Polynomial {
     pub fn new() -> Polynomial {
         Polynomial {
-            poly: MultivariatePolynomial::new(),
+            poly: AdaptivePolynomial::zero(),
             varmap: HashMap::new(),
             inv_varmap: vec![],
             varcount: 0,
@@ -32,42 +43,48 @@ impl Polynomial {
 
     fn to_monomial(
         e: &Element,
-        exp: &mut Vec<u32>,
+        exp: &mut Vec<isize>,
         varmap: &mut HashMap<VarName, usize>,
         inv_varmap: &mut Vec<VarName>,
         varcount: &mut usize,
     ) -> Result<Number, String> {
         match *e {
-            Element::Var(ref x, Number::SmallInt(e)) if e > 0 => {
+            Element::Var(ref x, Number::SmallInt(e)) if e != 0 => {
                 match varmap.entry(*x) {
                     Entry::Occupied(v) => {
-                        exp[*v.get()] = e as u32;
+                        exp[*v.get()] = e;
                     }
                     Entry::Vacant(mut v) => {
                         v.insert(*varcount);
                         inv_varmap.push(*x);
                         *varcount += 1;
-                        exp.push(e as u32);
+                        exp.push(e);
                     }
                 }
 
                 Ok(Number::one())
             }
+            Element::Num(_, Number::Float(_)) => {
+                Err(format!("Float {} not allowed in polynomial", e))
+            }
+            Element::Num(_, Number::Complex(_)) => {
+                Err(format!("Complex number {} not allowed in polynomial", e))
+            }
             Element::Num(_, ref nn) => Ok(nn.clone()),
             Element::Pow(_, ref p) => {
                 let (ref b, ref ex) = **p;
                 if let Element::Var(ref x, Number::SmallInt(ee1)) = *b {
                     if let Element::Num(_, Number::SmallInt(n)) = *ex {
-                        if n > 0 {
+                        if n != 0 {
                             match varmap.entry(*x) {
                                 Entry::Occupied(v) => {
-                                    exp[*v.get()] = (n * ee1) as u32;
+                                    exp[*v.get()] = n * ee1;
                                 }
                                 Entry::Vacant(mut v) => {
                                     v.insert(*varcount);
                                     inv_varmap.push(*x);
                                     *varcount += 1;
-                                    exp.push((n * ee1) as u32);
+                                    exp.push(n * ee1);
                                 }
                             }
                             return Ok(Number::one());
@@ -88,13 +105,25 @@ impl Polynomial {
     }
 
     pub fn from(e: &Element) -> Result<Polynomial, String> {
+        let (num, den) = Polynomial::from_laurent(e)?;
+        if den.poly.is_constant() {
+            Ok(num)
+        } else {
+            Err(format!("{} has negative exponents", e))
+        }
+    }
+
+    /// Convert an expression that may contain negative powers of variables,
+    /// such as `1/ep^2+x`, to a numerator and a monomial denominator.
+    /// Both polynomials share the same variable map.
+    pub fn from_laurent(e: &Element) -> Result<(Polynomial, Polynomial), String> {
         let mut varcount = 0;
         let mut varmap = HashMap::new();
         let mut inv_varmap = vec![];
 
+        let mut monomials = vec![];
         match *e {
             Element::SubExpr(_, ref args) => {
-                let mut monomials = Vec::with_capacity(args.len());
                 for x in args {
                     let mut exp = vec![0; varcount];
                     let coeff = Polynomial::to_monomial(
@@ -106,64 +135,113 @@ impl Polynomial {
                     )?;
                     monomials.push((coeff, exp));
                 }
-
-                let mut poly = MultivariatePolynomial::with_nvars(varcount);
-
-                for (c, mut e) in monomials {
-                    if e.len() < varcount {
-                        e.resize(varcount, 0);
-                    }
-                    poly.append_monomial(c, &e);
-                }
-
-                Ok(Polynomial {
-                    poly,
-                    varmap,
-                    inv_varmap,
-                    varcount,
-                })
             }
-            Element::Var(ref x, Number::SmallInt(e)) if e > 0 => {
-                let mut exp = vec![e as u32];
-                varmap.insert(*x, 0);
-                inv_varmap.push(*x);
-                Ok(Polynomial {
-                    poly: MultivariatePolynomial::from_monomial(Number::one(), exp),
-                    varmap: varmap,
-                    inv_varmap: inv_varmap,
-                    varcount: 1,
-                })
-            }
-            Element::Num(_, ref nn) => Ok(Polynomial {
-                poly: MultivariatePolynomial::from_constant_with_nvars(nn.clone(), 0),
-                varmap: varmap,
-                inv_varmap: inv_varmap,
-                varcount: 0,
-            }),
-            Element::Term(..) | Element::Pow(..) => {
+            Element::Var(..) | Element::Num(..) | Element::Term(..) | Element::Pow(..) => {
                 let mut exp = vec![];
-                let c = Polynomial::to_monomial(
+                let coeff = Polynomial::to_monomial(
                     e,
                     &mut exp,
                     &mut varmap,
                     &mut inv_varmap,
                     &mut varcount,
                 )?;
-                let mut poly = MultivariatePolynomial::from_monomial(c, exp);
-                Ok(Polynomial {
-                    poly,
-                    varmap,
-                    inv_varmap,
-                    varcount,
-                })
+                monomials.push((coeff, exp));
+            }
+            _ => return Err(format!("{} not allowed in polynomial", e)),
+        }
+
+        // shift the exponents such that they are all non-negative
+        let mut shift = vec![0u64; varcount];
+        for &(_, ref exp) in &monomials {
+            for (s, &ee) in shift.iter_mut().zip(exp) {
+                if ee < 0 && (-ee) as u64 > *s {
+                    *s = (-ee) as u64;
+                }
+            }
+        }
+
+        let mut poly = MultivariatePolynomial::with_nvars(varcount);
+        let mut newexp = vec![0; varcount];
+        for (c, exp) in monomials {
+            for (i, ne) in newexp.iter_mut().enumerate() {
+                *ne = (exp.get(i).cloned().unwrap_or(0) + shift[i] as isize) as u64;
             }
-            _ => Err(format!("{} not allowed in polynomial", e)),
+            poly.append_monomial(c, &newexp);
         }
+
+        let (num, den) = LaurentPolynomial::new(poly, shift).to_fraction();
+        Ok((
+            Polynomial {
+                poly: AdaptivePolynomial::new(num),
+                varmap: varmap.clone(),
+                inv_varmap: inv_varmap.clone(),
+                varcount,
+            },
+            Polynomial {
+                poly: AdaptivePolynomial::new(den),
+                varmap,
+                inv_varmap,
+                varcount,
+            },
+        ))
+    }
+
+    /// Convert a rational function, such as `(1+x)^-2*y+1/x`, to a normalized
+    /// numerator and denominator.
+    pub fn from_rational(e: &Element) -> Result<(Polynomial, Polynomial), String> {
+        let one = Polynomial::new().cloned_one();
+        let (mut num, mut den) = match *e {
+            Element::RationalPolynomialCoefficient(_, ref p) => {
+                return Ok((p.0.clone(), p.1.clone()))
+            }
+            Element::Term(_, ref fs) => {
+                let (mut num, mut den) = (one.clone(), one);
+                for f in fs {
+                    let (mut num1, mut den1) = Polynomial::from_rational(f)?;
+                    rationalpolynomial_mul(&mut num, &mut den, &mut num1, &mut den1);
+                }
+                return Ok((num, den));
+            }
+            Element::SubExpr(_, ref ts) => {
+                let (mut num, mut den) = (Polynomial::new(), one);
+                for t in ts {
+                    let (mut num1, mut den1) = Polynomial::from_rational(t)?;
+                    rationalpolynomial_add(&mut num, &mut den, &mut num1, &mut den1);
+                }
+                return Ok((num, den));
+            }
+            Element::Pow(_, ref be) => {
+                let n = match be.1 {
+                    Element::Num(_, Number::SmallInt(n)) => n,
+                    _ => return Err(format!("{} does not have an integer exponent", e)),
+                };
+
+                let (mut b, mut bd) = Polynomial::from_rational(&be.0)?;
+                if n < 0 {
+                    if b.is_zero() {
+                        return Err(format!("Division by zero in {}", e));
+                    }
+                    mem::swap(&mut b, &mut bd);
+                }
+
+                // the numerator and denominator are coprime, so no gcd is needed
+                let (mut num, mut den) = (b.cloned_one(), bd.cloned_one());
+                for _ in 0..n.abs() {
+                    num = num * b.clone();
+                    den = den * bd.clone();
+                }
+                (num, den)
+            }
+            _ => Polynomial::from_laurent(e)?,
+        };
+
+        rationalpolynomial_normalize(&mut num, &mut den);
+        Ok((num, den))
     }
 
     pub fn cloned_one(&self) -> Polynomial {
         Polynomial {
-            poly: MultivariatePolynomial::from_constant_with_nvars(Number::one(), self.varcount),
+            poly: AdaptivePolynomial::from_constant_with_nvars(Number::one(), self.varcount),
             varmap: self.varmap.clone(),
             inv_varmap: self.inv_varmap.clone(),
             varcount: self.varcount,
@@ -172,18 +250,16 @@ impl Polynomial {
 
     pub fn to_expression(self) -> Element {
         let mut terms = vec![];
-        for v in self.poly.into_iter() {
+        for (t, c) in self.poly.coefficients().iter().enumerate() {
             let mut factors = vec![];
-            for (name, pow) in v.exponents.iter().enumerate() {
-                if *pow >= 1 {
-                    factors.push(Element::Var(
-                        self.inv_varmap[name],
-                        Number::SmallInt(*pow as isize),
-                    ));
+            for (name, x) in self.inv_varmap.iter().enumerate() {
+                let pow = self.poly.exponent(t, name);
+                if pow >= 1 {
+                    factors.push(Element::Var(*x, Number::SmallInt(pow as isize)));
                 }
             }
 
-            factors.push(Element::Num(false, v.coefficient.clone()));
+            factors.push(Element::Num(false, c.clone()));
             terms.push(Element::Term(true, factors));
         }
         Element::SubExpr(true, terms)
@@ -221,61 +297,46 @@ impl Polynomial {
 
         if varcount > self.varcount {
             // we need to reconstruct the exponent in `self`
-            let mut newexp = vec![0; varcount * self.poly.nterms];
-
-            for t in 0..self.poly.nterms {
-                newexp[t * varcount..t * varcount + self.varcount]
-                    .copy_from_slice(self.poly.exponents(t));
-            }
-
-            self.poly.exponents = newexp;
-            self.poly.nvars = varcount;
+            self.poly.grow_to(varcount - 1);
             self.varcount = varcount;
         }
 
         // reconstruct 'other' with correct monomial ordering
-        let mut newother = MultivariatePolynomial::with_nvars(varcount);
-        for t in 0..other.poly.nterms {
-            let mut newexp = vec![0; varcount];
-            for e in 0..other.varcount {
-                newexp[*map.get(&e).unwrap()] = other.poly.exponents(t)[e];
-            }
-            newother.append_monomial(other.poly.coefficients[t].clone(), &newexp);
-        }
-
+        let map: Vec<_> = (0..other.varcount).map(|e| map[&e]).collect();
         other.varmap = self.varmap.clone();
         other.inv_varmap = self.inv_varmap.clone();
-        other.poly = newother;
+        other.poly = other.poly.remap_variables(&map, varcount);
         other.varcount = varcount;
     }
 
     fn fmt_output(&self, f: &mut fmt::Formatter, var_info: &GlobalVarInfo) -> fmt::Result {
         let mut is_first_term = true;
-        for monomial in &self.poly {
-            if monomial.coefficient.is_zero() {
+        for (t, coefficient) in self.poly.coefficients().iter().enumerate() {
+            if coefficient.is_zero() {
                 continue;
             }
             let mut is_first_factor = true;
-            if monomial.coefficient.eq(&Number::one()) {
+            if coefficient.eq(&Number::one()) {
                 if !is_first_term {
                     write!(f, "+")?;
                 }
-            } else if monomial.coefficient.eq(&Number::one().neg()) {
+            } else if coefficient.eq(&Number::one().neg()) {
                 write!(f, "-")?;
             } else {
                 if is_first_term {
-                    write!(f, "{}", monomial.coefficient)?;
+                    write!(f, "{}", coefficient)?;
                 } else {
-                    if monomial.coefficient < &Number::zero() {
-                        write!(f, "{}", monomial.coefficient)?;
+                    if coefficient < &Number::zero() {
+                        write!(f, "{}", coefficient)?;
                     } else {
-                        write!(f, "+{}", monomial.coefficient)?;
+                        write!(f, "+{}", coefficient)?;
                     }
                 }
                 is_first_factor = false;
             }
             is_first_term = false;
-            for (i, e) in monomial.exponents.into_iter().enumerate() {
+            for i in 0..self.varcount {
+                let e = self.poly.exponent(t, i);
                 if e.is_zero() {
                     continue;
                 }
@@ -302,7 +363,7 @@ impl Polynomial {
     pub fn gcd(&mut self, b: &mut Polynomial) -> Polynomial {
         self.unify_varmaps(b);
         Polynomial {
-            poly: MultivariatePolynomial::gcd(&self.poly, &b.poly),
+            poly: AdaptivePolynomial::gcd(&self.poly, &b.poly),
             varmap: self.varmap.clone(),
             inv_varmap: self.inv_varmap.clone(),
             varcount: self.varcount.clone(),
@@ -329,6 +390,264 @@ impl Polynomial {
             },
         )
     }
+
+    /// Create a polynomial with the same variable map as `self`.
+    fn with_poly(&self, poly: AdaptivePolynomial<Number>) -> Polynomial {
+        Polynomial {
+            poly,
+            varmap: self.varmap.clone(),
+            inv_varmap: self.inv_varmap.clone(),
+            varcount: self.varcount,
+        }
+    }
+
+    /// Create the zero polynomial with the same variable map as `self`.
+    fn zero_with_varmap(&self) -> Polynomial {
+        self.with_poly(AdaptivePolynomial::with_nvars(self.varcount))
+    }
+
+    /// Get the coefficients of the polynomial in `x`, from the lowest to the highest
+    /// power. Returns `None` if `x` does not occur or if other variables occur.
+    pub fn to_univariate_coefficients(&self, x: VarName) -> Option<Vec<Number>> {
+        self.varmap
+            .get(&x)
+            .and_then(|&i| self.poly.to_univariate(i))
+            .map(|p| p.coefficients)
+    }
+
+    /// Convert to a polynomial with coefficients in Q(a), where `a` is the variable
+    /// with index `var` and is a root of `minimal_polynomial`.
+    fn to_algebraic(
+        &self,
+        var: usize,
+        minimal_polynomial: &Arc<Vec<Number>>,
+    ) -> MultivariatePolynomial<AlgebraicNumber, u64> {
+        let nvars = self.poly.nvars();
+        let mut r = MultivariatePolynomial::with_nvars(nvars);
+        for t in 0..self.poly.nterms() {
+            let mut e: Vec<u64> = (0..nvars).map(|v| self.poly.exponent(t, v)).collect();
+
+            // compute a^k by repeated squaring
+            let mut c = AlgebraicNumber::new(
+                vec![self.poly.coefficients()[t].clone()],
+                minimal_polynomial.clone(),
+            );
+            let mut b = AlgebraicNumber::generator(minimal_polynomial.clone());
+            while e[var] != 0 {
+                if e[var] & 1 != 0 {
+                    c = c * b.clone();
+                }
+                b = b.clone() * b;
+                e[var] >>= 1;
+            }
+
+            r.append_monomial(c, &e);
+        }
+        r
+    }
+
+    /// Convert a polynomial with coefficients in Q(a) back to a polynomial in which
+    /// `a` is the variable with index `var`.
+    fn with_algebraic(
+        &self,
+        p: &MultivariatePolynomial<AlgebraicNumber, u64>,
+        var: usize,
+    ) -> Polynomial {
+        let mut r = MultivariatePolynomial::with_nvars(p.nvars());
+        for t in p {
+            let mut e = t.exponents.to_vec();
+            for (k, c) in t.coefficient.coefficients.iter().enumerate() {
+                e[var] = k as u64;
+                r.append_monomial(c.clone(), &e);
+            }
+        }
+        self.with_poly(AdaptivePolynomial::new(r))
+    }
+
+    /// Replace variables by polynomials simultaneously. Variables that do not
+    /// occur in the polynomial are ignored.
+    pub fn substitute(&mut self, subs: &mut [(VarName, Polynomial)]) -> Polynomial {
+        // the first pass adds all variables to `self`, the second pass makes sure
+        // that all polynomials use the same variable map
+        for &mut (_, ref mut v) in subs.iter_mut() {
+            self.unify_varmaps(v);
+        }
+        for &mut (_, ref mut v) in subs.iter_mut() {
+            self.unify_varmaps(v);
+        }
+
+        let s: Vec<_> = subs
+            .iter()
+            .filter_map(|&(ref x, ref v)| self.varmap.get(x).map(|i| (*i, v.poly.clone())))
+            .collect();
+        self.with_poly(self.poly.substitute_all(&s))
+    }
+
+    /// Compute the Taylor shift `p(x + a)`.
+    pub fn taylor_shift(&self, x: VarName, a: &Number) -> Polynomial {
+        match self.varmap.get(&x) {
+            Some(&i) => {
+                let mut e = vec![0; self.varcount];
+                e[i] = 1;
+                let v = MultivariatePolynomial::from_monomial(Number::one(), e) + a.clone();
+                self.with_poly(self.poly.substitute_all(&[(i, AdaptivePolynomial::U8(v))]))
+            }
+            None => self.clone(),
+        }
+    }
+
+    /// Differentiate the polynomial with respect to `x`.
+    pub fn derivative(&self, x: VarName) -> Polynomial {
+        match self.varmap.get(&x) {
+            Some(&i) => self.with_poly(self.poly.derivative(i)),
+            None => self.zero_with_varmap(),
+        }
+    }
+
+    /// Compute the antiderivative with respect to `x`, with the integration
+    /// constant set to zero.
+    pub fn integrate(&self, x: VarName) -> Polynomial {
+        match self.varmap.get(&x) {
+            Some(&i) => self.with_poly(self.poly.integrate(i)),
+            None => {
+                let mut varmap = HashMap::new();
+                varmap.insert(x, 0);
+                let xp = Polynomial {
+                    poly: AdaptivePolynomial::U8(MultivariatePolynomial::from_monomial(
+                        Number::one(),
+                        vec![1],
+                    )),
+                    varmap,
+                    inv_varmap: vec![x],
+                    varcount: 1,
+                };
+                self.clone() * xp
+            }
+        }
+    }
+
+    /// Get the lowest power of `x` that occurs in the polynomial.
+    pub fn valuation(&self, x: VarName) -> u64 {
+        match self.varmap.get(&x) {
+            Some(&i) => self.poly.monomial_content()[i],
+            None => 0,
+        }
+    }
+
+    /// Remove all terms in which the power of `x` is larger than `max`.
+    pub fn truncate(&self, x: VarName, max: isize) -> Polynomial {
+        if max < 0 {
+            return self.zero_with_varmap();
+        }
+        let i = match self.varmap.get(&x) {
+            Some(&i) => i,
+            None => return self.clone(),
+        };
+
+        self.with_poly(self.poly.truncate(i, max as u64))
+    }
+
+    /// Split the polynomial into the coefficients of the powers of `x`,
+    /// starting at `x^0`.
+    fn coefficient_list(&self, x: VarName) -> Vec<Polynomial> {
+        let i = match self.varmap.get(&x) {
+            Some(&i) => i,
+            None if self.is_zero() => return vec![],
+            None => return vec![self.clone()],
+        };
+
+        self.poly
+            .coefficient_list(i)
+            .into_iter()
+            .map(|p| self.with_poly(p))
+            .collect()
+    }
+
+    /// Get the value of every variable of the polynomial from `values`.
+    fn point<T: Clone>(&self, values: &[(VarName, T)]) -> Result<Vec<T>, String> {
+        self.inv_varmap
+            .iter()
+            .map(|x| {
+                values
+                    .iter()
+                    .find(|v| v.0 == *x)
+                    .map(|v| v.1.clone())
+                    .ok_or_else(|| "Not all variables have a value".to_owned())
+            })
+            .collect()
+    }
+
+    /// Evaluate the polynomial in the finite field of size `p`.
+    pub fn evaluate_finite_field(
+        &self,
+        values: &[(VarName, ufield)],
+        p: ufield,
+    ) -> Result<ufield, String> {
+        let point: Vec<_> = self
+            .point(values)?
+            .into_iter()
+            .map(|v| FiniteField::new(v, p))
+            .collect();
+
+        let mut poly = MultivariatePolynomial::with_nvars(self.varcount);
+        let mut e = vec![0u64; self.varcount];
+        for (t, coefficient) in self.poly.coefficients().iter().enumerate() {
+            let c = number_mod_p(coefficient, p).ok_or_else(|| {
+                format!(
+                    "The denominator of {} is not invertible mod {}",
+                    coefficient, p
+                )
+            })?;
+            for (i, x) in e.iter_mut().enumerate() {
+                *x = self.poly.exponent(t, i);
+            }
+            poly.append_monomial(FiniteField::new(c, p), &e);
+        }
+
+        if poly.is_zero() {
+            return Ok(0);
+        }
+        Ok(poly.evaluate(&point).n)
+    }
+
+    /// Evaluate the polynomial in floating point arithmetic.
+    pub fn evaluate_float(&self, values: &[(VarName, f64)]) -> Result<f64, String> {
+        let point = self.point(values)?;
+        Ok(self
+            .poly
+            .coefficients()
+            .iter()
+            .enumerate()
+            .map(|(t, c)| {
+                point.iter().enumerate().fold(c.to_f64(), |acc, (i, x)| {
+                    acc * x.powi(self.poly.exponent(t, i) as i32)
+                })
+            })
+            .sum())
+    }
+
+    /// Evaluate a univariate polynomial at many points.
+    pub fn evaluate_multipoint(&self, points: &[Number]) -> Result<Vec<Number>, String> {
+        if self.poly.is_constant() {
+            let c = self
+                .poly
+                .coefficients()
+                .first()
+                .cloned()
+                .unwrap_or_else(Number::zero);
+            return Ok(vec![c; points.len()]);
+        }
+
+        let last = self.poly.nterms() - 1;
+        let x = (0..self.varcount)
+            .position(|i| self.poly.exponent(last, i) > 0)
+            .unwrap();
+        let up = self
+            .poly
+            .to_univariate(x)
+            .ok_or_else(|| "The polynomial is not univariate".to_owned())?;
+        Ok(up.evaluate_multipoint(points))
+    }
 }
 
 impl Hash for Polynomial {
@@ -347,7 +666,7 @@ impl Mul for Polynomial {
         self.unify_varmaps(&mut other);
 
         Polynomial {
-            poly: self.poly * &other.poly,
+            poly: self.poly * other.poly,
             varmap: self.varmap,
             inv_varmap: self.inv_varmap,
             varcount: self.varcount,
@@ -359,7 +678,12 @@ impl Mul<Number> for Polynomial {
     type Output = Self;
 
     fn mul(mut self, other: Number) -> Self::Output {
-        for c in &mut self.poly.coefficients {
+        if other.is_zero() {
+            self.poly = AdaptivePolynomial::with_nvars(self.poly.nvars());
+            return self;
+        }
+
+        for c in self.poly.coefficients_mut() {
             *c *= other.clone();
         }
         self
@@ -432,7 +756,7 @@ impl Zero for Polynomial {
 
     #[inline]
     fn is_zero(&self) -> bool {
-        self.poly.nterms == 0
+        self.poly.is_zero()
     }
 }
 
@@ -456,7 +780,7 @@ impl Polynomial {
             v.push(buffer.read_u32::<LittleEndian>().unwrap());
         }
 
-        let p = MultivariatePolynomial::deserialize(buffer)?;
+        let p = AdaptivePolynomial::deserialize(buffer)?;
         let mut hm = HashMap::new();
         for (i, x) in v.iter().enumerate() {
             hm.insert(*x, i);
@@ -490,7 +814,12 @@ pub fn rationalpolynomial_normalize(num: &mut Polynomial, den: &mut Polynomial)
     // normalize all the coefficients by the lcm
     let mut norm = Number::one();
 
-    for x in num.poly.coefficients.iter().chain(&den.poly.coefficients) {
+    for x in num
+        .poly
+        .coefficients()
+        .iter()
+        .chain(den.poly.coefficients())
+    {
         match x {
             Number::SmallRat(_, d) => {
                 norm = (norm.clone() / GCD::gcd(norm, Number::SmallInt(*d))) * Number::SmallInt(*d);
@@ -509,7 +838,12 @@ pub fn rationalpolynomial_normalize(num: &mut Polynomial, den: &mut Polynomial)
     #[cfg(debug_assertions)]
     {
         // confirm that all fractions are gone
-        for x in num.poly.coefficients.iter().chain(&den.poly.coefficients) {
+        for x in num
+            .poly
+            .coefficients()
+            .iter()
+            .chain(den.poly.coefficients())
+        {
             match x {
                 Number::SmallRat(..) | Number::BigInt(..) => panic!(
                     "Fractions left in polynomial: {}; normalization: {}",
@@ -616,6 +950,275 @@ pub fn rationalpolynomial_div(
     rationalpolynomial_mul(num, den, den1, num1)
 }
 
+/// Reduce a rational polynomial num/den with coefficients in the algebraic number
+/// field Q(a), where `a` is the variable `x` with the monic minimal polynomial
+/// `minimal_polynomial`. The powers of `a` are reduced, the gcd over Q(a) is divided
+/// out and the denominator is made monic over Q(a), so that equal fractions have
+/// the same representation.
+/// The function returns false iff the denominator is zero.
+pub fn rationalpolynomial_algebraic(
+    num: &mut Polynomial,
+    den: &mut Polynomial,
+    x: VarName,
+    minimal_polynomial: &Arc<Vec<Number>>,
+) -> bool {
+    num.unify_varmaps(den);
+
+    let var = match num.varmap.get(&x) {
+        Some(&i) => i,
+        None => return true,
+    };
+
+    let mut n = num.to_algebraic(var, minimal_polynomial);
+    let mut d = den.to_algebraic(var, minimal_polynomial);
+    if d.is_zero() {
+        return false;
+    }
+
+    let g = MultivariatePolynomial::gcd(&n, &d);
+    n = n.divmod(&g).0;
+    d = d.divmod(&g).0;
+
+    let inv = AlgebraicNumber::one() / d.lcoeff();
+    *num = num.with_algebraic(&(n * inv.clone()), var);
+    *den = den.with_algebraic(&(d * inv), var);
+
+    // clear the rational denominators
+    rationalpolynomial_normalize(num, den);
+    true
+}
+
+/// Differentiate a rational polynomial num/den with respect to `x`,
+/// using the quotient rule.
+/// The function returns true iff the result is zero.
+pub fn rationalpolynomial_derivative(
+    num: &mut Polynomial,
+    den: &mut Polynomial,
+    x: VarName,
+) -> bool {
+    let dnum = num.derivative(x);
+    let dden = den.derivative(x);
+
+    if dden.is_zero() {
+        *num = dnum;
+    } else {
+        *num = dnum * den.clone() - mem::replace(num, Polynomial::new()) * dden;
+        *den = den.clone() * den.clone();
+    }
+
+    rationalpolynomial_normalize(num, den);
+    num.is_zero()
+}
+
+/// Truncate a rational polynomial num/den, seen as a Laurent series in `x`,
+/// beyond the power `order`: all terms of the numerator that only contribute
+/// to higher powers are removed.
+/// The function returns true iff the result is zero.
+pub fn rationalpolynomial_truncate(
+    num: &mut Polynomial,
+    den: &mut Polynomial,
+    x: VarName,
+    order: isize,
+) -> bool {
+    let t = num.truncate(x, order + den.valuation(x) as isize);
+    if t.poly.nterms() != num.poly.nterms() {
+        *num = t;
+        if num.is_zero() {
+            return true;
+        }
+        rationalpolynomial_normalize(num, den);
+    }
+    num.is_zero()
+}
+
+/// Expand a rational polynomial num/den into a Laurent series in `x`, up to and
+/// including the power `order`. The non-zero coefficients are returned
+/// as rational polynomials, together with their power of `x`.
+pub fn rationalpolynomial_series(
+    num: &Polynomial,
+    den: &Polynomial,
+    x: VarName,
+    order: isize,
+) -> Vec<(isize, Polynomial, Polynomial)> {
+    let (mut num, mut den) = (num.clone(), den.clone());
+    num.unify_varmaps(&mut den);
+
+    let n = num.coefficient_list(x);
+    let d = den.coefficient_list(x);
+
+    // factor out the lowest powers of x
+    let (u, v) = match (
+        n.iter().position(|c| !c.is_zero()),
+        d.iter().position(|c| !c.is_zero()),
+    ) {
+        (Some(u), Some(v)) => (u, v),
+        _ => return vec![],
+    };
+    let (n, d) = (&n[u..], &d[v..]);
+    let shift = u as isize - v as isize;
+    if order < shift {
+        return vec![];
+    }
+
+    // the jth coefficient of n/d is p_j/d_0^(j+1), with
+    // p_j = n_j*d_0^j - sum_{i=1}^j d_i*p_(j-i)*d_0^(i-1)
+    let mut p: Vec<Polynomial> = vec![];
+    let mut d0pow = vec![d[0].cloned_one()];
+    let mut res = vec![];
+    for j in 0..(order - shift) as usize + 1 {
+        if j > 0 {
+            let last = d0pow[j - 1].clone() * d[0].clone();
+            d0pow.push(last);
+        }
+
+        let mut pj = match n.get(j) {
+            Some(c) => c.clone() * d0pow[j].clone(),
+            None => d[0].zero_with_varmap(),
+        };
+        for i in 1..d.len().min(j + 1) {
+            pj = pj - d[i].clone() * p[j - i].clone() * d0pow[i - 1].clone();
+        }
+
+        if !pj.is_zero() {
+            let mut cnum = pj.clone();
+            let mut cden = d0pow[j].clone() * d[0].clone();
+            rationalpolynomial_normalize(&mut cnum, &mut cden);
+            res.push((shift + j as isize, cnum, cden));
+        }
+        p.push(pj);
+    }
+
+    res
+}
+
+/// Solve the linear system `a * x = b`, where every entry is a rational
+/// polynomial `(num, den)`. Systems with constant entries are solved over Q,
+/// others are solved over Q(y), with `y` the variables in the entries,
+/// using the dense interpolation of `solve_rational_polynomial`.
+pub fn rationalpolynomial_solve(
+    a: &mut [Vec<(Polynomial, Polynomial)>],
+    b: &mut [(Polynomial, Polynomial)],
+) -> Result<Vec<(Polynomial, Polynomial)>, RationalSolverError> {
+    // bring all entries to the same variable map
+    let mut reference = Polynomial::new();
+    for _ in 0..2 {
+        for &mut (ref mut num, ref mut den) in
+            a.iter_mut().flat_map(|r| r.iter_mut()).chain(b.iter_mut())
+        {
+            rationalpolynomial_normalize(num, den);
+            reference.unify_varmaps(num);
+            reference.unify_varmaps(den);
+        }
+    }
+
+    let to_polynomial = |poly| Polynomial {
+        poly,
+        varmap: reference.varmap.clone(),
+        inv_varmap: reference.inv_varmap.clone(),
+        varcount: reference.varcount,
+    };
+
+    let is_constant = a
+        .iter()
+        .flat_map(|r| r.iter())
+        .chain(b.iter())
+        .all(|&(ref num, ref den)| num.poly.is_constant() && den.poly.is_constant());
+
+    if is_constant {
+        let to_number = |&(ref num, ref den): &(Polynomial, Polynomial)| {
+            if num.is_zero() {
+                Number::zero()
+            } else {
+                num.poly.coefficients()[0].clone() / den.poly.coefficients()[0].clone()
+            }
+        };
+
+        let am: Vec<Vec<Number>> = a
+            .iter()
+            .map(|r| r.iter().map(|x| to_number(x)).collect())
+            .collect();
+        let bm: Vec<Number> = b.iter().map(|x| to_number(x)).collect();
+
+        return Ok(solve_rational(&am, &bm)?
+            .into_iter()
+            .map(|x| {
+                let mut num = to_polynomial(AdaptivePolynomial::from_constant_with_nvars(
+                    x,
+                    reference.varcount,
+                ));
+                let mut den = to_polynomial(AdaptivePolynomial::from_constant_with_nvars(
+                    Number::one(),
+                    reference.varcount,
+                ));
+                rationalpolynomial_normalize(&mut num, &mut den);
+                (num, den)
+            })
+            .collect());
+    }
+
+    // multiply every row by the lcm of its denominators
+    // the dense solver works with `u32` exponents, which any system that
+    // it can solve in practice fits in
+    let mut am = vec![];
+    let mut bm = vec![];
+    for (row, bb) in a.iter().zip(b.iter()) {
+        let mut lcm = bb.1.poly.clone();
+        for &(_, ref den) in row {
+            let g = AdaptivePolynomial::gcd(&lcm, &den.poly);
+            lcm = lcm.divmod(&g).0 * den.poly.clone();
+        }
+
+        am.push(
+            row.iter()
+                .map(|&(ref num, ref den)| (num.poly.clone() * lcm.divmod(&den.poly).0).to_poly())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(RationalSolverError::DegreeTooHigh)?,
+        );
+        bm.push(
+            (bb.0.poly.clone() * lcm.divmod(&bb.1.poly).0)
+                .to_poly()
+                .ok_or(RationalSolverError::DegreeTooHigh)?,
+        );
+    }
+
+    Ok(solve_rational_polynomial(&am, &bm)?
+        .into_iter()
+        .map(|(num, den)| {
+            let mut num = to_polynomial(AdaptivePolynomial::new(num));
+            let mut den = to_polynomial(AdaptivePolynomial::new(den));
+            rationalpolynomial_normalize(&mut num, &mut den);
+            (num, den)
+        })
+        .collect())
+}
+
+/// Reconstruct a rational polynomial in the variables `vars` from a black box `f`,
+/// which evaluates the function at a point modulo a prime.
+/// Returns `None` if the reconstruction failed.
+pub fn rationalpolynomial_reconstruct<F: Fn(&[ufield], ufield) -> Option<ufield>>(
+    f: F,
+    vars: &[VarName],
+) -> Option<(Polynomial, Polynomial)> {
+    let (num, den) = reconstruct(f, vars.len())?;
+
+    let mut varmap = HashMap::new();
+    for (i, v) in vars.iter().enumerate() {
+        varmap.insert(*v, i);
+    }
+
+    let to_polynomial = |poly| Polynomial {
+        poly,
+        varmap: varmap.clone(),
+        inv_varmap: vars.to_vec(),
+        varcount: vars.len(),
+    };
+
+    let mut num = to_polynomial(AdaptivePolynomial::new(num));
+    let mut den = to_polynomial(AdaptivePolynomial::new(den));
+    rationalpolynomial_normalize(&mut num, &mut den);
+    Some((num, den))
+}
+
 #[test]
 fn serialize() {
     let mut a = MultivariatePolynomial::from_monomial(Number::SmallInt(100), vec![0, 0]);
@@ -630,14 +1233,14 @@ fn serialize() {
     let im = vec![1, 2];
 
     let ap = Polynomial {
-        poly: a,
+        poly: AdaptivePolynomial::new(a),
         varmap: m.clone(),
         inv_varmap: im.clone(),
         varcount: 2,
     };
 
     let bp = Polynomial {
-        poly: b,
+        poly: AdaptivePolynomial::new(b),
         varmap: m.clone(),
         inv_varmap: im.clone(),
         varcount: 2,
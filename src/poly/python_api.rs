@@ -1,8 +1,15 @@
-use cpython::PyResult;
+use cpython::exc;
+use cpython::{
+    ObjectProtocol, PyDict, PyErr, PyFloat, PyLong, PyObject, PyResult, PyTuple, Python,
+    PythonObject, ToPyObject,
+};
 use number::Number;
 use poly::polynomial;
 use poly::polynomial::PolyPrinter;
+use poly::raw::zp::ufield;
+use rug::{Float, Integer, Rational};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::{Add, Div, Mul, Neg, Sub};
 use std::str::FromStr;
 use structure;
@@ -29,9 +36,105 @@ py_module_initializer!(reform, initreform, PyInit_reform, |py, m| {
     m.add_class::<Polynomial>(py)?;
     m.add_class::<RationalPolynomial>(py)?;
     m.add_class::<Expression>(py)?;
+    m.add(
+        py,
+        "reconstruct",
+        py_fn!(py, reconstruct(f: PyObject, vars: Vec<String>, var_info: &VarInfo)),
+    )?;
     Ok(())
 });
 
+/// Reconstruct a rational polynomial in `vars` from a function `f(point, p)`
+/// that evaluates it modulo the prime `p`. The function should return `None`
+/// if it is undefined at the point.
+fn reconstruct(
+    py: Python,
+    f: PyObject,
+    vars: Vec<String>,
+    var_info: &VarInfo,
+) -> PyResult<RationalPolynomial> {
+    let names: Vec<_> = vars
+        .iter()
+        .map(|v| var_info.var_info(py).borrow_mut().get_name(v))
+        .collect();
+
+    // store the first Python error, since the black box cannot return it
+    let error = RefCell::new(None);
+    let res = polynomial::rationalpolynomial_reconstruct(
+        |point: &[ufield], p: ufield| {
+            if error.borrow().is_some() {
+                return None;
+            }
+
+            match f
+                .call(py, (point.to_vec(), p), None)
+                .and_then(|r| r.extract::<Option<i64>>(py))
+            {
+                Ok(r) => r.map(|x| (((x % p as i64) + p as i64) % p as i64) as ufield),
+                Err(e) => {
+                    *error.borrow_mut() = Some(e);
+                    None
+                }
+            }
+        },
+        &names,
+    );
+
+    if let Some(e) = error.into_inner() {
+        return Err(e);
+    }
+
+    let (num, den) = res.ok_or_else(|| {
+        PyErr::new::<exc::ValueError, _>(py, "Could not reconstruct the function")
+    })?;
+
+    let vi = var_info.var_info(py).borrow().clone();
+    let np = Polynomial::create_instance(py, RefCell::new(num), vi.clone())?;
+    let dp = Polynomial::create_instance(py, RefCell::new(den), vi)?;
+    RationalPolynomial::create_instance(py, np, dp)
+}
+
+/// Convert a Python int, float or `Fraction` to a number.
+/// Floats get a precision of `prec` bits.
+fn to_number(py: Python, obj: &PyObject, prec: u32) -> PyResult<Number> {
+    if let Ok(f) = obj.cast_as::<PyFloat>(py) {
+        return Ok(Number::Float(Box::new(Float::with_val(prec, f.value(py)))));
+    }
+
+    // integers and fractions are converted exactly through their string representation
+    let s = obj.str(py)?.to_string(py)?.into_owned();
+    let mut parts = s.splitn(2, '/');
+    let num = parts.next().and_then(|n| n.trim().parse::<Integer>().ok());
+    let den = match parts.next() {
+        Some(d) => d.trim().parse::<Integer>().ok(),
+        None => Some(Integer::from(1)),
+    };
+
+    match (num, den) {
+        (Some(n), Some(ref d)) if *d != 0 => {
+            Ok(Number::BigRat(Box::new(Rational::from((n, d)))).normalized())
+        }
+        _ => Err(PyErr::new::<exc::ValueError, _>(
+            py,
+            format!("Cannot convert {} to a number", s),
+        )),
+    }
+}
+
+/// Convert a number to a Python int, `Fraction`, float or complex number.
+fn from_number(py: Python, n: &Number) -> PyResult<PyObject> {
+    match *n {
+        Number::SmallInt(i) => Ok((i as i64).to_py_object(py).into_object()),
+        Number::BigInt(_) => py.get_type::<PyLong>().call(py, (n.to_string(),), None),
+        Number::Float(_) => Ok(n.to_f64().to_py_object(py).into_object()),
+        Number::Complex(ref c) => {
+            let (re, im) = (from_number(py, &c.0)?, from_number(py, &c.1)?);
+            py.import("builtins")?.call(py, "complex", (re, im), None)
+        }
+        _ => py.import("fractions")?.call(py, "Fraction", (n.to_string(),), None),
+    }
+}
+
 py_class!(class VarInfo |py| {
     data var_info: RefCell<structure::VarInfo>;
 
@@ -109,6 +212,92 @@ py_class!(class Polynomial |py| {
                 clone_varmap(&self.var_info(py), &other.var_info(py)))
         }
     }
+
+    def substitute(&self, vars: Vec<String>, values: Vec<Polynomial>) -> PyResult<Polynomial> {
+        if vars.len() != values.len() {
+            return Err(PyErr::new::<exc::ValueError, _>(py, "The number of variables and values differ"));
+        }
+
+        let mut var_info = self.var_info(py).clone();
+        let mut subs = vec![];
+        for (x, v) in vars.iter().zip(&values) {
+            if let Some(name) = self.var_info(py).global_info.find_name(x) {
+                subs.push((name, v.poly(py).borrow().clone()));
+            }
+            var_info = clone_varmap(&var_info, &v.var_info(py));
+        }
+
+        let r = self.poly(py).borrow().clone().substitute(&mut subs);
+        Polynomial::create_instance(py, RefCell::new(r), var_info)
+    }
+
+    def taylor_shift(&self, x: &str, a: i64) -> PyResult<Polynomial> {
+        let r = match self.var_info(py).global_info.find_name(x) {
+            Some(name) => self.poly(py).borrow().taylor_shift(name, &Number::SmallInt(a as isize)),
+            None => self.poly(py).borrow().clone(),
+        };
+        Polynomial::create_instance(py, RefCell::new(r), self.var_info(py).clone())
+    }
+
+    def derivative(&self, x: &str) -> PyResult<Polynomial> {
+        let r = match self.var_info(py).global_info.find_name(x) {
+            Some(name) => self.poly(py).borrow().derivative(name),
+            None => polynomial::Polynomial::new(),
+        };
+        Polynomial::create_instance(py, RefCell::new(r), self.var_info(py).clone())
+    }
+
+    def integrate(&self, x: &str) -> PyResult<Polynomial> {
+        // the integration variable may be new
+        let mut var_info = self.var_info(py).clone();
+        let name = var_info.get_name(x);
+        let r = self.poly(py).borrow().integrate(name);
+        Polynomial::create_instance(py, RefCell::new(r), var_info)
+    }
+
+    def real_roots(&self, width: Option<(i64, i64)> = None) -> PyResult<Vec<(Polynomial, Polynomial)>> {
+        let eps = match width {
+            Some((n, d)) if n > 0 && d > 0 => Some(Number::SmallRat(n as isize, d as isize).normalized()),
+            Some(_) => return Err(PyErr::new::<exc::ValueError, _>(py, "The width should be positive")),
+            None => None,
+        };
+
+        let roots = self.poly(py).borrow().poly.to_wide().real_roots(eps.as_ref())
+            .ok_or_else(|| PyErr::new::<exc::ValueError, _>(py, "Polynomial is not univariate"))?;
+
+        let one = self.poly(py).borrow().cloned_one();
+        roots.into_iter().map(|(l, u)| Ok((
+            Polynomial::create_instance(py, RefCell::new(one.clone() * l), self.var_info(py).clone())?,
+            Polynomial::create_instance(py, RefCell::new(one.clone() * u), self.var_info(py).clone())?,
+        ))).collect()
+    }
+
+    def complex_roots(&self, prec: u32 = 53) -> PyResult<Vec<(f64, f64)>> {
+        let roots = self.poly(py).borrow().poly.to_wide().complex_roots(prec)
+            .ok_or_else(|| PyErr::new::<exc::ValueError, _>(py, "Polynomial is not univariate"))?;
+        Ok(roots.into_iter().map(|(re, im)| (re.to_f64(), im.to_f64())).collect())
+    }
+
+    def evaluate_mod(&self, vars: Vec<String>, values: Vec<ufield>, p: ufield) -> PyResult<ufield> {
+        let point: Vec<_> = vars.iter().zip(values).filter_map(|(x, v)|
+            self.var_info(py).global_info.find_name(x).map(|name| (name, v))).collect();
+        self.poly(py).borrow().evaluate_finite_field(&point, p).map_err(|e| PyErr::new::<exc::ValueError, _>(py, e))
+    }
+
+    def evaluate_float(&self, vars: Vec<String>, values: Vec<f64>) -> PyResult<f64> {
+        let point: Vec<_> = vars.iter().zip(values).filter_map(|(x, v)|
+            self.var_info(py).global_info.find_name(x).map(|name| (name, v))).collect();
+        self.poly(py).borrow().evaluate_float(&point).map_err(|e| PyErr::new::<exc::ValueError, _>(py, e))
+    }
+
+    def evaluate_multipoint(&self, points: Vec<i64>) -> PyResult<Vec<Polynomial>> {
+        let points: Vec<_> = points.into_iter().map(|x| Number::SmallInt(x as isize)).collect();
+        let values = self.poly(py).borrow().evaluate_multipoint(&points)
+            .map_err(|e| PyErr::new::<exc::ValueError, _>(py, e))?;
+
+        let one = self.poly(py).borrow().cloned_one();
+        values.into_iter().map(|v| Polynomial::create_instance(py, RefCell::new(one.clone() * v), self.var_info(py).clone())).collect()
+    }
 });
 
 py_class!(class RationalPolynomial |py| {
@@ -200,6 +389,24 @@ py_class!(class RationalPolynomial |py| {
         RationalPolynomial::create_instance(py, num, den)
     }
 
+    def derivative(&self, x: &str) -> PyResult<RationalPolynomial> {
+        let num = self.num(py).__copy__(py).unwrap();
+        let den = self.den(py).__copy__(py).unwrap();
+
+        let name = self.num(py).var_info(py).global_info.find_name(x)
+            .or_else(|| self.den(py).var_info(py).global_info.find_name(x));
+        if let Some(name) = name {
+            polynomial::rationalpolynomial_derivative(&mut num.poly(py).borrow_mut(),
+                &mut den.poly(py).borrow_mut(), name);
+        } else {
+            let one = den.poly(py).borrow().cloned_one();
+            *num.poly(py).borrow_mut() = polynomial::Polynomial::new();
+            *den.poly(py).borrow_mut() = one;
+        }
+
+        RationalPolynomial::create_instance(py, num, den)
+    }
+
 });
 
 py_class!(class Expression |py| {
@@ -266,6 +473,53 @@ py_class!(class Expression |py| {
         Expression::create_instance(py, RefCell::new(r), self.var_info(py).clone())
     }
 
+    def evaluate(&self, values: &PyDict, functions: Option<PyDict> = None, prec: u32 = 53) -> PyResult<PyObject> {
+        let global_info = &self.var_info(py).global_info;
+
+        let mut vals = HashMap::new();
+        for (k, v) in values.items(py) {
+            if let Some(name) = global_info.find_name(&k.extract::<String>(py)?) {
+                vals.insert(name, to_number(py, &v, prec)?);
+            }
+        }
+
+        let mut funcs = HashMap::new();
+        if let Some(functions) = functions {
+            for (k, f) in functions.items(py) {
+                if let Some(name) = global_info.find_name(&k.extract::<String>(py)?) {
+                    funcs.insert(name, f);
+                }
+            }
+        }
+
+        // store the first Python error, since the callback cannot return it
+        let error = RefCell::new(None);
+        let res = self.expr(py).borrow().evaluate(&vals, &|name, args: &[Number]| {
+            if error.borrow().is_some() {
+                return None;
+            }
+
+            let f = funcs.get(&name)?;
+            match args.iter().map(|a| from_number(py, a)).collect::<PyResult<Vec<_>>>()
+                .and_then(|a| f.call(py, PyTuple::new(py, &a), None))
+                .and_then(|r| to_number(py, &r, prec))
+            {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    *error.borrow_mut() = Some(e);
+                    None
+                }
+            }
+        }, prec);
+
+        if let Some(e) = error.into_inner() {
+            return Err(e);
+        }
+
+        let n = res.map_err(|e| PyErr::new::<exc::ValueError, _>(py, e))?;
+        from_number(py, &n)
+    }
+
     def id(&self, lhs: &str, rhs: &str, var_info: &VarInfo) -> PyResult<Expression> {
         let mut lhs = Element::<String>::from_str(lhs).unwrap().to_element(&mut var_info.var_info(py).borrow_mut());
         lhs.normalize_inplace(&var_info.var_info(py).borrow().global_info);
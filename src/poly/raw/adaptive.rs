@@ -0,0 +1,482 @@
+//! Polynomials that store their exponents in the smallest integer type that fits,
+//! and widen them automatically when an operation overflows.
+
+use num_traits::cast::ToPrimitive;
+use num_traits::{One, Zero};
+use number::Number;
+use poly::exponent::Exponent;
+use poly::raw::gcd::PolynomialGCD;
+use poly::raw::order::{LexOrder, MonomialOrder};
+use poly::raw::univariate::UnivariatePolynomial;
+use poly::raw::MultivariatePolynomial;
+use poly::ring::Ring;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A multivariate polynomial with `u8`, `u16`, `u32` or `u64` exponents.
+/// Small exponents pack more variables into a machine word, which speeds up
+/// the comparison of monomials and reduces memory usage.
+#[derive(Clone, Debug)]
+pub enum AdaptivePolynomial<R: Ring, O: MonomialOrder = LexOrder> {
+    U8(MultivariatePolynomial<R, u8, O>),
+    U16(MultivariatePolynomial<R, u16, O>),
+    U32(MultivariatePolynomial<R, u32, O>),
+    U64(MultivariatePolynomial<R, u64, O>),
+}
+
+/// Evaluate `$e` with `$p` bound to the polynomial in `$a`, whatever
+/// the exponent type.
+macro_rules! dispatch {
+    ($a:expr, $p:pat => $e:expr) => {
+        match $a {
+            AdaptivePolynomial::U8($p) => $e,
+            AdaptivePolynomial::U16($p) => $e,
+            AdaptivePolynomial::U32($p) => $e,
+            AdaptivePolynomial::U64($p) => $e,
+        }
+    };
+}
+
+impl<R: Ring> AdaptivePolynomial<R> {
+    /// Create the zero polynomial with `nvars` variables.
+    pub fn with_nvars(nvars: usize) -> AdaptivePolynomial<R> {
+        AdaptivePolynomial::U8(MultivariatePolynomial::with_nvars(nvars))
+    }
+
+    /// Create a constant polynomial with `nvars` variables.
+    pub fn from_constant_with_nvars(constant: R, nvars: usize) -> AdaptivePolynomial<R> {
+        AdaptivePolynomial::U8(MultivariatePolynomial::from_constant_with_nvars(
+            constant, nvars,
+        ))
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> AdaptivePolynomial<R, O> {
+    /// Store the polynomial using the smallest exponent type that fits.
+    pub fn new<E: Exponent>(poly: MultivariatePolynomial<R, E, O>) -> AdaptivePolynomial<R, O> {
+        let max = poly
+            .exponents
+            .iter()
+            .map(|e| e.to_u64().unwrap())
+            .max()
+            .unwrap_or(0);
+
+        if max <= u8::max_value() as u64 {
+            AdaptivePolynomial::U8(poly.convert_exponents().unwrap())
+        } else if max <= u16::max_value() as u64 {
+            AdaptivePolynomial::U16(poly.convert_exponents().unwrap())
+        } else if max <= u32::max_value() as u64 {
+            AdaptivePolynomial::U32(poly.convert_exponents().unwrap())
+        } else {
+            AdaptivePolynomial::U64(poly.convert_exponents().unwrap())
+        }
+    }
+
+    /// Convert to a polynomial with `u32` exponents.
+    /// Returns `None` if an exponent does not fit in 32 bits.
+    pub fn to_poly(&self) -> Option<MultivariatePolynomial<R, u32, O>> {
+        dispatch!(*self, ref p => p.convert_exponents())
+    }
+
+    /// Convert to a polynomial with `u64` exponents, which can hold
+    /// the exponents of every width.
+    pub fn to_wide(&self) -> MultivariatePolynomial<R, u64, O> {
+        dispatch!(*self, ref p => p.convert_exponents()).unwrap()
+    }
+
+    /// The number of bits of an exponent.
+    pub fn exponent_bits(&self) -> u32 {
+        match *self {
+            AdaptivePolynomial::U8(..) => 8,
+            AdaptivePolynomial::U16(..) => 16,
+            AdaptivePolynomial::U32(..) => 32,
+            AdaptivePolynomial::U64(..) => 64,
+        }
+    }
+
+    /// Widen the exponents such that they have at least `bits` bits.
+    pub fn widen_to(self, bits: u32) -> AdaptivePolynomial<R, O> {
+        match self {
+            AdaptivePolynomial::U8(p) if bits > 8 => {
+                AdaptivePolynomial::U16(p.convert_exponents().unwrap()).widen_to(bits)
+            }
+            AdaptivePolynomial::U16(p) if bits > 16 => {
+                AdaptivePolynomial::U32(p.convert_exponents().unwrap()).widen_to(bits)
+            }
+            AdaptivePolynomial::U32(p) if bits > 32 => {
+                AdaptivePolynomial::U64(p.convert_exponents().unwrap())
+            }
+            x => x,
+        }
+    }
+
+    /// Widen the exponents of `self` and `other` to the same type.
+    fn unify_exponents(
+        self,
+        other: AdaptivePolynomial<R, O>,
+    ) -> (AdaptivePolynomial<R, O>, AdaptivePolynomial<R, O>) {
+        let bits = self.exponent_bits().max(other.exponent_bits());
+        (self.widen_to(bits), other.widen_to(bits))
+    }
+
+    /// Returns the number of terms in the polynomial.
+    pub fn nterms(&self) -> usize {
+        dispatch!(*self, ref p => p.nterms)
+    }
+
+    /// Returns the number of variables in the polynomial.
+    pub fn nvars(&self) -> usize {
+        dispatch!(*self, ref p => p.nvars)
+    }
+
+    /// Returns true if the polynomial is constant.
+    pub fn is_constant(&self) -> bool {
+        dispatch!(*self, ref p => p.is_constant())
+    }
+
+    /// The coefficients of the terms, in the order of the terms.
+    pub fn coefficients(&self) -> &[R] {
+        dispatch!(*self, ref p => &p.coefficients)
+    }
+
+    /// The coefficients of the terms, in the order of the terms.
+    pub fn coefficients_mut(&mut self) -> &mut [R] {
+        dispatch!(*self, ref mut p => &mut p.coefficients)
+    }
+
+    /// Grow the exponent list so the variable index fits in.
+    pub fn grow_to(&mut self, var: usize) {
+        dispatch!(*self, ref mut p => p.grow_to(var))
+    }
+
+    /// Divide two polynomials, returning the quotient and the remainder.
+    pub fn divmod(
+        &self,
+        div: &AdaptivePolynomial<R, O>,
+    ) -> (AdaptivePolynomial<R, O>, AdaptivePolynomial<R, O>) {
+        // division cannot overflow the exponents
+        match (self, div) {
+            (&AdaptivePolynomial::U8(ref a), &AdaptivePolynomial::U8(ref b)) => {
+                let (q, r) = a.divmod(b);
+                (AdaptivePolynomial::U8(q), AdaptivePolynomial::U8(r))
+            }
+            (&AdaptivePolynomial::U16(ref a), &AdaptivePolynomial::U16(ref b)) => {
+                let (q, r) = a.divmod(b);
+                (AdaptivePolynomial::U16(q), AdaptivePolynomial::U16(r))
+            }
+            (&AdaptivePolynomial::U32(ref a), &AdaptivePolynomial::U32(ref b)) => {
+                let (q, r) = a.divmod(b);
+                (AdaptivePolynomial::U32(q), AdaptivePolynomial::U32(r))
+            }
+            (&AdaptivePolynomial::U64(ref a), &AdaptivePolynomial::U64(ref b)) => {
+                let (q, r) = a.divmod(b);
+                (AdaptivePolynomial::U64(q), AdaptivePolynomial::U64(r))
+            }
+            _ => {
+                let (a, b) = self.clone().unify_exponents(div.clone());
+                a.divmod(&b)
+            }
+        }
+    }
+
+    /// Get the exponent of the variable `v` in the term `t`.
+    pub fn exponent(&self, t: usize, v: usize) -> u64 {
+        dispatch!(*self, ref p => p.exponents(t)[v].to_u64().unwrap())
+    }
+
+    /// Get the monomial content: the lowest power of every variable
+    /// that occurs in all terms.
+    pub fn monomial_content(&self) -> Vec<u64> {
+        dispatch!(*self, ref p => p
+            .monomial_content()
+            .iter()
+            .map(|e| e.to_u64().unwrap())
+            .collect())
+    }
+
+    /// Convert to a univariate polynomial in `x`, if it is the only variable
+    /// that occurs.
+    pub fn to_univariate(&self, x: usize) -> Option<UnivariatePolynomial<R>> {
+        dispatch!(*self, ref p => UnivariatePolynomial::from_multivariate(p, x))
+    }
+
+    /// Differentiate the polynomial with respect to the variable `x`.
+    pub fn derivative(&self, x: usize) -> AdaptivePolynomial<R, O> {
+        // differentiation cannot overflow the exponents
+        dispatch!(*self, ref p => AdaptivePolynomial::from(p.derivative(x)))
+    }
+
+    /// Remove all terms in which the power of `x` is larger than `max`.
+    pub fn truncate(&self, x: usize, max: u64) -> AdaptivePolynomial<R, O> {
+        dispatch!(*self, ref p => {
+            let mut res = p.zero_with_capacity(0);
+            for t in 0..p.nterms {
+                if p.exponents(t)[x].to_u64().unwrap() <= max {
+                    // the order of the terms is preserved
+                    res.append_monomial_back(p.coefficients[t].clone(), p.exponents(t));
+                }
+            }
+            AdaptivePolynomial::from(res)
+        })
+    }
+
+    /// Split the polynomial into the coefficients of the powers of `x`,
+    /// starting at `x^0`.
+    pub fn coefficient_list(&self, x: usize) -> Vec<AdaptivePolynomial<R, O>> {
+        dispatch!(*self, ref p => {
+            let mut res = vec![];
+            let mut e = vec![];
+            for t in 0..p.nterms {
+                let d = p.exponents(t)[x].to_u64().unwrap() as usize;
+                while res.len() <= d {
+                    res.push(p.zero_with_capacity(0));
+                }
+
+                e.clear();
+                e.extend_from_slice(p.exponents(t));
+                e[x] = Zero::zero();
+                res[d].append_monomial(p.coefficients[t].clone(), &e);
+            }
+            res.into_iter().map(AdaptivePolynomial::from).collect()
+        })
+    }
+
+    /// Move the variable `i` to `map[i]` in a polynomial with `nvars` variables.
+    pub fn remap_variables(&self, map: &[usize], nvars: usize) -> AdaptivePolynomial<R, O> {
+        dispatch!(*self, ref p => {
+            let mut res = MultivariatePolynomial::with_nvars_and_order(nvars, p.order().clone());
+            let mut e = vec![Zero::zero(); nvars];
+            for t in 0..p.nterms {
+                for (i, x) in p.exponents(t).iter().enumerate() {
+                    e[map[i]] = *x;
+                }
+                res.append_monomial(p.coefficients[t].clone(), &e);
+            }
+            AdaptivePolynomial::from(res)
+        })
+    }
+
+    /// Replace variables by polynomials simultaneously, so that for example
+    /// `x -> y, y -> x` swaps the two variables. The polynomials should have
+    /// the same number of variables.
+    pub fn substitute_all(
+        &self,
+        subs: &[(usize, AdaptivePolynomial<R, O>)],
+    ) -> AdaptivePolynomial<R, O> {
+        // the products are computed with `AdaptivePolynomial`, which widens
+        // the exponents when they overflow
+        let vars: Vec<_> = subs.iter().map(|s| s.0).collect();
+
+        // cache of the positive powers of the polynomials that are substituted
+        let mut cache: Vec<Vec<AdaptivePolynomial<R, O>>> = vec![vec![]; subs.len()];
+
+        let mut res = dispatch!(*self, ref p => AdaptivePolynomial::from(p.zero_with_capacity(0)));
+        for t in 0..self.nterms() {
+            let mut m = dispatch!(*self, ref p => {
+                let mut e = p.exponents(t).to_vec();
+                for &n in &vars {
+                    e[n] = Zero::zero();
+                }
+                AdaptivePolynomial::from(MultivariatePolynomial::from_monomial_with_order(
+                    p.coefficients[t].clone(),
+                    e,
+                    p.order().clone(),
+                ))
+            });
+            for (i, &(n, ref v)) in subs.iter().enumerate() {
+                let p = self.exponent(t, n) as usize;
+                if p > 0 {
+                    while cache[i].len() < p {
+                        let next = match cache[i].last() {
+                            Some(l) => l.clone() * v.clone(),
+                            None => v.clone(),
+                        };
+                        cache[i].push(next);
+                    }
+                    m = m * cache[i][p - 1].clone();
+                }
+            }
+            res = res + m;
+        }
+
+        res
+    }
+}
+
+impl<O: MonomialOrder> AdaptivePolynomial<Number, O> {
+    /// Compute the antiderivative with respect to the variable `x`, with
+    /// the integration constant set to zero.
+    pub fn integrate(&self, x: usize) -> AdaptivePolynomial<Number, O> {
+        // widen the exponents if the power of `x` could overflow
+        let overflows = dispatch!(*self, ref p => p.degree(x).checked_add(&One::one()).is_none());
+        if overflows && self.exponent_bits() < 64 {
+            let bits = self.exponent_bits() * 2;
+            return self.clone().widen_to(bits).integrate(x);
+        }
+
+        dispatch!(*self, ref p => AdaptivePolynomial::from(p.integrate(x)))
+    }
+}
+
+/// Wrap a polynomial with the exponent type `$e` in the variant `$v`.
+macro_rules! impl_from {
+    ($e:ty, $v:ident) => {
+        impl<R: Ring, O: MonomialOrder> From<MultivariatePolynomial<R, $e, O>>
+            for AdaptivePolynomial<R, O>
+        {
+            fn from(poly: MultivariatePolynomial<R, $e, O>) -> AdaptivePolynomial<R, O> {
+                AdaptivePolynomial::$v(poly)
+            }
+        }
+    };
+}
+
+impl_from!(u8, U8);
+impl_from!(u16, U16);
+impl_from!(u32, U32);
+impl_from!(u64, U64);
+
+impl<R: Ring> AdaptivePolynomial<R>
+where
+    MultivariatePolynomial<R, u8>: PolynomialGCD,
+    MultivariatePolynomial<R, u16>: PolynomialGCD,
+    MultivariatePolynomial<R, u32>: PolynomialGCD,
+    MultivariatePolynomial<R, u64>: PolynomialGCD,
+{
+    /// Compute the gcd of two polynomials.
+    pub fn gcd(a: &AdaptivePolynomial<R>, b: &AdaptivePolynomial<R>) -> AdaptivePolynomial<R> {
+        // the gcd divides both polynomials, so its exponents fit
+        match (a, b) {
+            (&AdaptivePolynomial::U8(ref a), &AdaptivePolynomial::U8(ref b)) => {
+                AdaptivePolynomial::U8(MultivariatePolynomial::gcd(a, b))
+            }
+            (&AdaptivePolynomial::U16(ref a), &AdaptivePolynomial::U16(ref b)) => {
+                AdaptivePolynomial::U16(MultivariatePolynomial::gcd(a, b))
+            }
+            (&AdaptivePolynomial::U32(ref a), &AdaptivePolynomial::U32(ref b)) => {
+                AdaptivePolynomial::U32(MultivariatePolynomial::gcd(a, b))
+            }
+            (&AdaptivePolynomial::U64(ref a), &AdaptivePolynomial::U64(ref b)) => {
+                AdaptivePolynomial::U64(MultivariatePolynomial::gcd(a, b))
+            }
+            _ => {
+                let (a, b) = a.clone().unify_exponents(b.clone());
+                AdaptivePolynomial::gcd(&a, &b)
+            }
+        }
+    }
+}
+
+impl<R: Ring + PartialEq, O: MonomialOrder> PartialEq for AdaptivePolynomial<R, O> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (&AdaptivePolynomial::U8(ref a), &AdaptivePolynomial::U8(ref b)) => a == b,
+            (&AdaptivePolynomial::U16(ref a), &AdaptivePolynomial::U16(ref b)) => a == b,
+            (&AdaptivePolynomial::U32(ref a), &AdaptivePolynomial::U32(ref b)) => a == b,
+            (&AdaptivePolynomial::U64(ref a), &AdaptivePolynomial::U64(ref b)) => a == b,
+            _ => self.to_wide() == other.to_wide(),
+        }
+    }
+}
+
+impl<R: Ring + Eq, O: MonomialOrder> Eq for AdaptivePolynomial<R, O> {}
+
+impl<R: Ring + Hash, O: MonomialOrder> Hash for AdaptivePolynomial<R, O> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hash the exponents as `u64`, so that equal polynomials
+        // with different exponent types have the same hash
+        self.nvars().hash(state);
+        self.coefficients().hash(state);
+        dispatch!(*self, ref p => for e in &p.exponents {
+            e.to_u64().unwrap().hash(state);
+        })
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> Zero for AdaptivePolynomial<R, O> {
+    #[inline]
+    fn zero() -> Self {
+        AdaptivePolynomial::U8(MultivariatePolynomial::zero())
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.nterms() == 0
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> Add for AdaptivePolynomial<R, O> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        // addition cannot overflow the exponents
+        match self.unify_exponents(other) {
+            (AdaptivePolynomial::U8(a), AdaptivePolynomial::U8(b)) => AdaptivePolynomial::U8(a + b),
+            (AdaptivePolynomial::U16(a), AdaptivePolynomial::U16(b)) => {
+                AdaptivePolynomial::U16(a + b)
+            }
+            (AdaptivePolynomial::U32(a), AdaptivePolynomial::U32(b)) => {
+                AdaptivePolynomial::U32(a + b)
+            }
+            (AdaptivePolynomial::U64(a), AdaptivePolynomial::U64(b)) => {
+                AdaptivePolynomial::U64(a + b)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> Sub for AdaptivePolynomial<R, O> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.add(other.neg())
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> Neg for AdaptivePolynomial<R, O> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            AdaptivePolynomial::U8(a) => AdaptivePolynomial::U8(-a),
+            AdaptivePolynomial::U16(a) => AdaptivePolynomial::U16(-a),
+            AdaptivePolynomial::U32(a) => AdaptivePolynomial::U32(-a),
+            AdaptivePolynomial::U64(a) => AdaptivePolynomial::U64(-a),
+        }
+    }
+}
+
+impl<R: Ring, O: MonomialOrder> Mul for AdaptivePolynomial<R, O> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        // if the exponents overflow, widen them and try again
+        match self.unify_exponents(other) {
+            (AdaptivePolynomial::U8(a), AdaptivePolynomial::U8(b)) => match a.checked_mul(&b) {
+                Some(r) => AdaptivePolynomial::U8(r),
+                None => {
+                    AdaptivePolynomial::U8(a).widen_to(16) * AdaptivePolynomial::U8(b).widen_to(16)
+                }
+            },
+            (AdaptivePolynomial::U16(a), AdaptivePolynomial::U16(b)) => match a.checked_mul(&b) {
+                Some(r) => AdaptivePolynomial::U16(r),
+                None => {
+                    AdaptivePolynomial::U16(a).widen_to(32)
+                        * AdaptivePolynomial::U16(b).widen_to(32)
+                }
+            },
+            (AdaptivePolynomial::U32(a), AdaptivePolynomial::U32(b)) => match a.checked_mul(&b) {
+                Some(r) => AdaptivePolynomial::U32(r),
+                None => {
+                    AdaptivePolynomial::U32(a).widen_to(64)
+                        * AdaptivePolynomial::U32(b).widen_to(64)
+                }
+            },
+            (AdaptivePolynomial::U64(a), AdaptivePolynomial::U64(b)) => {
+                AdaptivePolynomial::U64(a.checked_mul(&b).expect("overflow in adding exponents"))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
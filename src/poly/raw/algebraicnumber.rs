@@ -0,0 +1,416 @@
+//! Algebraic number fields Q(a). An element is a polynomial in `a` with rational
+//! coefficients and a degree smaller than k, where `a` is a root of a monic
+//! irreducible polynomial of degree k, the minimal polynomial. For example,
+//! `sqrt(3)` has minimal polynomial `a^2-3` and a primitive cube root of unity
+//! has `a^2+a+1`.
+//!
+//! Algebraic numbers can be used as coefficients of `MultivariatePolynomial`. In the
+//! language, the `algebraic` statement declares a variable as the generator of Q(a),
+//! after which `rat_` fractions are reduced with `rationalpolynomial_algebraic`: the
+//! powers of the generator are reduced and the gcd is computed over Q(a).
+
+use num_traits::{One, Pow, Zero};
+use number::Number;
+use poly::raw::finitefield::FiniteField;
+use poly::raw::galoisfield::GaloisField;
+use poly::raw::rational_solve::number_mod_p;
+use poly::raw::zp::ufield;
+use poly::ring::{MulModNum, ToFiniteField};
+use std::fmt;
+use std::ops::{Add, Div, Mul, MulAssign, Neg, Rem, Sub};
+use std::sync::Arc;
+use tools::GCD;
+
+/// Remove the leading zero coefficients of a dense polynomial.
+fn trim(a: &mut Vec<Number>) {
+    while a.last().map(|x| x.is_zero()).unwrap_or(false) {
+        a.pop();
+    }
+}
+
+/// Compute `a + s * b` for dense polynomials in Q[x].
+fn add_mul(a: &[Number], b: &[Number], s: &Number) -> Vec<Number> {
+    let mut r = a.to_vec();
+    if r.len() < b.len() {
+        r.resize(b.len(), Number::zero());
+    }
+    for (x, y) in r.iter_mut().zip(b) {
+        *x = x.clone() + y.clone() * s.clone();
+    }
+    trim(&mut r);
+    r
+}
+
+/// Multiply two dense polynomials in Q[x].
+fn mul_dense(a: &[Number], b: &[Number]) -> Vec<Number> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let mut r = vec![Number::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        if x.is_zero() {
+            continue;
+        }
+        for (j, y) in b.iter().enumerate() {
+            r[i + j] = r[i + j].clone() + x.clone() * y.clone();
+        }
+    }
+    trim(&mut r);
+    r
+}
+
+/// Divide `a` by the non-zero polynomial `b` in Q[x], returning the quotient and remainder.
+fn divmod_dense(a: &[Number], b: &[Number]) -> (Vec<Number>, Vec<Number>) {
+    let db = b.len() - 1;
+    let inv = Number::one() / b[db].clone();
+
+    let mut r = a.to_vec();
+    trim(&mut r);
+    if r.len() < b.len() {
+        return (vec![], r);
+    }
+
+    let mut q = vec![Number::zero(); r.len() - db];
+    while r.len() >= b.len() {
+        let c = r.last().unwrap().clone() * inv.clone();
+        let shift = r.len() - b.len();
+        for (x, y) in r[shift..].iter_mut().zip(b) {
+            *x = x.clone() - c.clone() * y.clone();
+        }
+        q[shift] = c;
+        trim(&mut r);
+    }
+    (q, r)
+}
+
+/// An element of the algebraic number field Q(a).
+///
+/// Rational numbers that are not bound to a field, such as those created by `zero()`,
+/// `one()` and `from_finite_field()`, have an empty minimal polynomial and adopt the
+/// minimal polynomial of the other operand in arithmetic operations.
+#[derive(Debug, Clone)]
+pub struct AlgebraicNumber {
+    /// The coefficients of the number as a polynomial in `a`,
+    /// from the lowest to the highest power.
+    pub coefficients: Vec<Number>,
+    /// The monic minimal polynomial of `a`.
+    pub minimal_polynomial: Arc<Vec<Number>>,
+}
+
+impl AlgebraicNumber {
+    /// Create a new element of Q(a), where `minimal_polynomial` is a monic irreducible
+    /// polynomial over Q.
+    pub fn new(coefficients: Vec<Number>, minimal_polynomial: Arc<Vec<Number>>) -> AlgebraicNumber {
+        let mut c = coefficients;
+        trim(&mut c);
+        if !minimal_polynomial.is_empty() {
+            c = divmod_dense(&c, &minimal_polynomial).1;
+        }
+
+        AlgebraicNumber {
+            coefficients: c,
+            minimal_polynomial,
+        }
+    }
+
+    /// Get the root `a` of the minimal polynomial.
+    pub fn generator(minimal_polynomial: Arc<Vec<Number>>) -> AlgebraicNumber {
+        AlgebraicNumber::new(vec![Number::zero(), Number::one()], minimal_polynomial)
+    }
+
+    /// Create a rational number that is not bound to a field.
+    pub fn from_number(n: Number) -> AlgebraicNumber {
+        AlgebraicNumber::new(vec![n], Arc::new(vec![]))
+    }
+
+    /// Get the minimal polynomial of the field that contains both `self` and `other`.
+    fn unify(&self, other: &AlgebraicNumber) -> Arc<Vec<Number>> {
+        if self.minimal_polynomial.is_empty() {
+            other.minimal_polynomial.clone()
+        } else {
+            debug_assert!(
+                other.minimal_polynomial.is_empty()
+                    || self.minimal_polynomial == other.minimal_polynomial
+            );
+            self.minimal_polynomial.clone()
+        }
+    }
+
+    /// Compute the multiplicative inverse in the field with the given minimal polynomial.
+    fn inverse_in(&self, minimal_polynomial: Arc<Vec<Number>>) -> AlgebraicNumber {
+        assert!(!self.coefficients.is_empty(), "0 is not invertible");
+
+        if self.coefficients.len() == 1 {
+            return AlgebraicNumber {
+                coefficients: vec![Number::one() / self.coefficients[0].clone()],
+                minimal_polynomial,
+            };
+        }
+
+        // extended Euclidean algorithm, with the invariant r_i = s_i * self mod minimal_polynomial
+        let mut r0 = minimal_polynomial.to_vec();
+        let mut r1 = self.coefficients.clone();
+        let mut s0 = vec![];
+        let mut s1 = vec![Number::one()];
+        while !r1.is_empty() {
+            let (q, r) = divmod_dense(&r0, &r1);
+            let s = add_mul(&s0, &mul_dense(&q, &s1), &Number::SmallInt(-1));
+            r0 = r1;
+            r1 = r;
+            s0 = s1;
+            s1 = s;
+        }
+
+        // the gcd is a constant, since the minimal polynomial is irreducible
+        assert_eq!(r0.len(), 1, "Minimal polynomial is not irreducible");
+        let inv = Number::one() / r0[0].clone();
+        AlgebraicNumber::new(add_mul(&[], &s0, &inv), minimal_polynomial)
+    }
+
+    /// Compute the multiplicative inverse.
+    pub fn inverse(&self) -> AlgebraicNumber {
+        self.inverse_in(self.minimal_polynomial.clone())
+    }
+
+    /// Map the number to the Galois field GF(p^k), defined by the image of the
+    /// minimal polynomial modulo `p`. Returns `None` if a denominator vanishes.
+    pub fn to_galois_field(&self, p: ufield, modulus: &Arc<Vec<ufield>>) -> Option<GaloisField> {
+        let mut c = Vec::with_capacity(self.coefficients.len());
+        for x in &self.coefficients {
+            c.push(number_mod_p(x, p)?);
+        }
+        Some(GaloisField::new(c, p, modulus.clone()))
+    }
+}
+
+impl PartialEq for AlgebraicNumber {
+    fn eq(&self, other: &AlgebraicNumber) -> bool {
+        self.coefficients == other.coefficients
+    }
+}
+
+impl Eq for AlgebraicNumber {}
+
+impl fmt::Display for AlgebraicNumber {
+    /// Print the number as a polynomial in the generator `a`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = String::new();
+        let mut nterms = 0;
+        for (i, c) in self.coefficients.iter().enumerate() {
+            if c.is_zero() {
+                continue;
+            }
+
+            let t = match i {
+                0 => format!("{}", c),
+                1 if c.is_one() => "a".to_owned(),
+                1 => format!("{}*a", c),
+                i if c.is_one() => format!("a^{}", i),
+                i => format!("{}*a^{}", c, i),
+            };
+
+            if nterms > 0 && !t.starts_with('-') {
+                s.push('+');
+            }
+            s.push_str(&t);
+            nterms += 1;
+        }
+
+        match nterms {
+            0 => write!(f, "0"),
+            1 => write!(f, "{}", s),
+            _ => write!(f, "({})", s),
+        }
+    }
+}
+
+impl Mul for AlgebraicNumber {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let minimal_polynomial = self.unify(&other);
+        let mut c = mul_dense(&self.coefficients, &other.coefficients);
+        if !minimal_polynomial.is_empty() {
+            c = divmod_dense(&c, &minimal_polynomial).1;
+        }
+
+        AlgebraicNumber {
+            coefficients: c,
+            minimal_polynomial,
+        }
+    }
+}
+
+impl MulAssign for AlgebraicNumber {
+    fn mul_assign(&mut self, other: Self) {
+        *self = self.clone() * other;
+    }
+}
+
+impl Add for AlgebraicNumber {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        AlgebraicNumber {
+            minimal_polynomial: self.unify(&other),
+            coefficients: add_mul(&self.coefficients, &other.coefficients, &Number::one()),
+        }
+    }
+}
+
+impl Sub for AlgebraicNumber {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        AlgebraicNumber {
+            minimal_polynomial: self.unify(&other),
+            coefficients: add_mul(
+                &self.coefficients,
+                &other.coefficients,
+                &Number::SmallInt(-1),
+            ),
+        }
+    }
+}
+
+impl Zero for AlgebraicNumber {
+    fn zero() -> Self {
+        AlgebraicNumber {
+            coefficients: vec![],
+            minimal_polynomial: Arc::new(vec![]),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+}
+
+impl One for AlgebraicNumber {
+    fn one() -> AlgebraicNumber {
+        AlgebraicNumber {
+            coefficients: vec![Number::one()],
+            minimal_polynomial: Arc::new(vec![]),
+        }
+    }
+
+    fn is_one(&self) -> bool {
+        self.coefficients.len() == 1 && self.coefficients[0].is_one()
+    }
+}
+
+impl Neg for AlgebraicNumber {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        AlgebraicNumber {
+            coefficients: self.coefficients.into_iter().map(|x| -x).collect(),
+            minimal_polynomial: self.minimal_polynomial,
+        }
+    }
+}
+
+impl Div for AlgebraicNumber {
+    type Output = Self;
+
+    fn div(self, other: AlgebraicNumber) -> Self::Output {
+        let minimal_polynomial = self.unify(&other);
+        self * other.inverse_in(minimal_polynomial)
+    }
+}
+
+impl MulModNum for AlgebraicNumber {
+    fn mul_num(&self, n: ufield) -> AlgebraicNumber {
+        AlgebraicNumber {
+            coefficients: add_mul(&[], &self.coefficients, &Number::SmallInt(n as isize)),
+            minimal_polynomial: self.minimal_polynomial.clone(),
+        }
+    }
+
+    fn mod_num(&self, n: ufield) -> AlgebraicNumber {
+        self.clone() % AlgebraicNumber::from_number(Number::SmallInt(n as isize))
+    }
+}
+
+impl Rem for AlgebraicNumber {
+    type Output = Self;
+
+    fn rem(self, _other: Self) -> Self::Output {
+        return AlgebraicNumber::zero();
+    }
+}
+
+impl Pow<u32> for AlgebraicNumber {
+    type Output = Self;
+
+    fn pow(self, mut e: u32) -> Self::Output {
+        let mut r = AlgebraicNumber {
+            coefficients: vec![Number::one()],
+            minimal_polynomial: self.minimal_polynomial.clone(),
+        };
+        let mut b = self;
+        while e != 0 {
+            if e & 1 != 0 {
+                r = r * b.clone();
+            }
+            b = b.clone() * b;
+            e >>= 1;
+        }
+        r
+    }
+}
+
+impl ToFiniteField for AlgebraicNumber {
+    /// Map a rational number to a finite field of size `p`.
+    /// The higher powers of the generator are ignored.
+    fn to_finite_field(&self, p: ufield) -> FiniteField {
+        FiniteField::new(
+            self.coefficients
+                .first()
+                .and_then(|x| number_mod_p(x, p))
+                .unwrap_or(0),
+            p,
+        )
+    }
+
+    fn from_finite_field(ff: &FiniteField) -> AlgebraicNumber {
+        AlgebraicNumber::from_number(Number::from_finite_field(ff))
+    }
+}
+
+impl GCD for AlgebraicNumber {
+    fn gcd(a: AlgebraicNumber, b: AlgebraicNumber) -> AlgebraicNumber {
+        if a == b {
+            a
+        } else {
+            AlgebraicNumber {
+                coefficients: vec![Number::one()],
+                minimal_polynomial: a.unify(&b),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_algebraic_number() {
+    // Q(sqrt(3))
+    let minpoly = Arc::new(vec![Number::SmallInt(-3), Number::zero(), Number::one()]);
+    let a = AlgebraicNumber::generator(minpoly.clone());
+    assert_eq!(
+        a.clone() * a.clone(),
+        AlgebraicNumber::from_number(Number::SmallInt(3))
+    );
+
+    // 1/(1+sqrt(3)) = (sqrt(3)-1)/2
+    let b = AlgebraicNumber::new(vec![Number::one(), Number::one()], minpoly.clone());
+    assert_eq!(
+        b.inverse(),
+        AlgebraicNumber::new(
+            vec![Number::SmallRat(-1, 2), Number::SmallRat(1, 2)],
+            minpoly.clone()
+        )
+    );
+    assert_eq!(b.clone() / b.clone(), AlgebraicNumber::one());
+    assert_eq!(b.pow(2).to_string(), "(4+2*a)");
+}
@@ -2,6 +2,8 @@ use num_traits::cast::AsPrimitive;
 use num_traits::{One, Pow, Zero};
 use poly::raw::zp;
 use poly::raw::zp::ufield;
+use poly::raw::zp64;
+use poly::raw::zp_mod::{Modulus, Montgomery64};
 use poly::ring::{MulModNum, ToFiniteField};
 use std::fmt;
 use std::ops::{Add, Div, Mul, MulAssign, Neg, Rem, Sub};
@@ -230,3 +232,218 @@ impl GCD for FiniteField {
         }
     }
 }
+
+/// An element of a finite field with a prime `p < 2^63`. The number is stored
+/// in Montgomery form, so that multiplication does not require a division.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FiniteField64 {
+    n: u64,
+    p: Montgomery64,
+}
+
+impl FiniteField64 {
+    /// Create a new element. The prime `p` has to be odd.
+    pub fn new(n: u64, p: u64) -> FiniteField64 {
+        FiniteField64::from_u64(n, Montgomery64::new(p))
+    }
+
+    /// Create a new element, reusing the Montgomery parameters of the prime.
+    #[inline]
+    pub fn from_u64(n: u64, p: Montgomery64) -> FiniteField64 {
+        let pv = p.value();
+        FiniteField64 {
+            n: p.to_montgomery(if n >= pv { n % pv } else { n }),
+            p,
+        }
+    }
+
+    pub fn from_i64(n: i64, p: Montgomery64) -> FiniteField64 {
+        let pv = p.value() as i128;
+        FiniteField64::from_u64(((i128::from(n) % pv + pv) % pv) as u64, p)
+    }
+
+    /// Get the number in the standard representation `0 <= n < p`.
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.p.from_montgomery(self.n)
+    }
+
+    /// Get the prime of the finite field.
+    #[inline]
+    pub fn prime(&self) -> u64 {
+        self.p.value()
+    }
+
+    /// Get the Montgomery parameters of the prime.
+    #[inline]
+    pub fn modulus(&self) -> Montgomery64 {
+        self.p
+    }
+
+    /// Compute the multiplicative inverse.
+    pub fn inverse(&self) -> FiniteField64 {
+        FiniteField64::from_u64(zp64::inv(self.value(), self.p), self.p)
+    }
+}
+
+impl fmt::Display for FiniteField64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl Mul for FiniteField64 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        debug_assert_eq!(self.p, other.p);
+        FiniteField64 {
+            n: self.p.mul(self.n, other.n),
+            p: self.p,
+        }
+    }
+}
+
+impl MulAssign for FiniteField64 {
+    fn mul_assign(&mut self, other: Self) {
+        debug_assert_eq!(self.p, other.p);
+        self.n = self.p.mul(self.n, other.n);
+    }
+}
+
+impl Add for FiniteField64 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        debug_assert_eq!(self.p, other.p);
+        FiniteField64 {
+            n: zp64::add(self.n, other.n, self.p),
+            p: self.p,
+        }
+    }
+}
+
+impl Sub for FiniteField64 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        debug_assert_eq!(self.p, other.p);
+        FiniteField64 {
+            n: zp64::sub(self.n, other.n, self.p),
+            p: self.p,
+        }
+    }
+}
+
+impl Zero for FiniteField64 {
+    fn zero() -> Self {
+        // the Montgomery form requires an odd prime
+        FiniteField64 {
+            n: 0,
+            p: Montgomery64::new(3),
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.n == 0
+    }
+}
+
+impl One for FiniteField64 {
+    fn one() -> FiniteField64 {
+        FiniteField64::new(1, 3)
+    }
+
+    fn is_one(&self) -> bool {
+        self.n == self.p.one()
+    }
+}
+
+impl Neg for FiniteField64 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        FiniteField64 {
+            n: zp64::neg(self.n, self.p),
+            p: self.p,
+        }
+    }
+}
+
+impl Div for FiniteField64 {
+    type Output = Self;
+
+    fn div(self, other: FiniteField64) -> Self::Output {
+        debug_assert_eq!(self.p, other.p);
+        self * other.inverse()
+    }
+}
+
+impl MulModNum for FiniteField64 {
+    fn mul_num(&self, n: ufield) -> FiniteField64 {
+        self.clone() * FiniteField64::from_u64(n as u64, self.p)
+    }
+
+    fn mod_num(&self, n: ufield) -> FiniteField64 {
+        self.clone() % FiniteField64::from_u64(n as u64, self.p)
+    }
+}
+
+impl Rem for FiniteField64 {
+    type Output = Self;
+
+    fn rem(self, _other: Self) -> Self::Output {
+        return FiniteField64::zero();
+    }
+}
+
+impl Pow<u32> for FiniteField64 {
+    type Output = Self;
+
+    fn pow(self, mut e: u32) -> Self::Output {
+        let mut r = self.p.one();
+        let mut b = self.n;
+        while e != 0 {
+            if e & 1 != 0 {
+                r = self.p.mul(r, b);
+            }
+            b = self.p.mul(b, b);
+            e >>= 1;
+        }
+        FiniteField64 { n: r, p: self.p }
+    }
+}
+
+impl ToFiniteField for FiniteField64 {
+    fn to_finite_field(&self, p: ufield) -> FiniteField {
+        FiniteField::from_u64(self.value(), p)
+    }
+
+    fn from_finite_field(ff: &FiniteField) -> FiniteField64 {
+        FiniteField64::new(ff.n as u64, ff.p as u64)
+    }
+}
+
+impl GCD for FiniteField64 {
+    fn gcd(a: FiniteField64, b: FiniteField64) -> FiniteField64 {
+        debug_assert_eq!(a.p, b.p);
+        if a == b {
+            a
+        } else {
+            FiniteField64::from_u64(1, a.p)
+        }
+    }
+}
+
+#[test]
+fn test_finite_field_64() {
+    let p = Montgomery64::new(9223372036854775783);
+    let a = FiniteField64::from_i64(-2, p);
+    let b = FiniteField64::from_u64(3, p);
+
+    assert_eq!((a * b).value(), 9223372036854775777);
+    assert_eq!(a / b * b, a);
+    assert_eq!((a + b).value(), 1);
+    assert_eq!(a.pow(3), FiniteField64::from_i64(-8, p));
+    assert_eq!(b.to_finite_field(7), FiniteField::new(3, 7));
+}
@@ -0,0 +1,499 @@
+//! Galois fields GF(p^k). An element is a polynomial over GF(p) of degree smaller than k,
+//! modulo a monic irreducible polynomial of degree k. Extension fields provide more
+//! evaluation points than the prime field, which is used by the multivariate gcd
+//! over small prime fields. The arithmetic is built on `UnivariatePolynomial`.
+
+use num_traits::{One, Pow, Zero};
+use poly::raw::finitefield::FiniteField;
+use poly::raw::univariate::UnivariatePolynomial;
+use poly::raw::zp::ufield;
+use poly::ring::{MulModNum, ToFiniteField};
+use rand;
+use rand::distributions::{Distribution, Uniform};
+use std::cmp::max;
+use std::fmt;
+use std::ops::{Add, Div, Mul, MulAssign, Neg, Rem, Sub};
+use std::sync::Arc;
+use tools::GCD;
+
+/// Convert a list of coefficients to a dense polynomial over GF(p).
+fn to_dense(a: &[ufield], p: ufield) -> UnivariatePolynomial<FiniteField> {
+    UnivariatePolynomial::new(a.iter().map(|&x| FiniteField::new(x, p)).collect())
+}
+
+/// Get the list of coefficients of a dense polynomial over GF(p).
+fn from_dense(a: &UnivariatePolynomial<FiniteField>) -> Vec<ufield> {
+    a.coefficients.iter().map(|x| x.n).collect()
+}
+
+/// Compute `a^n` modulo the monic polynomial `f` in GF(p)[x].
+fn pow_mod(
+    a: &UnivariatePolynomial<FiniteField>,
+    mut n: u64,
+    f: &UnivariatePolynomial<FiniteField>,
+) -> UnivariatePolynomial<FiniteField> {
+    let mut r = UnivariatePolynomial::new(vec![FiniteField::new(1, f.lcoeff().p)]).rem(f);
+    let mut b = a.clone();
+    while n != 0 {
+        if n & 1 != 0 {
+            r = (&r * &b).rem(f);
+        }
+        b = (&b * &b).rem(f);
+        n >>= 1;
+    }
+    r
+}
+
+/// Test if the monic polynomial `f` is irreducible in GF(p)[x], using Rabin's test:
+/// a polynomial of degree k is irreducible if and only if `x^(p^k) = x mod f` and
+/// `gcd(x^(p^(k/q)) - x, f) = 1` for all prime divisors q of k.
+pub fn is_irreducible(f: &[ufield], p: ufield) -> bool {
+    let k = f.len() - 1;
+    if k == 0 {
+        return false;
+    }
+
+    let f = to_dense(f, p);
+    let x = to_dense(&[0, 1], p).rem(&f);
+
+    // compute x^(p^i) mod f for i = 1..k
+    let mut powers = Vec::with_capacity(k);
+    let mut y = x.clone();
+    for _ in 0..k {
+        y = pow_mod(&y, u64::from(p), &f);
+        powers.push(y.clone());
+    }
+
+    if powers[k - 1] != x {
+        return false;
+    }
+
+    let mut kk = k;
+    let mut q = 2;
+    while kk > 1 {
+        if kk % q == 0 {
+            while kk % q == 0 {
+                kk /= q;
+            }
+
+            let h = &powers[k / q - 1] - &x;
+            if h.gcd(&f).degree() > 0 {
+                return false;
+            }
+        }
+        q += 1;
+    }
+    true
+}
+
+/// Find a random monic irreducible polynomial of degree `k` in GF(p)[x].
+pub fn find_irreducible_polynomial(p: ufield, k: usize) -> Vec<ufield> {
+    assert!(k > 0);
+    let mut rng = rand::thread_rng();
+    let range = Uniform::new(0, p);
+
+    loop {
+        let mut f: Vec<ufield> = (0..k).map(|_| range.sample(&mut rng)).collect();
+        f.push(1);
+        if is_irreducible(&f, p) {
+            return f;
+        }
+    }
+}
+
+/// An element of the Galois field GF(p^k).
+///
+/// Elements of the prime field that are not bound to an extension, such as those
+/// created by `from_finite_field()`, have an empty modulus and adopt the modulus of
+/// the other operand in arithmetic operations. The elements created by `zero()` and
+/// `one()` do not know the prime either: they are small integers with `p = 0`, that
+/// are mapped to GF(p) when they meet an element of a field.
+#[derive(Debug, Clone)]
+pub struct GaloisField {
+    /// The coefficients of the element as a polynomial in the generator of the field,
+    /// from the lowest to the highest power. For `p = 0`, the integer is stored in
+    /// two's complement.
+    pub coefficients: Vec<ufield>,
+    pub p: ufield,
+    /// The monic irreducible polynomial of degree k that defines the field.
+    pub modulus: Arc<Vec<ufield>>,
+}
+
+impl GaloisField {
+    /// Create a new element of GF(p^k), where `modulus` is a monic irreducible polynomial
+    /// of degree k over GF(p).
+    pub fn new(coefficients: Vec<ufield>, p: ufield, modulus: Arc<Vec<ufield>>) -> GaloisField {
+        GaloisField::from_polynomial(to_dense(&coefficients, p), p, modulus)
+    }
+
+    /// Create an element from a dense polynomial over GF(p), reducing it by the modulus.
+    fn from_polynomial(
+        c: UnivariatePolynomial<FiniteField>,
+        p: ufield,
+        modulus: Arc<Vec<ufield>>,
+    ) -> GaloisField {
+        let c = if modulus.is_empty() {
+            c
+        } else {
+            c.rem(&to_dense(&modulus, p))
+        };
+
+        GaloisField {
+            coefficients: from_dense(&c),
+            p,
+            modulus,
+        }
+    }
+
+    /// Create an integer that is not bound to a field.
+    fn from_integer(n: i64) -> GaloisField {
+        GaloisField {
+            coefficients: if n == 0 {
+                vec![]
+            } else {
+                vec![n as i32 as ufield]
+            },
+            p: 0,
+            modulus: Arc::new(vec![]),
+        }
+    }
+
+    /// Get the value of an integer that is not bound to a field.
+    fn integer_value(&self) -> i64 {
+        debug_assert_eq!(self.p, 0);
+        self.coefficients
+            .first()
+            .map(|&c| i64::from(c as i32))
+            .unwrap_or(0)
+    }
+
+    /// Get the element as a dense polynomial over GF(p), mapping an integer
+    /// that is not bound to a field to GF(p).
+    fn to_dense_in(&self, p: ufield) -> UnivariatePolynomial<FiniteField> {
+        if self.p == 0 {
+            UnivariatePolynomial::new(vec![FiniteField::from_i64(self.integer_value(), p)])
+        } else {
+            to_dense(&self.coefficients, p)
+        }
+    }
+
+    /// Get the generator of the field, i.e., the root of the modulus.
+    pub fn generator(p: ufield, modulus: Arc<Vec<ufield>>) -> GaloisField {
+        GaloisField::new(vec![0, 1], p, modulus)
+    }
+
+    /// Get the prime and modulus of the field that contains both `self` and `other`.
+    /// The prime is 0 if neither of them is bound to a field.
+    fn unify(&self, other: &GaloisField) -> (ufield, Arc<Vec<ufield>>) {
+        if self.p == 0 || (self.modulus.is_empty() && other.p != 0) {
+            debug_assert!(self.p == 0 || self.p == other.p);
+            (other.p, other.modulus.clone())
+        } else {
+            debug_assert!(other.p == 0 || other.p == self.p);
+            debug_assert!(other.modulus.is_empty() || self.modulus == other.modulus);
+            (self.p, self.modulus.clone())
+        }
+    }
+
+    /// Compute the multiplicative inverse in GF(p^k) with the given prime and modulus.
+    fn inverse_in(&self, p: ufield, modulus: Arc<Vec<ufield>>) -> GaloisField {
+        assert!(!self.is_zero(), "0 is not invertible");
+
+        let c = self.to_dense_in(p);
+        let one = FiniteField::new(1, p);
+        if c.degree() == 0 {
+            return GaloisField {
+                coefficients: vec![(one / c.coefficients[0]).n],
+                p,
+                modulus,
+            };
+        }
+
+        // extended Euclidean algorithm, with the invariant r_i = s_i * self mod modulus
+        let mut r0 = to_dense(&modulus, p);
+        let mut r1 = c;
+        let mut s0 = UnivariatePolynomial::new(vec![]);
+        let mut s1 = UnivariatePolynomial::new(vec![one]);
+        while !r1.is_zero() {
+            let (q, r) = r0.divmod(&r1);
+            let s = &s0 - &(&q * &s1);
+            r0 = r1;
+            r1 = r;
+            s0 = s1;
+            s1 = s;
+        }
+
+        // the gcd is a constant, since the modulus is irreducible
+        assert_eq!(r0.degree(), 0, "Modulus is not irreducible");
+        GaloisField::from_polynomial(s0.mul_coeff(&(one / r0.coefficients[0])), p, modulus)
+    }
+
+    /// Compute the multiplicative inverse.
+    pub fn inverse(&self) -> GaloisField {
+        if self.p == 0 {
+            GaloisField::one() / self.clone()
+        } else {
+            self.inverse_in(self.p, self.modulus.clone())
+        }
+    }
+
+    /// Map an element of the prime field to a finite field of size `p`.
+    /// Returns `None` if the element contains powers of the generator.
+    pub fn to_prime_field(&self, p: ufield) -> Option<FiniteField> {
+        if self.p == 0 {
+            return Some(FiniteField::from_i64(self.integer_value(), p));
+        }
+
+        match self.coefficients.len() {
+            0 => Some(FiniteField::new(0, p)),
+            1 => Some(FiniteField::new(self.coefficients[0], p)),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for GaloisField {
+    fn eq(&self, other: &GaloisField) -> bool {
+        if (self.p == 0) == (other.p == 0) {
+            self.coefficients == other.coefficients
+        } else {
+            let p = max(self.p, other.p);
+            self.to_dense_in(p) == other.to_dense_in(p)
+        }
+    }
+}
+
+impl Eq for GaloisField {}
+
+impl fmt::Display for GaloisField {
+    /// Print the element as a polynomial in the generator `a`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.p == 0 {
+            return write!(f, "{}", self.integer_value());
+        }
+
+        let terms: Vec<String> = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c != 0)
+            .map(|(i, c)| match (i, *c) {
+                (0, c) => format!("{}", c),
+                (1, 1) => "a".to_owned(),
+                (1, c) => format!("{}*a", c),
+                (i, 1) => format!("a^{}", i),
+                (i, c) => format!("{}*a^{}", c, i),
+            })
+            .collect();
+
+        match terms.len() {
+            0 => write!(f, "0"),
+            1 => write!(f, "{}", terms[0]),
+            _ => write!(f, "({})", terms.join("+")),
+        }
+    }
+}
+
+impl Mul for GaloisField {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let (p, modulus) = self.unify(&other);
+        if p == 0 {
+            return GaloisField::from_integer(self.integer_value() * other.integer_value());
+        }
+
+        let c = &self.to_dense_in(p) * &other.to_dense_in(p);
+        GaloisField::from_polynomial(c, p, modulus)
+    }
+}
+
+impl MulAssign for GaloisField {
+    fn mul_assign(&mut self, other: Self) {
+        *self = self.clone() * other;
+    }
+}
+
+impl Add for GaloisField {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let (p, modulus) = self.unify(&other);
+        if p == 0 {
+            return GaloisField::from_integer(self.integer_value() + other.integer_value());
+        }
+
+        let c = &self.to_dense_in(p) + &other.to_dense_in(p);
+        GaloisField::from_polynomial(c, p, modulus)
+    }
+}
+
+impl Sub for GaloisField {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        let (p, modulus) = self.unify(&other);
+        if p == 0 {
+            return GaloisField::from_integer(self.integer_value() - other.integer_value());
+        }
+
+        let c = &self.to_dense_in(p) - &other.to_dense_in(p);
+        GaloisField::from_polynomial(c, p, modulus)
+    }
+}
+
+impl Zero for GaloisField {
+    fn zero() -> Self {
+        GaloisField::from_integer(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+}
+
+impl One for GaloisField {
+    fn one() -> GaloisField {
+        GaloisField::from_integer(1)
+    }
+
+    fn is_one(&self) -> bool {
+        self.coefficients == [1]
+    }
+}
+
+impl Neg for GaloisField {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        if self.p == 0 {
+            return GaloisField::from_integer(-self.integer_value());
+        }
+
+        let c = -to_dense(&self.coefficients, self.p);
+        GaloisField::from_polynomial(c, self.p, self.modulus)
+    }
+}
+
+impl Div for GaloisField {
+    type Output = Self;
+
+    fn div(self, other: GaloisField) -> Self::Output {
+        let (p, modulus) = self.unify(&other);
+        if p == 0 {
+            let (a, b) = (self.integer_value(), other.integer_value());
+            assert!(
+                b != 0 && a % b == 0,
+                "Cannot divide integers that are not bound to a Galois field"
+            );
+            return GaloisField::from_integer(a / b);
+        }
+
+        self * other.inverse_in(p, modulus)
+    }
+}
+
+impl MulModNum for GaloisField {
+    fn mul_num(&self, n: ufield) -> GaloisField {
+        if self.p == 0 {
+            return GaloisField::from_integer(self.integer_value() * i64::from(n));
+        }
+
+        let c = to_dense(&self.coefficients, self.p).mul_coeff(&FiniteField::new(n, self.p));
+        GaloisField::from_polynomial(c, self.p, self.modulus.clone())
+    }
+
+    fn mod_num(&self, _n: ufield) -> GaloisField {
+        GaloisField::zero()
+    }
+}
+
+impl Rem for GaloisField {
+    type Output = Self;
+
+    fn rem(self, _other: Self) -> Self::Output {
+        return GaloisField::zero();
+    }
+}
+
+impl Pow<u32> for GaloisField {
+    type Output = Self;
+
+    fn pow(self, mut e: u32) -> Self::Output {
+        let mut r = GaloisField::one();
+        let mut b = self;
+        while e != 0 {
+            if e & 1 != 0 {
+                r = r * b.clone();
+            }
+            b = b.clone() * b;
+            e >>= 1;
+        }
+        r
+    }
+}
+
+impl ToFiniteField for GaloisField {
+    /// Map an element of the prime field to a finite field of size `p`.
+    /// Panics if the element contains powers of the generator, see `to_prime_field`.
+    fn to_finite_field(&self, p: ufield) -> FiniteField {
+        self.to_prime_field(p)
+            .unwrap_or_else(|| panic!("{} is not an element of the prime field", self))
+    }
+
+    fn from_finite_field(ff: &FiniteField) -> GaloisField {
+        GaloisField::new(vec![ff.n], ff.p, Arc::new(vec![]))
+    }
+}
+
+impl GCD for GaloisField {
+    fn gcd(a: GaloisField, b: GaloisField) -> GaloisField {
+        if a == b {
+            a
+        } else {
+            let (p, modulus) = a.unify(&b);
+            GaloisField {
+                coefficients: vec![1],
+                p,
+                modulus,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_galois_field() {
+    // GF(3^2) with the modulus x^2 + 1
+    let modulus = Arc::new(vec![1, 0, 1]);
+    assert!(is_irreducible(&modulus, 3));
+    assert!(!is_irreducible(&[2, 0, 1], 3));
+
+    let a = GaloisField::generator(3, modulus.clone());
+    assert_eq!(
+        a.clone() * a.clone(),
+        GaloisField::new(vec![2], 3, modulus.clone())
+    );
+
+    // the multiplicative group has order 8
+    let b = GaloisField::new(vec![1, 1], 3, modulus.clone());
+    assert_eq!(b.clone().pow(8), GaloisField::one());
+    assert_eq!(b.clone() * b.inverse(), GaloisField::one());
+    assert_eq!(b.to_string(), "(1+a)");
+
+    // zero and one are mapped to GF(3^2) when they meet an element of the field
+    let one = GaloisField::new(vec![1], 3, modulus.clone());
+    assert_eq!(-GaloisField::one() + one.clone(), GaloisField::zero());
+    assert_eq!(
+        (GaloisField::one() + GaloisField::one()) * a.clone(),
+        GaloisField::new(vec![0, 2], 3, modulus.clone())
+    );
+    assert_eq!(
+        GaloisField::one().mul_num(5) * one,
+        GaloisField::new(vec![2], 3, modulus)
+    );
+    assert_eq!(a.to_prime_field(3), None);
+
+    let f = find_irreducible_polynomial(5, 4);
+    assert_eq!(f.len(), 5);
+    assert!(is_irreducible(&f, 5));
+}
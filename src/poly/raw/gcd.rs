@@ -7,18 +7,26 @@ use poly::ring::Ring;
 use fnv::FnvHashMap;
 use number;
 use number::Number;
-use poly::raw::finitefield::FiniteField;
+use poly::raw::algebraicnumber::AlgebraicNumber;
+use poly::raw::finitefield::{FiniteField, FiniteField64};
+use poly::raw::galoisfield::{find_irreducible_polynomial, is_irreducible, GaloisField};
+use poly::raw::rational_solve::number_mod_p;
+use poly::raw::univariate::{one_like, DenseMultiplication, UnivariatePolynomial};
 use poly::raw::zp;
 use poly::raw::zp::{ufield, FastModulus};
-use poly::raw::zp_mod::Modulus;
+use poly::raw::zp_mod::{Modulus, Montgomery64};
 use poly::raw::MultivariatePolynomial;
 use poly::ring::MulModNum;
 use poly::ring::ToFiniteField;
 use rand;
 use rand::distributions::{Distribution, Uniform};
 use rand::Rng;
-use std::cmp::{max, min};
+use rug::Integer;
+use std::cmp::{max, min, Ordering};
 use std::collections::hash_map::Entry;
+use std::fmt;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::sync::Arc;
 use tools::GCD;
 
 use ndarray::{arr1, Array};
@@ -41,6 +49,35 @@ pub const LARGE_U32_PRIMES: [ufield; 100] = [
     4293491591, 4293492169, 4293492821, 4293493487,
 ];
 
+// the 100 largest primes below 2^63
+pub const LARGE_U63_PRIMES: [u64; 100] = [
+    9223372036854775783, 9223372036854775643, 9223372036854775549, 9223372036854775507,
+    9223372036854775433, 9223372036854775421, 9223372036854775417, 9223372036854775399,
+    9223372036854775351, 9223372036854775337, 9223372036854775291, 9223372036854775279,
+    9223372036854775259, 9223372036854775181, 9223372036854775159, 9223372036854775139,
+    9223372036854775097, 9223372036854775073, 9223372036854775057, 9223372036854774959,
+    9223372036854774937, 9223372036854774917, 9223372036854774893, 9223372036854774797,
+    9223372036854774739, 9223372036854774713, 9223372036854774679, 9223372036854774629,
+    9223372036854774587, 9223372036854774571, 9223372036854774559, 9223372036854774511,
+    9223372036854774509, 9223372036854774499, 9223372036854774451, 9223372036854774413,
+    9223372036854774341, 9223372036854774319, 9223372036854774307, 9223372036854774277,
+    9223372036854774257, 9223372036854774247, 9223372036854774233, 9223372036854774199,
+    9223372036854774179, 9223372036854774173, 9223372036854774053, 9223372036854773999,
+    9223372036854773977, 9223372036854773953, 9223372036854773899, 9223372036854773867,
+    9223372036854773783, 9223372036854773639, 9223372036854773561, 9223372036854773557,
+    9223372036854773519, 9223372036854773507, 9223372036854773489, 9223372036854773477,
+    9223372036854773443, 9223372036854773429, 9223372036854773407, 9223372036854773353,
+    9223372036854773293, 9223372036854773173, 9223372036854773069, 9223372036854773047,
+    9223372036854772961, 9223372036854772957, 9223372036854772949, 9223372036854772903,
+    9223372036854772847, 9223372036854772801, 9223372036854772733, 9223372036854772681,
+    9223372036854772547, 9223372036854772469, 9223372036854772429, 9223372036854772367,
+    9223372036854772289, 9223372036854772241, 9223372036854772169, 9223372036854772141,
+    9223372036854772061, 9223372036854772051, 9223372036854772039, 9223372036854771989,
+    9223372036854771977, 9223372036854771973, 9223372036854771953, 9223372036854771869,
+    9223372036854771841, 9223372036854771833, 9223372036854771797, 9223372036854771749,
+    9223372036854771737, 9223372036854771727, 9223372036854771703, 9223372036854771689,
+];
+
 /// The maximum power of a variable that is cached
 pub const POW_CACHE_SIZE: usize = 1000;
 pub const INITIAL_POW_MAP_SIZE: usize = 1000;
@@ -48,12 +85,177 @@ pub const INITIAL_POW_MAP_SIZE: usize = 1000;
 /// The upper bound of the range to be sampled during the computation of multiple gcds
 pub const MAX_RNG_PREFACTOR: u32 = 5000;
 
+/// The default maximal size in bits of the integers created by the heuristic gcd.
+pub const DEFAULT_GCDHEU_MAX_BITS: usize = 4096;
+
+/// The number of evaluation points tried by the heuristic gcd before giving up.
+pub const GCDHEU_ATTEMPTS: usize = 6;
+
+/// The minimal degree from which the univariate gcd in a finite field switches to
+/// the dense representation.
+pub const DENSE_UNIVARIATE_GCD_THRESHOLD: usize = 32;
+
+/// The minimal number of evaluation points for the multivariate gcd in a finite field.
+/// For smaller primes the gcd is computed in an extension field GF(p^k).
+pub const MIN_EVALUATION_FIELD_SIZE: u64 = 1 << 16;
+
+static GCDHEU_MAX_BITS: AtomicUsize = AtomicUsize::new(DEFAULT_GCDHEU_MAX_BITS);
+
+static GCD_USE_U63_PRIMES: AtomicBool = AtomicBool::new(false);
+
+static GCD_STATS_HEURISTIC: AtomicUsize = AtomicUsize::new(0);
+static GCD_STATS_HEURISTIC_FAILED: AtomicUsize = AtomicUsize::new(0);
+static GCD_STATS_LARGE_PRIMES: AtomicUsize = AtomicUsize::new(0);
+static GCD_STATS_ZIPPEL: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the maximal size in bits of the integers created by the heuristic gcd.
+/// If the evaluation would create larger integers, the heuristic bails out and
+/// Zippel's algorithm is used instead. Use 0 to disable the heuristic gcd.
+pub fn set_gcdheu_max_bits(bits: usize) {
+    GCDHEU_MAX_BITS.store(bits, atomic::Ordering::Relaxed);
+}
+
+/// The size of the primes used by the modular gcd over the integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrimeSize {
+    /// Primes that fit in `ufield`.
+    U32,
+    /// Primes smaller than 2^63, using Montgomery multiplication. Polynomials with
+    /// large coefficients require fewer images for the Chinese remainder theorem.
+    /// The images are computed with dense interpolation instead of Zippel's
+    /// sparse interpolation.
+    U63,
+}
+
+/// Set the size of the primes used by the modular gcd over the integers.
+pub fn set_gcd_prime_size(size: PrimeSize) {
+    GCD_USE_U63_PRIMES.store(size == PrimeSize::U63, atomic::Ordering::Relaxed);
+}
+
+/// Get the size of the primes used by the modular gcd over the integers.
+pub fn gcd_prime_size() -> PrimeSize {
+    if GCD_USE_U63_PRIMES.load(atomic::Ordering::Relaxed) {
+        PrimeSize::U63
+    } else {
+        PrimeSize::U32
+    }
+}
+
+/// Counts how often each algorithm was used to compute the gcd of
+/// polynomials over the integers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GCDStatistics {
+    /// The gcd was computed by the heuristic gcd.
+    pub heuristic: usize,
+    /// The heuristic gcd bailed out or did not find the gcd.
+    pub heuristic_failed: usize,
+    /// The gcd was computed by the modular gcd with 63-bit primes.
+    pub large_primes: usize,
+    /// The gcd was computed by Zippel's algorithm.
+    pub zippel: usize,
+}
+
+impl fmt::Display for GCDStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "heuristic: {} (failed: {}), large primes: {}, zippel: {}",
+            self.heuristic, self.heuristic_failed, self.large_primes, self.zippel
+        )
+    }
+}
+
+/// Get the gcd statistics accumulated over all threads.
+pub fn gcd_statistics() -> GCDStatistics {
+    GCDStatistics {
+        heuristic: GCD_STATS_HEURISTIC.load(atomic::Ordering::Relaxed),
+        heuristic_failed: GCD_STATS_HEURISTIC_FAILED.load(atomic::Ordering::Relaxed),
+        large_primes: GCD_STATS_LARGE_PRIMES.load(atomic::Ordering::Relaxed),
+        zippel: GCD_STATS_ZIPPEL.load(atomic::Ordering::Relaxed),
+    }
+}
+
+/// Reset the gcd statistics.
+pub fn reset_gcd_statistics() {
+    GCD_STATS_HEURISTIC.store(0, atomic::Ordering::Relaxed);
+    GCD_STATS_HEURISTIC_FAILED.store(0, atomic::Ordering::Relaxed);
+    GCD_STATS_LARGE_PRIMES.store(0, atomic::Ordering::Relaxed);
+    GCD_STATS_ZIPPEL.store(0, atomic::Ordering::Relaxed);
+}
+
+/// Convert an integral number to a big integer.
+fn to_integer(n: &Number) -> Option<Integer> {
+    match n.clone().normalized() {
+        Number::SmallInt(i) => Some(Integer::from(i)),
+        Number::BigInt(i) => Some(i),
+        _ => None,
+    }
+}
+
+/// Map an integral number to a finite field with a prime `p < 2^63`.
+fn to_finite_field_64(n: &Number, p: Montgomery64) -> FiniteField64 {
+    match *n {
+        Number::SmallInt(i) => FiniteField64::from_i64(i as i64, p),
+        Number::BigInt(ref i) => {
+            let pp = Integer::from(p.value());
+            let mut r = i.clone() % &pp;
+            if r < 0 {
+                r += &pp;
+            }
+            FiniteField64::from_u64(r.to_u64().unwrap(), p)
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Map a polynomial with integral coefficients to a finite field with a prime `p < 2^63`.
+fn to_polynomial_64<E: Exponent>(
+    a: &MultivariatePolynomial<Number, E>,
+    p: Montgomery64,
+) -> MultivariatePolynomial<FiniteField64, E> {
+    let mut res = MultivariatePolynomial::with_nvars_and_capacity(a.nvars, a.nterms);
+    for t in a.into_iter() {
+        res.append_monomial_back(to_finite_field_64(t.coefficient, p), t.exponents);
+    }
+    res
+}
+
+/// Map a polynomial over a finite field with a prime `p < 2^63` to the integers,
+/// using the symmetric representation `-p/2 < c <= p/2`.
+fn from_polynomial_64<E: Exponent>(
+    a: &MultivariatePolynomial<FiniteField64, E>,
+) -> MultivariatePolynomial<Number, E> {
+    let mut res = MultivariatePolynomial::with_nvars_and_capacity(a.nvars, a.nterms);
+    for t in a.into_iter() {
+        let (v, p) = (t.coefficient.value(), t.coefficient.prime());
+        let c = if v > p / 2 {
+            v as isize - p as isize
+        } else {
+            v as isize
+        };
+        res.append_monomial_back(Number::SmallInt(c), t.exponents);
+    }
+    res
+}
+
+/// Get the exponents of `vars` in the leading monomial with respect to the
+/// lexicographic order of `vars`.
+fn leading_exponents<R: Ring, E: Exponent>(
+    a: &MultivariatePolynomial<R, E>,
+    vars: &[usize],
+) -> Vec<E> {
+    a.into_iter()
+        .map(|m| vars.iter().map(|&x| m.exponents[x].clone()).collect::<Vec<_>>())
+        .max()
+        .unwrap_or_default()
+}
+
 enum GCDError {
     BadOriginalImage,
     BadCurrentImage,
 }
 
-fn newton_interpolation<E: Exponent>(
+pub fn newton_interpolation<E: Exponent>(
     a: &[FiniteField],
     u: &[MultivariatePolynomial<FiniteField, E>],
     p: &FastModulus,
@@ -675,6 +877,18 @@ impl<E: Exponent> MultivariatePolynomial<FiniteField, E> {
             mem::swap(&mut c, &mut d);
         }
 
+        // use the dense representation for large polynomials that are not too sparse
+        let deg = c.ldegree_max().as_() as usize;
+        if deg >= DENSE_UNIVARIATE_GCD_THRESHOLD && c.nterms * 4 >= deg {
+            let x = c.last_exponents().iter().position(|e| !e.is_zero()).unwrap();
+            if let (Some(cd), Some(dd)) = (
+                UnivariatePolynomial::from_multivariate(&c, x),
+                UnivariatePolynomial::from_multivariate(&d, x),
+            ) {
+                return cd.gcd(&dd).to_multivariate(c.nvars, x);
+            }
+        }
+
         let p = FastModulus::from(a.coefficients[0].p);
 
         // TODO: there exists an efficient algorithm for univariate poly
@@ -790,6 +1004,46 @@ impl<E: Exponent> MultivariatePolynomial<FiniteField, E> {
         res
     }
 
+    /// Compute the gcd of two polynomials over GF(p) in the extension field GF(p^k),
+    /// where k is chosen such that the extension has at least `MIN_EVALUATION_FIELD_SIZE`
+    /// elements. Since the gcd is defined over GF(p), the monic gcd in GF(p^k) maps back.
+    fn gcd_in_extension(
+        a: &MultivariatePolynomial<FiniteField, E>,
+        b: &MultivariatePolynomial<FiniteField, E>,
+        vars: &[usize],
+        p: ufield,
+    ) -> MultivariatePolynomial<FiniteField, E> {
+        let mut k = 1;
+        let mut size = u64::from(p);
+        while size < MIN_EVALUATION_FIELD_SIZE {
+            k += 1;
+            size *= u64::from(p);
+        }
+
+        let modulus = Arc::new(find_irreducible_polynomial(p, k));
+        debug!(
+            "Computing gcd in GF({}^{}) with modulus {:?}",
+            p, k, modulus
+        );
+
+        let to_extension = |x: &MultivariatePolynomial<FiniteField, E>| {
+            let mut r = MultivariatePolynomial::with_nvars_and_capacity(x.nvars, x.nterms);
+            for t in x.into_iter() {
+                let c = GaloisField::new(vec![t.coefficient.n], p, modulus.clone());
+                r.append_monomial_back(c, t.exponents);
+            }
+            r
+        };
+
+        let g = MultivariatePolynomial::gcd_dense_modular(&to_extension(a), &to_extension(b), vars);
+
+        let mut r = MultivariatePolynomial::with_nvars_and_capacity(g.nvars, g.nterms);
+        for t in g.into_iter() {
+            r.append_monomial_back(t.coefficient.to_finite_field(p), t.exponents);
+        }
+        r
+    }
+
     /// Compute the gcd shape of two polynomials in a finite field by filling in random
     /// numbers.
     fn gcd_shape_modular(
@@ -813,6 +1067,16 @@ impl<E: Exponent> MultivariatePolynomial<FiniteField, E> {
             return Some(gg);
         }
 
+        // the field is too small to provide enough evaluation points
+        if u64::from(p.value()) < MIN_EVALUATION_FIELD_SIZE {
+            let gg = MultivariatePolynomial::gcd_in_extension(a, b, vars, p.value());
+            if gg.degree(vars[0]).as_() > bounds[vars[0]] {
+                return None;
+            }
+            bounds[vars[0]] = gg.degree(vars[0]).as_(); // update degree bound
+            return Some(gg);
+        }
+
         // the gcd of the content in the last variable should be 1
         let c = MultivariatePolynomial::multivariate_content_gcd(a, b, lastvar);
         if !c.is_one() {
@@ -1239,6 +1503,43 @@ where
         return g1.ldegree_max();
     }
 
+    /// Find upper bounds for the degrees of the variables `vars` in the gcd,
+    /// by computing univariate gcds in a finite field.
+    pub fn get_gcd_var_bounds_modular(
+        a: &MultivariatePolynomial<R, E>,
+        b: &MultivariatePolynomial<R, E>,
+        vars: &[usize],
+        loose_bounds: &[u32],
+    ) -> Vec<u32> {
+        let mut tight_bounds = loose_bounds.to_vec();
+        let mut i = 0;
+        loop {
+            let ap = a.to_finite_field(LARGE_U32_PRIMES[i]);
+            let bp = b.to_finite_field(LARGE_U32_PRIMES[i]);
+            if ap.nterms > 0
+                && bp.nterms > 0
+                && ap.last_exponents() == a.last_exponents()
+                && bp.last_exponents() == b.last_exponents()
+            {
+                for var in vars.iter() {
+                    let mut vvars = vars
+                        .iter()
+                        .filter(|i| *i != var)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    tight_bounds[*var] = MultivariatePolynomial::<Number, E>::get_gcd_var_bound(
+                        &ap, &bp, &vvars, *var,
+                    ).as_();
+                }
+                break;
+            } else {
+                debug!("Variable bounds failed due to unlucky prime");
+                i += 1;
+            }
+        }
+        tight_bounds
+    }
+
     /// Compute the gcd of two multivariate polynomials.
     pub fn gcd(
         a: &MultivariatePolynomial<R, E>,
@@ -1334,32 +1635,7 @@ where
 
         // find better upper bounds for all variables
         // these bounds could actually be wrong due to an unfortunate prime or sampling points
-        let mut tight_bounds = bounds.clone();
-        let mut i = 0;
-        loop {
-            let ap = a.to_finite_field(LARGE_U32_PRIMES[i]);
-            let bp = b.to_finite_field(LARGE_U32_PRIMES[i]);
-            if ap.nterms > 0
-                && bp.nterms > 0
-                && ap.last_exponents() == a.last_exponents()
-                && bp.last_exponents() == b.last_exponents()
-            {
-                for var in vars.iter() {
-                    let mut vvars = vars
-                        .iter()
-                        .filter(|i| *i != var)
-                        .cloned()
-                        .collect::<Vec<_>>();
-                    tight_bounds[*var] = MultivariatePolynomial::<Number, E>::get_gcd_var_bound(
-                        &ap, &bp, &vvars, *var,
-                    ).as_();
-                }
-                break;
-            } else {
-                debug!("Variable bounds failed due to unlucky prime");
-                i += 1;
-            }
-        }
+        let mut tight_bounds = PolynomialGCD::get_gcd_var_bounds(a, b, &vars, &bounds);
 
         // Determine a good variable ordering based on the estimated degree (decreasing) in the gcd.
         // If it is different from the input, make a copy and rearrange so that the
@@ -1422,6 +1698,168 @@ where
 }
 
 impl<E: Exponent> MultivariatePolynomial<Number, E> {
+    /// Compute the gcd of two multivariate polynomials with integer coefficients
+    /// using the heuristic gcd (GCDHEU): the variables are evaluated at a
+    /// large integer `xi`, the integer gcd is computed and the gcd is
+    /// reconstructed by a `xi`-adic expansion. The result is verified by division.
+    /// Returns `None` if the heuristic fails or the integers become too large.
+    /// Reference: "GCDHEU: Heuristic polynomial GCD algorithm based on integer GCD computation"
+    /// by Char, Geddes, Gonnet (1989)
+    pub fn gcd_heuristic(
+        a: &MultivariatePolynomial<Number, E>,
+        b: &MultivariatePolynomial<Number, E>,
+        vars: &[usize],
+    ) -> Option<MultivariatePolynomial<Number, E>> {
+        let max_bits = GCDHEU_MAX_BITS.load(atomic::Ordering::Relaxed);
+        if max_bits == 0 {
+            return None;
+        }
+
+        // the heuristic only works over the integers
+        for x in a.coefficients.iter().chain(&b.coefficients) {
+            match *x {
+                Number::SmallRat(..) | Number::BigRat(..) => return None,
+                _ => {}
+            }
+        }
+
+        let mut g = MultivariatePolynomial::gcd_heuristic_rec(a, b, vars, max_bits as u32)?;
+
+        // use the same normalization as Zippel's algorithm
+        if g.lcoeff_varorder(vars) < Number::zero() {
+            g = -g;
+        }
+        Some(g)
+    }
+
+    fn gcd_heuristic_rec(
+        a: &MultivariatePolynomial<Number, E>,
+        b: &MultivariatePolynomial<Number, E>,
+        vars: &[usize],
+        max_bits: u32,
+    ) -> Option<MultivariatePolynomial<Number, E>> {
+        if a.is_zero() {
+            return Some(b.clone());
+        }
+        if b.is_zero() {
+            return Some(a.clone());
+        }
+
+        // remove the integer content
+        let ca = a.content();
+        let cb = b.content();
+        let c = GCD::gcd(ca.clone(), cb.clone());
+        let mut a = a.clone();
+        let mut b = b.clone();
+        for x in &mut a.coefficients {
+            *x = mem::replace(x, Number::zero()) / ca.clone();
+        }
+        for x in &mut b.coefficients {
+            *x = mem::replace(x, Number::zero()) / cb.clone();
+        }
+
+        let (v, rest) = match vars.split_last() {
+            Some((v, rest)) => (*v, rest),
+            None => {
+                debug_assert!(a.is_constant() && b.is_constant());
+                return Some(MultivariatePolynomial::from_constant_with_nvars(c, a.nvars));
+            }
+        };
+
+        // choose xi larger than twice the coefficients of the gcd
+        let mut max_a = Integer::new();
+        for x in &a.coefficients {
+            let x = to_integer(x)?.abs();
+            if x > max_a {
+                max_a = x;
+            }
+        }
+        let mut max_b = Integer::new();
+        for x in &b.coefficients {
+            let x = to_integer(x)?.abs();
+            if x > max_b {
+                max_b = x;
+            }
+        }
+        let mut xi = Integer::from(max_a.min(max_b) * 2u32) + 29u32;
+
+        let deg: u32 = max(a.degree(v), b.degree(v)).as_();
+
+        for _ in 0..GCDHEU_ATTEMPTS {
+            if xi.significant_bits() * (deg + 1) > max_bits {
+                debug!("Heuristic gcd bails out: evaluation point too large");
+                return None;
+            }
+
+            let xin = Number::BigInt(xi.clone()).normalized();
+            let ae = a.replace(v, xin.clone());
+            let be = b.replace(v, xin);
+
+            if let Some(ge) = MultivariatePolynomial::gcd_heuristic_rec(&ae, &be, rest, max_bits) {
+                let mut g = MultivariatePolynomial::xi_adic_reconstruction(&ge, &xi, v)?;
+                if g.is_zero() {
+                    return None;
+                }
+
+                let gc = g.content();
+                for x in &mut g.coefficients {
+                    *x = mem::replace(x, Number::zero()) / gc.clone();
+                }
+
+                if a.divmod(&g).1.is_zero() && b.divmod(&g).1.is_zero() {
+                    return Some(g * c);
+                }
+            }
+
+            // the golden ratio makes it unlikely that xi becomes unlucky again
+            xi = xi * 73794u32 / 27011u32;
+        }
+
+        None
+    }
+
+    /// Reconstruct a polynomial in the variable `v` from its image `g` at `v = xi`,
+    /// by taking the coefficients of the `xi`-adic expansion in the
+    /// symmetric representation.
+    fn xi_adic_reconstruction(
+        g: &MultivariatePolynomial<Number, E>,
+        xi: &Integer,
+        v: usize,
+    ) -> Option<MultivariatePolynomial<Number, E>> {
+        let mut coefficients = Vec::with_capacity(g.nterms);
+        for x in &g.coefficients {
+            coefficients.push(to_integer(x)?);
+        }
+
+        let half = Integer::from(xi >> 1u32);
+        let mut res = MultivariatePolynomial::with_nvars(g.nvars);
+        let mut e = vec![E::zero(); g.nvars];
+        let mut pow = 0;
+        while coefficients.iter().any(|x| *x != 0) {
+            for (t, x) in coefficients.iter_mut().enumerate() {
+                if *x == 0 {
+                    continue;
+                }
+
+                let (mut q, mut r) = mem::replace(x, Integer::new()).div_rem_floor(xi.clone());
+                if r > half {
+                    r -= xi;
+                    q += 1u32;
+                }
+                *x = q;
+
+                if r != 0 {
+                    e.copy_from_slice(g.exponents(t));
+                    e[v] = E::from_u32(pow)?;
+                    res.append_monomial(Number::BigInt(r).normalized(), &e);
+                }
+            }
+            pow += 1;
+        }
+
+        Some(res)
+    }
+
     /// Compute the gcd of two multivariate polynomials using Zippel's algorithm.
     /// TODO: provide a parallel implementation?
     fn gcd_zippel(
@@ -1677,6 +2115,522 @@ impl<E: Exponent> MultivariatePolynomial<Number, E> {
             }
         }
     }
+
+    /// Compute the gcd of two multivariate polynomials over the integers using
+    /// a modular algorithm with primes smaller than 2^63. Since the images are
+    /// much larger than for `ufield`, fewer primes are needed for large coefficients.
+    /// The images are computed with dense evaluation and interpolation, see
+    /// `gcd_dense_modular`. Returns `None` if the algorithm runs out of primes.
+    pub fn gcd_large_primes(
+        a: &MultivariatePolynomial<Number, E>,
+        b: &MultivariatePolynomial<Number, E>,
+        vars: &[usize],
+    ) -> Option<MultivariatePolynomial<Number, E>> {
+        let content = GCD::gcd(a.content(), b.content());
+        let la = a.lcoeff_varorder(vars);
+        let lb = b.lcoeff_varorder(vars);
+        let gamma = GCD::gcd(la.clone(), lb.clone());
+
+        let mut bound = None;
+        let mut gm = MultivariatePolynomial::with_nvars(a.nvars);
+        let mut old_gm = MultivariatePolynomial::with_nvars(a.nvars);
+        let mut m = Number::one(); // used for CRT
+
+        for &p in LARGE_U63_PRIMES.iter() {
+            let fastp = Montgomery64::new(p);
+
+            // the leading monomials should not vanish
+            if to_finite_field_64(&la, fastp).is_zero() || to_finite_field_64(&lb, fastp).is_zero()
+            {
+                continue;
+            }
+
+            let gammap = to_finite_field_64(&gamma, fastp);
+            let ap = to_polynomial_64(a, fastp);
+            let bp = to_polynomial_64(b, fastp);
+
+            let gp = MultivariatePolynomial::gcd_dense_modular(&ap, &bp, vars);
+            debug!("Image {} mod {}", gp, p);
+
+            if gp.is_constant() {
+                return Some(MultivariatePolynomial::from_constant_with_nvars(
+                    content, a.nvars,
+                ));
+            }
+
+            // an unlucky prime can only increase the leading monomial
+            let lm = leading_exponents(&gp, vars);
+            match bound.as_ref().map(|b| lm.cmp(b)) {
+                Some(Ordering::Greater) => {
+                    debug!("Unlucky current image: try new one");
+                    continue;
+                }
+                Some(Ordering::Equal) => {}
+                _ => {
+                    if bound.is_some() {
+                        debug!("Unlucky original image: restart");
+                    }
+                    bound = Some(lm);
+                    gm = from_polynomial_64(&(gp * gammap));
+                    old_gm = MultivariatePolynomial::with_nvars(a.nvars);
+                    m = Number::SmallInt(p as isize);
+                    continue;
+                }
+            }
+
+            // use chinese remainder theorem to merge the coefficients: the new coefficients
+            // are gm + m * ((gp - gm) / m mod p), mapped to the symmetric range
+            let diff = (gp * gammap - to_polynomial_64(&gm, fastp))
+                * to_finite_field_64(&m, fastp).inverse();
+            gm = gm + from_polynomial_64(&diff) * m.clone();
+            m *= Number::SmallInt(p as isize);
+            for c in &mut gm.coefficients {
+                let c2 = c.clone() * Number::SmallInt(2);
+                if c2 > m {
+                    *c = c.clone() - m.clone();
+                } else if -c2 >= m {
+                    *c = c.clone() + m.clone();
+                }
+            }
+            debug!("gm: {} from ring {}", gm, m);
+
+            if gm == old_gm {
+                // divide by the integer content
+                let mut gmc = gm.content();
+                if gm.lcoeff_varorder(vars) < Number::SmallInt(0) {
+                    gmc = -gmc;
+                }
+                let mut gc = gm.clone();
+                gc.coefficients = gc
+                    .coefficients
+                    .iter()
+                    .map(|x| x.clone() / gmc.clone())
+                    .collect();
+
+                debug!("Final suggested gcd: {}", gc);
+                if a.divmod(&gc).1.is_zero() && b.divmod(&gc).1.is_zero() {
+                    return Some(gc * content);
+                }
+
+                // if it does not divide, we need more primes
+                debug!("Does not divide: more primes needed");
+            }
+
+            old_gm = gm.clone();
+        }
+
+        None
+    }
+}
+
+/// A finite field that provides the evaluation points for the dense modular gcd.
+pub trait EvaluationField: DenseMultiplication {
+    /// Sample a random element of the field that `self` belongs to.
+    fn sample<G: Rng>(&self, rng: &mut G) -> Self;
+}
+
+impl EvaluationField for FiniteField64 {
+    fn sample<G: Rng>(&self, rng: &mut G) -> FiniteField64 {
+        FiniteField64::from_u64(Uniform::new(1, self.prime()).sample(rng), self.modulus())
+    }
+}
+
+impl EvaluationField for GaloisField {
+    fn sample<G: Rng>(&self, rng: &mut G) -> GaloisField {
+        let range = Uniform::new(0, self.p);
+        let c = (1..self.modulus.len()).map(|_| range.sample(rng)).collect();
+        GaloisField::new(c, self.p, self.modulus.clone())
+    }
+}
+
+impl<F: EvaluationField, E: Exponent> MultivariatePolynomial<F, E> {
+    /// Compute the univariate gcd in `x` using the dense representation. The result is monic.
+    fn univariate_gcd_dense(
+        a: &MultivariatePolynomial<F, E>,
+        b: &MultivariatePolynomial<F, E>,
+        x: usize,
+    ) -> MultivariatePolynomial<F, E> {
+        let ad = UnivariatePolynomial::from_multivariate(a, x).unwrap();
+        let bd = UnivariatePolynomial::from_multivariate(b, x).unwrap();
+        ad.gcd(&bd).to_multivariate(a.nvars, x)
+    }
+
+    /// Compute the content of a polynomial in the variables `vars` except the
+    /// last one, as a monic univariate polynomial in the last variable.
+    fn content_last_var(
+        a: &MultivariatePolynomial<F, E>,
+        vars: &[usize],
+    ) -> MultivariatePolynomial<F, E> {
+        let lastvar = *vars.last().unwrap();
+        let mut coeffs = a
+            .to_multivariate_polynomial(&vars[..vars.len() - 1], true)
+            .into_iter()
+            .map(|(_, c)| c);
+
+        let first = coeffs.next().unwrap();
+        let lc = first.lcoeff();
+        let mut c = first * (one_like(&lc) / lc);
+        for x in coeffs {
+            if c.is_constant() {
+                break;
+            }
+            c = MultivariatePolynomial::univariate_gcd_dense(&c, &x, lastvar);
+        }
+        c
+    }
+
+    /// Compute the gcd of two polynomials over a finite field using dense evaluation
+    /// and interpolation of the variables in `vars` (Brown's algorithm). The polynomials
+    /// should only depend on `vars` and should not be zero. The result is monic with
+    /// respect to the lexicographic order of `vars`.
+    pub fn gcd_dense_modular(
+        a: &MultivariatePolynomial<F, E>,
+        b: &MultivariatePolynomial<F, E>,
+        vars: &[usize],
+    ) -> MultivariatePolynomial<F, E> {
+        if vars.len() == 1 {
+            return MultivariatePolynomial::univariate_gcd_dense(a, b, vars[0]);
+        }
+
+        let (rest, lastvar) = (&vars[..vars.len() - 1], *vars.last().unwrap());
+
+        // remove the content in the last variable
+        let ca = MultivariatePolynomial::content_last_var(a, vars);
+        let cb = MultivariatePolynomial::content_last_var(b, vars);
+        let c = MultivariatePolynomial::univariate_gcd_dense(&ca, &cb, lastvar);
+        let a = a.divmod(&ca).0;
+        let b = b.divmod(&cb).0;
+
+        let gamma = MultivariatePolynomial::univariate_gcd_dense(
+            &a.lcoeff_last_varorder(vars),
+            &b.lcoeff_last_varorder(vars),
+            lastvar,
+        );
+
+        // the number of points needed to interpolate the last variable
+        let d = min(a.degree(lastvar), b.degree(lastvar)) + gamma.degree(lastvar);
+        let npoints = d.to_usize().unwrap() + 1;
+
+        let one = one_like(&a.coefficients[0]);
+        let mut e = vec![E::zero(); a.nvars];
+        e[lastvar] = E::one();
+        let x = MultivariatePolynomial::from_monomial(one.clone(), e);
+
+        let mut rng = rand::thread_rng();
+
+        let mut bound = None;
+        let mut gv = MultivariatePolynomial::with_nvars(a.nvars); // the interpolation
+        let mut q = MultivariatePolynomial::from_constant_with_nvars(one.clone(), a.nvars);
+        let mut vseq = vec![];
+
+        loop {
+            let v = a.coefficients[0].sample(&mut rng);
+            let gammav = gamma.replace(lastvar, v.clone());
+            if gammav.is_zero() || vseq.contains(&v) {
+                continue;
+            }
+
+            let g = MultivariatePolynomial::gcd_dense_modular(
+                &a.replace(lastvar, v.clone()),
+                &b.replace(lastvar, v.clone()),
+                rest,
+            );
+
+            // an unlucky evaluation can only increase the leading monomial
+            let lm = leading_exponents(&g, rest);
+            match bound.as_ref().map(|b| lm.cmp(b)) {
+                Some(Ordering::Greater) => continue,
+                Some(Ordering::Equal) => {}
+                _ => {
+                    bound = Some(lm);
+                    gv = MultivariatePolynomial::with_nvars(a.nvars);
+                    q = MultivariatePolynomial::from_constant_with_nvars(one.clone(), a.nvars);
+                    vseq.clear();
+                }
+            }
+
+            // incremental Newton interpolation
+            let g = g * gammav.coefficients[0].clone();
+            let qv = q.replace(lastvar, v.clone()).coefficients[0].clone();
+            let delta = g - gv.replace(lastvar, v.clone());
+            let unchanged = delta.is_zero();
+            gv = gv + delta * q.clone() * (one.clone() / qv);
+            q = q
+                * (x.clone()
+                    - MultivariatePolynomial::from_constant_with_nvars(v.clone(), a.nvars));
+            vseq.push(v);
+
+            if !unchanged && vseq.len() < npoints {
+                continue;
+            }
+
+            // remove the content in the last variable and do a division test
+            let cg = MultivariatePolynomial::content_last_var(&gv, vars);
+            let gc = gv.divmod(&cg).0;
+            if a.divmod(&gc).1.is_zero() && b.divmod(&gc).1.is_zero() {
+                let gc = gc * c;
+                let lc = gc.lcoeff_varorder(vars);
+                return gc * (one / lc);
+            }
+
+            if vseq.len() >= npoints {
+                debug!("Division test failed: restart with new evaluation points");
+                bound = None;
+            }
+        }
+    }
+}
+
+impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E>
+where
+    MultivariatePolynomial<R, E>: PolynomialGCD,
+{
+    /// Get the coefficient of `x^d`, viewing the polynomial as a univariate polynomial in `x`.
+    fn univariate_coefficient(&self, x: usize, d: u32) -> MultivariatePolynomial<R, E> {
+        self.to_univariate_polynomial(x)
+            .into_iter()
+            .find(|c| c.1 == d)
+            .map(|c| c.0)
+            .unwrap_or_else(|| MultivariatePolynomial::with_nvars(self.nvars))
+    }
+
+    /// Compute a multiple of the pseudo-remainder of the division of `self` by `div`,
+    /// viewed as univariate polynomials in `x`.
+    fn pseudo_remainder(
+        &self,
+        div: &MultivariatePolynomial<R, E>,
+        x: usize,
+    ) -> MultivariatePolynomial<R, E> {
+        let db = div.degree(x).as_();
+        let lcb = div.univariate_coefficient(x, db);
+
+        let mut e = vec![E::zero(); self.nvars];
+        let mut r = self.clone();
+        while !r.is_zero() && r.degree(x).as_() >= db {
+            let dr = r.degree(x);
+            let lcr = r.univariate_coefficient(x, dr.as_());
+            e[x] = dr - E::from_u32(db).unwrap();
+            r = r * lcb.clone() - lcr * div.mul_exponents(&e);
+        }
+        r
+    }
+
+    /// Compute the gcd of two polynomials over a field, using the primitive
+    /// polynomial remainder sequence in the first variable of `vars`. The contents
+    /// are computed recursively. The gcd is normalized to have leading coefficient 1.
+    pub fn gcd_prs(
+        a: &MultivariatePolynomial<R, E>,
+        b: &MultivariatePolynomial<R, E>,
+        vars: &[usize],
+    ) -> MultivariatePolynomial<R, E> {
+        let x = vars[0];
+
+        let ca = a.univariate_content(x);
+        let cb = b.univariate_content(x);
+        let c = MultivariatePolynomial::gcd(&ca, &cb);
+
+        let mut f0 = a.divmod(&ca).0;
+        let mut f1 = b.divmod(&cb).0;
+        if f0.degree(x) < f1.degree(x) {
+            mem::swap(&mut f0, &mut f1);
+        }
+
+        while !f1.is_zero() {
+            if f1.degree(x).is_zero() {
+                // the primitive parts are coprime
+                f0 = MultivariatePolynomial::from_constant_with_nvars(R::one(), a.nvars);
+                break;
+            }
+
+            let r = f0.pseudo_remainder(&f1, x);
+            f0 = f1;
+            f1 = if r.is_zero() {
+                r
+            } else {
+                let cr = r.univariate_content(x);
+                r.divmod(&cr).0
+            };
+        }
+
+        let g = c * f0;
+        let inv = R::one() / g.lcoeff();
+        g * inv
+    }
+}
+
+impl<E: Exponent> MultivariatePolynomial<AlgebraicNumber, E> {
+    /// Map the polynomial to GF(p^k), where `modulus` is the image of the minimal
+    /// polynomial. Returns `None` if a denominator vanishes.
+    fn to_galois_field(
+        &self,
+        p: ufield,
+        modulus: &Arc<Vec<ufield>>,
+    ) -> Option<MultivariatePolynomial<GaloisField, E>> {
+        let mut r = MultivariatePolynomial::with_nvars_and_capacity(self.nvars, self.nterms);
+        for t in self.into_iter() {
+            let c = t.coefficient.to_galois_field(p, modulus)?;
+            if !c.is_zero() {
+                r.append_monomial_back(c, t.exponents);
+            }
+        }
+        Some(r)
+    }
+
+    /// Compute the gcd of two polynomials over an algebraic number field using a
+    /// modular algorithm. The gcd is computed in GF(p^k) for primes for which the
+    /// minimal polynomial stays irreducible, the images are merged with the Chinese
+    /// remainder theorem and the rational coefficients are found by rational reconstruction.
+    /// The gcd is normalized to have leading coefficient 1. Returns `None` if there
+    /// are not enough suitable primes, for example when the minimal polynomial is
+    /// reducible modulo every prime.
+    pub fn gcd_modular_algebraic(
+        a: &MultivariatePolynomial<AlgebraicNumber, E>,
+        b: &MultivariatePolynomial<AlgebraicNumber, E>,
+    ) -> Option<MultivariatePolynomial<AlgebraicNumber, E>> {
+        let minimal_polynomial = a
+            .coefficients
+            .iter()
+            .chain(&b.coefficients)
+            .map(|c| c.minimal_polynomial.clone())
+            .find(|m| !m.is_empty())?;
+        let k = minimal_polynomial.len() - 1;
+        let nvars = a.nvars;
+
+        let mut degrees: Vec<u32> = vec![];
+        let mut gm = MultivariatePolynomial::with_nvars(nvars);
+        let mut gm_coeffs: Vec<Vec<Number>> = vec![];
+        let mut m = Number::one(); // used for CRT
+        let mut old_g = None;
+
+        for &p in LARGE_U32_PRIMES.iter() {
+            let modulus: Option<Vec<ufield>> = minimal_polynomial
+                .iter()
+                .map(|c| number_mod_p(c, p))
+                .collect();
+            let modulus = match modulus {
+                Some(x) => {
+                    if is_irreducible(&x, p) {
+                        Arc::new(x)
+                    } else {
+                        continue;
+                    }
+                }
+                None => continue,
+            };
+
+            let (ap, bp) = match (
+                a.to_galois_field(p, &modulus),
+                b.to_galois_field(p, &modulus),
+            ) {
+                (Some(ap), Some(bp)) => (ap, bp),
+                _ => continue,
+            };
+
+            if ap.nterms == 0
+                || bp.nterms == 0
+                || ap.last_exponents() != a.last_exponents()
+                || bp.last_exponents() != b.last_exponents()
+            {
+                continue;
+            }
+
+            let gp = MultivariatePolynomial::gcd(&ap, &bp);
+            let inv = GaloisField::one() / gp.lcoeff();
+            let gp = gp * inv;
+            debug!("Image of gcd: {} in GF({}^{})", gp, p, k);
+
+            // an unlucky prime can only increase the degree
+            if gp.is_one() {
+                return Some(MultivariatePolynomial::from_constant_with_nvars(
+                    AlgebraicNumber::one(),
+                    nvars,
+                ));
+            }
+
+            let gp_degrees: Vec<u32> = (0..nvars).map(|v| gp.degree(v).as_()).collect();
+            if degrees.is_empty()
+                || (gp_degrees != degrees && gp_degrees.iter().zip(&degrees).all(|(x, y)| x <= y))
+            {
+                debug!("New first image");
+                degrees = gp_degrees;
+                gm = gp.clone();
+                gm_coeffs = gp
+                    .coefficients
+                    .iter()
+                    .map(|c| {
+                        (0..k)
+                            .map(
+                                |i| Number::SmallInt(*c.coefficients.get(i).unwrap_or(&0) as isize),
+                            )
+                            .collect()
+                    })
+                    .collect();
+                m = Number::SmallInt(p as isize);
+                old_g = None;
+                continue;
+            }
+
+            if gp.exponents != gm.exponents {
+                debug!("Unlucky current image: try new one");
+                continue;
+            }
+
+            // use chinese remainder theorem to merge coefficients
+            for (gmc, gpc) in gm_coeffs.iter_mut().zip(&gp.coefficients) {
+                for (i, c) in gmc.iter_mut().enumerate() {
+                    let coeff = if *c < Number::zero() {
+                        c.clone() + m.clone()
+                    } else {
+                        c.clone()
+                    };
+
+                    *c = number::chinese_remainder(
+                        coeff,
+                        Number::SmallInt(*gpc.coefficients.get(i).unwrap_or(&0) as isize),
+                        m.clone(),
+                        Number::SmallInt(p as isize),
+                    );
+                }
+            }
+            m *= Number::SmallInt(p as isize);
+
+            // reconstruct the rational coefficients
+            let mut g = MultivariatePolynomial::with_nvars_and_capacity(nvars, gm.nterms);
+            'term: for (t, gmc) in gm_coeffs.iter().enumerate() {
+                let mut c = Vec::with_capacity(k);
+                for x in gmc {
+                    match number::rational_reconstruction(x, &m) {
+                        Some(r) => c.push(r),
+                        None => {
+                            g = MultivariatePolynomial::with_nvars(nvars);
+                            break 'term;
+                        }
+                    }
+                }
+                g.append_monomial_back(
+                    AlgebraicNumber::new(c, minimal_polynomial.clone()),
+                    gm.exponents(t),
+                );
+            }
+
+            if g.is_zero() {
+                continue;
+            }
+
+            if old_g.as_ref() == Some(&g) {
+                debug!("Final suggested gcd: {}", g);
+                if a.divmod(&g).1.is_zero() && b.divmod(&g).1.is_zero() {
+                    return Some(g);
+                }
+
+                // if it does not divide, we need more primes
+                debug!("Does not divide: more primes needed");
+            }
+            old_g = Some(g);
+        }
+
+        None
+    }
 }
 
 pub trait PolynomialGCD: Sized {
@@ -1687,6 +2641,10 @@ pub trait PolynomialGCD: Sized {
         bounds: &mut [u32],
         tight_bounds: &mut [u32],
     ) -> Self;
+
+    /// Find upper bounds for the degrees of the variables `vars` in the gcd
+    /// that are at most `loose_bounds`.
+    fn get_gcd_var_bounds(a: &Self, b: &Self, vars: &[usize], loose_bounds: &[u32]) -> Vec<u32>;
 }
 
 impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<Number, E> {
@@ -1697,8 +2655,34 @@ impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<Number, E> {
         bounds: &mut [u32],
         tight_bounds: &mut [u32],
     ) -> MultivariatePolynomial<Number, E> {
+        // first try the heuristic gcd, which is fast for small dense polynomials
+        if let Some(g) = MultivariatePolynomial::gcd_heuristic(&a, &b, vars) {
+            GCD_STATS_HEURISTIC.fetch_add(1, atomic::Ordering::Relaxed);
+            return g;
+        }
+        GCD_STATS_HEURISTIC_FAILED.fetch_add(1, atomic::Ordering::Relaxed);
+
+        if gcd_prime_size() == PrimeSize::U63 {
+            if let Some(g) = MultivariatePolynomial::gcd_large_primes(&a, &b, vars) {
+                GCD_STATS_LARGE_PRIMES.fetch_add(1, atomic::Ordering::Relaxed);
+                return g;
+            }
+            debug!("Modular gcd with large primes failed, using Zippel's algorithm");
+        }
+
+        debug!("Heuristic gcd failed, using Zippel's algorithm");
+        GCD_STATS_ZIPPEL.fetch_add(1, atomic::Ordering::Relaxed);
         MultivariatePolynomial::gcd_zippel(&a, &b, vars, bounds, tight_bounds)
     }
+
+    fn get_gcd_var_bounds(
+        a: &MultivariatePolynomial<Number, E>,
+        b: &MultivariatePolynomial<Number, E>,
+        vars: &[usize],
+        loose_bounds: &[u32],
+    ) -> Vec<u32> {
+        MultivariatePolynomial::get_gcd_var_bounds_modular(a, b, vars, loose_bounds)
+    }
 }
 
 impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<FiniteField, E> {
@@ -1723,4 +2707,60 @@ impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<FiniteField, E> {
             }),
         ).unwrap()
     }
+
+    fn get_gcd_var_bounds(
+        a: &MultivariatePolynomial<FiniteField, E>,
+        b: &MultivariatePolynomial<FiniteField, E>,
+        vars: &[usize],
+        loose_bounds: &[u32],
+    ) -> Vec<u32> {
+        MultivariatePolynomial::get_gcd_var_bounds_modular(a, b, vars, loose_bounds)
+    }
+}
+
+impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<GaloisField, E> {
+    fn gcd(
+        a: &MultivariatePolynomial<GaloisField, E>,
+        b: &MultivariatePolynomial<GaloisField, E>,
+        vars: &[usize],
+        _bounds: &mut [u32],
+        _tight_bounds: &mut [u32],
+    ) -> MultivariatePolynomial<GaloisField, E> {
+        MultivariatePolynomial::gcd_prs(a, b, vars)
+    }
+
+    fn get_gcd_var_bounds(
+        _a: &MultivariatePolynomial<GaloisField, E>,
+        _b: &MultivariatePolynomial<GaloisField, E>,
+        _vars: &[usize],
+        loose_bounds: &[u32],
+    ) -> Vec<u32> {
+        loose_bounds.to_vec()
+    }
+}
+
+impl<E: Exponent> PolynomialGCD for MultivariatePolynomial<AlgebraicNumber, E> {
+    fn gcd(
+        a: &MultivariatePolynomial<AlgebraicNumber, E>,
+        b: &MultivariatePolynomial<AlgebraicNumber, E>,
+        vars: &[usize],
+        _bounds: &mut [u32],
+        _tight_bounds: &mut [u32],
+    ) -> MultivariatePolynomial<AlgebraicNumber, E> {
+        if let Some(g) = MultivariatePolynomial::gcd_modular_algebraic(a, b) {
+            return g;
+        }
+
+        debug!("Modular gcd failed, using the polynomial remainder sequence");
+        MultivariatePolynomial::gcd_prs(a, b, vars)
+    }
+
+    fn get_gcd_var_bounds(
+        _a: &MultivariatePolynomial<AlgebraicNumber, E>,
+        _b: &MultivariatePolynomial<AlgebraicNumber, E>,
+        _vars: &[usize],
+        loose_bounds: &[u32],
+    ) -> Vec<u32> {
+        loose_bounds.to_vec()
+    }
 }
@@ -0,0 +1,204 @@
+//! Laurent polynomials: polynomials that may contain negative powers of the variables.
+
+use num_traits::{One, Zero};
+use poly::exponent::Exponent;
+use poly::raw::gcd::PolynomialGCD;
+use poly::raw::MultivariatePolynomial;
+use poly::ring::Ring;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A Laurent polynomial `poly / x^shift`, stored with an offset for every variable.
+/// In normalized form, a variable either has a zero offset or
+/// does not divide all terms of `poly`, so that the representation is unique.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LaurentPolynomial<R: Ring, E: Exponent> {
+    pub poly: MultivariatePolynomial<R, E>,
+    pub shift: Vec<E>,
+}
+
+impl<R: Ring, E: Exponent> LaurentPolynomial<R, E> {
+    /// Create the Laurent polynomial `poly / x^shift`.
+    pub fn new(poly: MultivariatePolynomial<R, E>, shift: Vec<E>) -> LaurentPolynomial<R, E> {
+        debug_assert!(poly.nvars == shift.len());
+        let mut res = LaurentPolynomial { poly, shift };
+        res.normalize();
+        res
+    }
+
+    /// Create a Laurent polynomial without negative exponents.
+    pub fn from_poly(poly: MultivariatePolynomial<R, E>) -> LaurentPolynomial<R, E> {
+        let shift = vec![E::zero(); poly.nvars];
+        LaurentPolynomial { poly, shift }
+    }
+
+    /// Remove the monomial content of the polynomial that can be
+    /// cancelled against the offset.
+    pub fn normalize(&mut self) {
+        if self.poly.is_zero() {
+            for s in &mut self.shift {
+                *s = E::zero();
+            }
+            return;
+        }
+
+        let mut content = self.poly.monomial_content();
+        let mut changed = false;
+        for (c, s) in content.iter_mut().zip(&mut self.shift) {
+            if *c > *s {
+                *c = s.clone();
+            }
+            if !c.is_zero() {
+                *s = s.clone() - c.clone();
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.poly = self.poly.div_exponents(&content);
+        }
+    }
+
+    /// Returns `true` if there are no negative exponents.
+    pub fn is_polynomial(&self) -> bool {
+        self.shift.iter().all(|s| s.is_zero())
+    }
+
+    /// Get the numerator and the monomial denominator.
+    pub fn to_fraction(&self) -> (MultivariatePolynomial<R, E>, MultivariatePolynomial<R, E>) {
+        (
+            self.poly.clone(),
+            MultivariatePolynomial::from_monomial(R::one(), self.shift.clone()),
+        )
+    }
+
+    /// Bring `self` and `other` to a common offset, which is the largest
+    /// offset of every variable.
+    fn align(
+        &self,
+        other: &LaurentPolynomial<R, E>,
+    ) -> (
+        MultivariatePolynomial<R, E>,
+        MultivariatePolynomial<R, E>,
+        Vec<E>,
+    ) {
+        let shift: Vec<E> = self
+            .shift
+            .iter()
+            .zip(&other.shift)
+            .map(|(a, b)| a.max(b).clone())
+            .collect();
+
+        let sa: Vec<E> = shift
+            .iter()
+            .zip(&self.shift)
+            .map(|(s, a)| s.clone() - a.clone())
+            .collect();
+        let sb: Vec<E> = shift
+            .iter()
+            .zip(&other.shift)
+            .map(|(s, b)| s.clone() - b.clone())
+            .collect();
+
+        (
+            self.poly.mul_exponents(&sa),
+            other.poly.mul_exponents(&sb),
+            shift,
+        )
+    }
+
+    /// Divide two Laurent polynomials. Since monomials are units,
+    /// the offsets and the monomial content of `div` are cleared first.
+    pub fn divmod(
+        &self,
+        div: &LaurentPolynomial<R, E>,
+    ) -> (LaurentPolynomial<R, E>, LaurentPolynomial<R, E>) {
+        let content = div.poly.monomial_content();
+        let d = div.poly.div_exponents(&content);
+
+        // self.poly * x^(div.shift) = q * d + r
+        let (q, r) = self.poly.mul_exponents(&div.shift).divmod(&d);
+
+        let add_shift = |a: &[E], b: &[E]| -> Vec<E> {
+            a.iter()
+                .zip(b)
+                .map(|(x, y)| x.checked_add(y).expect("overflow in adding exponents"))
+                .collect()
+        };
+
+        (
+            LaurentPolynomial::new(q, add_shift(&self.shift, &content)),
+            LaurentPolynomial::new(r, add_shift(&self.shift, &div.shift)),
+        )
+    }
+}
+
+impl<R: Ring, E: Exponent> LaurentPolynomial<R, E>
+where
+    MultivariatePolynomial<R, E>: PolynomialGCD,
+{
+    /// Compute the gcd of two Laurent polynomials. The result is
+    /// unique up to a monomial, so the monomial content is removed.
+    pub fn gcd(
+        a: &LaurentPolynomial<R, E>,
+        b: &LaurentPolynomial<R, E>,
+    ) -> LaurentPolynomial<R, E> {
+        let a1 = a.poly.div_exponents(&a.poly.monomial_content());
+        let b1 = b.poly.div_exponents(&b.poly.monomial_content());
+        LaurentPolynomial::from_poly(MultivariatePolynomial::gcd(&a1, &b1))
+    }
+}
+
+impl<R: Ring + fmt::Display, E: Exponent + One + fmt::Display> fmt::Display
+    for LaurentPolynomial<R, E>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_polynomial() {
+            write!(f, "{}", self.poly)
+        } else {
+            write!(f, "({})/({})", self.poly, self.to_fraction().1)
+        }
+    }
+}
+
+impl<R: Ring, E: Exponent> Add for LaurentPolynomial<R, E> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        let (a, b, shift) = self.align(&other);
+        LaurentPolynomial::new(a + b, shift)
+    }
+}
+
+impl<R: Ring, E: Exponent> Sub for LaurentPolynomial<R, E> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.add(other.neg())
+    }
+}
+
+impl<R: Ring, E: Exponent> Neg for LaurentPolynomial<R, E> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        LaurentPolynomial {
+            poly: -self.poly,
+            shift: self.shift,
+        }
+    }
+}
+
+impl<R: Ring, E: Exponent> Mul for LaurentPolynomial<R, E> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let shift = self
+            .shift
+            .iter()
+            .zip(&other.shift)
+            .map(|(a, b)| a.checked_add(b).expect("overflow in adding exponents"))
+            .collect();
+        LaurentPolynomial::new(self.poly * &other.poly, shift)
+    }
+}
@@ -1,11 +1,23 @@
+pub mod adaptive;
+pub mod algebraicnumber;
 pub mod finitefield;
 pub mod fraction;
+pub mod galoisfield;
 pub mod gcd;
+pub mod laurent;
 pub mod monomial;
 pub mod multivar;
+pub mod order;
 pub mod overflowing;
+pub mod rational_solve;
+pub mod reconstruct;
+pub mod roots;
+pub mod univariate;
 pub mod zp;
+pub mod zp64;
+pub mod zp_matrix;
 pub mod zp_mod;
 pub mod zp_solve;
+pub mod zp_sparse;
 
 pub use poly::raw::multivar::MultivariatePolynomial;
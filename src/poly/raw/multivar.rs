@@ -1,3 +1,5 @@
+use crossbeam;
+use rug::Integer;
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
 use std::collections::BinaryHeap;
@@ -9,16 +11,60 @@ use tools::GCD;
 
 use num_traits::{One, Zero};
 
+use number::Number;
 use poly::exponent::Exponent;
 use poly::ring::Ring;
 
 use poly::raw::finitefield::FiniteField;
 use poly::raw::monomial::Monomial;
+use poly::raw::order::{LexOrder, MonomialOrder};
 use poly::raw::zp::ufield;
 
+/// Push `x` onto the binary max-heap `h`, which is ordered by `cmp`.
+fn heap_push<T: Copy, F: Fn(T, T) -> Ordering>(h: &mut Vec<T>, x: T, cmp: &F) {
+    h.push(x);
+    let mut i = h.len() - 1;
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if cmp(h[i], h[parent]) != Ordering::Greater {
+            break;
+        }
+        h.swap(i, parent);
+        i = parent;
+    }
+}
+
+/// Remove the largest element from the binary max-heap `h`, which is ordered by `cmp`.
+fn heap_pop<T: Copy, F: Fn(T, T) -> Ordering>(h: &mut Vec<T>, cmp: &F) -> Option<T> {
+    if h.is_empty() {
+        return None;
+    }
+    let last = h.len() - 1;
+    h.swap(0, last);
+    let top = h.pop();
+
+    let mut i = 0;
+    loop {
+        let (l, r) = (2 * i + 1, 2 * i + 2);
+        let mut largest = i;
+        if l < h.len() && cmp(h[l], h[largest]) == Ordering::Greater {
+            largest = l;
+        }
+        if r < h.len() && cmp(h[r], h[largest]) == Ordering::Greater {
+            largest = r;
+        }
+        if largest == i {
+            break;
+        }
+        h.swap(i, largest);
+        i = largest;
+    }
+    top
+}
+
 /// Multivariate polynomial with a degree sparse and variable dense representation.
 #[derive(Clone, Hash)]
-pub struct MultivariatePolynomial<R: Ring, E: Exponent> {
+pub struct MultivariatePolynomial<R: Ring, E: Exponent, O: MonomialOrder = LexOrder> {
     // Data format: the i-th monomial is stored as coefficients[i] and
     // exponents[i * nvars .. (i + 1) * nvars]. Keep coefficients.len() == nterms and
     // exponents.len() == nterms * nvars. Terms are always expanded and sorted by the exponents via
@@ -27,71 +73,118 @@ pub struct MultivariatePolynomial<R: Ring, E: Exponent> {
     pub exponents: Vec<E>,
     pub nterms: usize,
     pub nvars: usize,
+    pub order: O,
 }
 
 impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     /// Constructs a zero polynomial.
     #[inline]
     pub fn new() -> Self {
-        Self {
-            coefficients: Vec::new(),
-            exponents: Vec::new(),
-            nterms: 0,
-            nvars: 0,
-        }
+        Self::with_order(LexOrder)
     }
 
     /// Constructs a zero polynomial with the given number of variables.
     #[inline]
     pub fn with_nvars(nvars: usize) -> Self {
-        Self {
-            coefficients: Vec::new(),
-            exponents: Vec::new(),
-            nterms: 0,
-            nvars: nvars,
-        }
+        Self::with_nvars_and_order(nvars, LexOrder)
     }
 
     /// Constructs a zero polynomial with the given number of variables and capacity.
     #[inline]
     pub fn with_nvars_and_capacity(nvars: usize, cap: usize) -> Self {
+        Self::with_nvars_capacity_and_order(nvars, cap, LexOrder)
+    }
+
+    /// Constructs a constant polynomial with the given number of variables.
+    #[inline]
+    pub fn from_constant_with_nvars(constant: R, nvars: usize) -> Self {
+        Self::from_monomial_with_order(constant, vec![E::zero(); nvars], LexOrder)
+    }
+
+    /// Constructs a polynomial with a single term.
+    #[inline]
+    pub fn from_monomial(coefficient: R, exponents: Vec<E>) -> Self {
+        Self::from_monomial_with_order(coefficient, exponents, LexOrder)
+    }
+}
+
+impl<R: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<R, E, O> {
+    /// Constructs a zero polynomial with the given monomial order.
+    #[inline]
+    pub fn with_order(order: O) -> Self {
+        Self::with_nvars_and_order(0, order)
+    }
+
+    /// Constructs a zero polynomial with the given number of variables and monomial order.
+    #[inline]
+    pub fn with_nvars_and_order(nvars: usize, order: O) -> Self {
         Self {
-            coefficients: Vec::with_capacity(cap),
-            exponents: Vec::with_capacity(cap * nvars),
+            coefficients: Vec::new(),
+            exponents: Vec::new(),
             nterms: 0,
             nvars: nvars,
+            order: order,
         }
     }
 
-    /// Constructs a constant polynomial with the given number of variables.
+    /// Constructs a zero polynomial with the given number of variables, capacity and
+    /// monomial order.
     #[inline]
-    pub fn from_constant_with_nvars(constant: R, nvars: usize) -> Self {
-        if constant.is_zero() {
-            return Self::with_nvars(nvars);
-        }
+    pub fn with_nvars_capacity_and_order(nvars: usize, cap: usize, order: O) -> Self {
         Self {
-            coefficients: vec![constant],
-            exponents: vec![E::zero(); nvars],
-            nterms: 1,
+            coefficients: Vec::with_capacity(cap),
+            exponents: Vec::with_capacity(cap * nvars),
+            nterms: 0,
             nvars: nvars,
+            order: order,
         }
     }
 
-    /// Constructs a polynomial with a single term.
+    /// Constructs a polynomial with a single term and the given monomial order.
     #[inline]
-    pub fn from_monomial(coefficient: R, exponents: Vec<E>) -> Self {
+    pub fn from_monomial_with_order(coefficient: R, exponents: Vec<E>, order: O) -> Self {
         if coefficient.is_zero() {
-            return Self::with_nvars(exponents.len());
+            return Self::with_nvars_and_order(exponents.len(), order);
         }
         Self {
             coefficients: vec![coefficient],
             nvars: exponents.len(),
             exponents: exponents,
             nterms: 1,
+            order: order,
+        }
+    }
+
+    /// Constructs a zero polynomial with the same number of variables and
+    /// monomial order as `self`.
+    #[inline]
+    pub fn zero_with_capacity(&self, cap: usize) -> Self {
+        Self::with_nvars_capacity_and_order(self.nvars, cap, self.order.clone())
+    }
+
+    /// Returns the monomial order of the polynomial.
+    #[inline]
+    pub fn order(&self) -> &O {
+        &self.order
+    }
+
+    /// Converts the polynomial to another monomial order.
+    pub fn to_order<O1: MonomialOrder>(&self, order: O1) -> MultivariatePolynomial<R, E, O1> {
+        let mut indices: Vec<usize> = (0..self.nterms).collect();
+        indices
+            .sort_unstable_by(|&a, &b| order.cmp_exponents(self.exponents(a), self.exponents(b)));
+
+        let mut res =
+            MultivariatePolynomial::with_nvars_capacity_and_order(self.nvars, self.nterms, order);
+        for i in indices {
+            res.coefficients.push(self.coefficients[i].clone());
+            res.exponents.extend_from_slice(self.exponents(i));
         }
+        res.nterms = self.nterms;
+        res
     }
 
-    pub fn to_finite_field(&self, p: ufield) -> MultivariatePolynomial<FiniteField, E> {
+    pub fn to_finite_field(&self, p: ufield) -> MultivariatePolynomial<FiniteField, E, O> {
         let mut newc = Vec::with_capacity(self.coefficients.len());
         let mut newe = Vec::with_capacity(self.exponents.len());
 
@@ -103,7 +196,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
             }
         }
 
-        let mut a = MultivariatePolynomial::with_nvars(self.nvars);
+        let mut a = MultivariatePolynomial::with_nvars_and_order(self.nvars, self.order.clone());
         a.nterms = newc.len();
         a.exponents = newe;
         a.coefficients = newc;
@@ -196,21 +289,43 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
     }
 
-    /// Compares exponent vectors of two monomials.
+    /// Compares exponent vectors of two monomials in the monomial order of the polynomial.
     #[inline]
-    fn cmp_exponents(a: &[E], b: &[E]) -> Ordering {
-        debug_assert!(a.len() == b.len());
-        // TODO: Introduce other term orders.
-        a.cmp(b)
+    fn cmp_exponents(&self, a: &[E], b: &[E]) -> Ordering {
+        self.order.cmp_exponents(a, b)
     }
 
     /// Grow the exponent list so the variable index fits in.
+    /// The new variables are added at the end, which preserves the monomial ordering.
     pub fn grow_to(&mut self, var: usize) {
-        if self.nterms() < var {
-            // move all the exponents
-            self.exponents.resize(var, E::zero());
-            unimplemented!()
+        if var < self.nvars {
+            return;
         }
+
+        // move all the exponents
+        let nvars = var + 1;
+        let mut newexp = vec![E::zero(); self.nterms * nvars];
+        for t in 0..self.nterms {
+            newexp[t * nvars..t * nvars + self.nvars].clone_from_slice(self.exponents(t));
+        }
+
+        self.exponents = newexp;
+        self.nvars = nvars;
+    }
+
+    /// Convert the exponents to another type, for example to widen them.
+    /// Returns `None` if an exponent does not fit in the new type.
+    pub fn convert_exponents<E1: Exponent>(&self) -> Option<MultivariatePolynomial<R, E1, O>> {
+        let mut exponents = Vec::with_capacity(self.exponents.len());
+        for &e in &self.exponents {
+            exponents.push(E1::from_u64(e.to_u64()?)?);
+        }
+
+        let mut res = MultivariatePolynomial::with_nvars_and_order(self.nvars, self.order.clone());
+        res.coefficients = self.coefficients.clone();
+        res.exponents = exponents;
+        res.nterms = self.nterms;
+        Some(res)
     }
 
     /// Check if the polynomial is sorted and has only non-zero coefficients
@@ -225,10 +340,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
 
         for t in 1..self.nterms {
-            match MultivariatePolynomial::<R, E>::cmp_exponents(
-                self.exponents(t),
-                &self.exponents(t - 1),
-            ) {
+            match self.cmp_exponents(self.exponents(t), &self.exponents(t - 1)) {
                 Ordering::Equal => panic!("Inconsistent polynomial (equal monomials): {}", self),
                 Ordering::Less => panic!(
                     "Inconsistent polynomial (wrong monomial ordering): {}",
@@ -278,7 +390,9 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
 
         // should we append to the back?
-        if self.nterms == 0 || self.last_exponents() < exponents {
+        if self.nterms == 0
+            || self.cmp_exponents(self.last_exponents(), exponents) == Ordering::Less
+        {
             self.coefficients.push(coefficient);
             self.exponents.extend(exponents);
             self.nterms += 1;
@@ -291,7 +405,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
 
         while l <= r {
             let m = (l + r) / 2;
-            let c = Self::cmp_exponents(exponents, self.exponents(m)); // note the reversal
+            let c = self.cmp_exponents(exponents, self.exponents(m)); // note the reversal
 
             match c {
                 Ordering::Equal => {
@@ -340,10 +454,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Default for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Default for MultivariatePolynomial<R, E, O> {
     #[inline]
     fn default() -> Self {
-        Self::new()
+        Self::with_order(O::default())
     }
 }
 
@@ -355,12 +469,19 @@ pub struct MultivariateMonomialView<'a, R: 'a, E: 'a> {
 }
 
 /// Iterator over terms in a multivariate polynomial.
-pub struct MultivariateMonomialViewIterator<'a, R: 'a + Ring, E: 'a + Exponent> {
-    poly: &'a MultivariatePolynomial<R, E>,
+pub struct MultivariateMonomialViewIterator<
+    'a,
+    R: 'a + Ring,
+    E: 'a + Exponent,
+    O: 'a + MonomialOrder = LexOrder,
+> {
+    poly: &'a MultivariatePolynomial<R, E, O>,
     index: usize,
 }
 
-impl<'a, R: Ring, E: Exponent> Iterator for MultivariateMonomialViewIterator<'a, R, E> {
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> Iterator
+    for MultivariateMonomialViewIterator<'a, R, E, O>
+{
     type Item = MultivariateMonomialView<'a, R, E>;
 
     #[inline]
@@ -378,9 +499,11 @@ impl<'a, R: Ring, E: Exponent> Iterator for MultivariateMonomialViewIterator<'a,
     }
 }
 
-impl<'a, R: Ring, E: Exponent> IntoIterator for &'a MultivariatePolynomial<R, E> {
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> IntoIterator
+    for &'a MultivariatePolynomial<R, E, O>
+{
     type Item = MultivariateMonomialView<'a, R, E>;
-    type IntoIter = MultivariateMonomialViewIterator<'a, R, E>;
+    type IntoIter = MultivariateMonomialViewIterator<'a, R, E, O>;
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -391,7 +514,9 @@ impl<'a, R: Ring, E: Exponent> IntoIterator for &'a MultivariatePolynomial<R, E>
     }
 }
 
-impl<R: Ring + fmt::Debug, E: Exponent + fmt::Debug> fmt::Debug for MultivariatePolynomial<R, E> {
+impl<R: Ring + fmt::Debug, E: Exponent + fmt::Debug, O: MonomialOrder> fmt::Debug
+    for MultivariatePolynomial<R, E, O>
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_zero() {
             return write!(f, "[]");
@@ -414,8 +539,8 @@ impl<R: Ring + fmt::Debug, E: Exponent + fmt::Debug> fmt::Debug for Multivariate
     }
 }
 
-impl<R: Ring + fmt::Display, E: Exponent + One + fmt::Display> fmt::Display
-    for MultivariatePolynomial<R, E>
+impl<R: Ring + fmt::Display, E: Exponent + One + fmt::Display, O: MonomialOrder> fmt::Display
+    for MultivariatePolynomial<R, E, O>
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut is_first_term = true;
@@ -461,7 +586,9 @@ impl<R: Ring + fmt::Display, E: Exponent + One + fmt::Display> fmt::Display
     }
 }
 
-impl<R: Ring + PartialEq, E: Exponent> PartialEq for MultivariatePolynomial<R, E> {
+impl<R: Ring + PartialEq, E: Exponent, O: MonomialOrder> PartialEq
+    for MultivariatePolynomial<R, E, O>
+{
     #[inline]
     fn eq(&self, other: &Self) -> bool {
         if self.nvars != other.nvars {
@@ -482,12 +609,12 @@ impl<R: Ring + PartialEq, E: Exponent> PartialEq for MultivariatePolynomial<R, E
     }
 }
 
-impl<R: Ring + Eq, E: Exponent> Eq for MultivariatePolynomial<R, E> {}
+impl<R: Ring + Eq, E: Exponent, O: MonomialOrder> Eq for MultivariatePolynomial<R, E, O> {}
 
-impl<R: Ring, E: Exponent> Zero for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Zero for MultivariatePolynomial<R, E, O> {
     #[inline]
     fn zero() -> Self {
-        Self::new()
+        Self::with_order(O::default())
     }
 
     #[inline]
@@ -496,10 +623,10 @@ impl<R: Ring, E: Exponent> Zero for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> One for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> One for MultivariatePolynomial<R, E, O> {
     #[inline]
     fn one() -> Self {
-        MultivariatePolynomial::from_constant_with_nvars(R::one(), 0)
+        Self::from_monomial_with_order(R::one(), vec![], O::default())
     }
 
     #[inline]
@@ -510,7 +637,7 @@ impl<R: Ring, E: Exponent> One for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Add for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Add for MultivariatePolynomial<R, E, O> {
     type Output = Self;
 
     fn add(mut self, mut other: Self) -> Self::Output {
@@ -523,6 +650,10 @@ impl<R: Ring, E: Exponent> Add for MultivariatePolynomial<R, E> {
         if self.nvars != other.nvars {
             panic!("nvars mismatched");
         }
+        if self.order != other.order {
+            // weight and block orders with different parameters sort differently
+            panic!("monomial orders mismatched");
+        }
 
         // Merge the two polynomials, which are assumed to be already sorted.
 
@@ -533,7 +664,7 @@ impl<R: Ring, E: Exponent> Add for MultivariatePolynomial<R, E> {
         let mut j = 0;
 
         while i < self.nterms && j < other.nterms {
-            let c = Self::cmp_exponents(self.exponents(i), other.exponents(j));
+            let c = self.cmp_exponents(self.exponents(i), other.exponents(j));
             match c {
                 Ordering::Less => {
                     Self::add_push(
@@ -605,11 +736,12 @@ impl<R: Ring, E: Exponent> Add for MultivariatePolynomial<R, E> {
             exponents: new_exponents,
             nterms: new_nterms,
             nvars: self.nvars,
+            order: self.order,
         }
     }
 }
 
-impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<R, E, O> {
     #[inline(always)]
     fn add_push(
         new_coefficients: &mut Vec<R>,
@@ -628,7 +760,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Sub for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Sub for MultivariatePolynomial<R, E, O> {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self::Output {
@@ -636,7 +768,7 @@ impl<R: Ring, E: Exponent> Sub for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Neg for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Neg for MultivariatePolynomial<R, E, O> {
     type Output = Self;
     fn neg(mut self) -> Self::Output {
         // Negate coefficients of all terms.
@@ -647,7 +779,7 @@ impl<R: Ring, E: Exponent> Neg for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Mul for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Mul for MultivariatePolynomial<R, E, O> {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self::Output {
@@ -655,32 +787,37 @@ impl<R: Ring, E: Exponent> Mul for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<'a, R: Ring, E: Exponent> Mul<&'a MultivariatePolynomial<R, E>>
-    for MultivariatePolynomial<R, E>
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> Mul<&'a MultivariatePolynomial<R, E, O>>
+    for MultivariatePolynomial<R, E, O>
 {
     type Output = Self;
 
-    fn mul(self, other: &'a MultivariatePolynomial<R, E>) -> Self::Output {
+    fn mul(self, other: &'a MultivariatePolynomial<R, E, O>) -> Self::Output {
         if self.is_zero() {
-            return Self::with_nvars(other.nvars);
+            return other.zero_with_capacity(0);
         }
         if other.is_zero() {
-            return Self::with_nvars(self.nvars);
+            return self.zero_with_capacity(0);
         }
         if self.nvars != other.nvars {
             panic!("nvars mismatched");
         }
-        // TODO: this is a quick implementation. To be improved.
-        let mut new_poly = Self::with_nvars(self.nvars);
-        for m in other {
-            let p = self.clone().mul_monomial(m.coefficient, m.exponents);
-            new_poly = new_poly.add(p);
+        if self.order != other.order {
+            panic!("monomial orders mismatched");
         }
-        new_poly
+        if other.nterms == 1 {
+            return self.mul_monomial(&other.coefficients[0], &other.exponents);
+        }
+        if self.nterms == 1 {
+            return other
+                .clone()
+                .mul_monomial(&self.coefficients[0], &self.exponents);
+        }
+        self.heap_mul(other)
     }
 }
 
-impl<R: Ring, E: Exponent> Mul<R> for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Mul<R> for MultivariatePolynomial<R, E, O> {
     type Output = Self;
 
     fn mul(mut self, other: R) -> Self::Output {
@@ -691,7 +828,7 @@ impl<R: Ring, E: Exponent> Mul<R> for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> Add<R> for MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> Add<R> for MultivariatePolynomial<R, E, O> {
     type Output = Self;
 
     fn add(mut self, other: R) -> Self::Output {
@@ -701,7 +838,7 @@ impl<R: Ring, E: Exponent> Add<R> for MultivariatePolynomial<R, E> {
     }
 }
 
-impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
+impl<R: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<R, E, O> {
     #[inline]
     fn mul_monomial(mut self, coefficient: &R, exponents: &[E]) -> Self {
         debug_assert!(self.nvars == exponents.len());
@@ -720,6 +857,193 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         self
     }
 
+    /// Returns `true` if the exponents of the product of `self` and `other`
+    /// do not fit in `E`.
+    pub fn mul_overflows(&self, other: &MultivariatePolynomial<R, E, O>) -> bool {
+        // the maximal degrees of a variable in both polynomials are attained by
+        // some pair of terms, so this check is exact
+        (0..self.nvars).any(|v| self.degree(v).checked_add(&other.degree(v)).is_none())
+    }
+
+    /// Multiply two polynomials, returning `None` if the exponents overflow.
+    pub fn checked_mul(
+        &self,
+        other: &MultivariatePolynomial<R, E, O>,
+    ) -> Option<MultivariatePolynomial<R, E, O>> {
+        if self.nvars == other.nvars && self.mul_overflows(other) {
+            None
+        } else {
+            Some(self.clone() * other)
+        }
+    }
+
+    /// Multiply two polynomials using a heap that merges the rows
+    /// `self[i] * other` (Johnson's algorithm). If the monomial order supports it,
+    /// the exponents are packed into a single word for fast comparison.
+    /// Reference: "Sparse polynomial division using a heap" by Monagan, Pearce (2011)
+    pub fn heap_mul(
+        &self,
+        other: &MultivariatePolynomial<R, E, O>,
+    ) -> MultivariatePolynomial<R, E, O> {
+        if self.is_zero() || other.is_zero() {
+            return self.zero_with_capacity(0);
+        }
+        if self.nvars != other.nvars {
+            panic!("nvars mismatched");
+        }
+        if self.order != other.order {
+            panic!("monomial orders mismatched");
+        }
+
+        // the total degree of the product bounds every field of a packed exponent vector
+        let max_degree = |p: &Self| {
+            (0..p.nterms)
+                .map(|t| p.exponents(t).iter().map(|&e| e.to_u64().unwrap()).sum::<u64>())
+                .max()
+                .unwrap_or(0)
+        };
+        let bound = max_degree(self) + max_degree(other);
+        let bits = 64 - bound.leading_zeros();
+
+        let packed_self: Option<Vec<u64>> = (0..self.nterms)
+            .map(|t| self.order.pack(self.exponents(t), bits))
+            .collect();
+        let packed_other: Option<Vec<u64>> = (0..other.nterms)
+            .map(|t| other.order.pack(other.exponents(t), bits))
+            .collect();
+
+        if let (Some(ps), Some(po)) = (packed_self, packed_other) {
+            return self.heap_mul_with_cmp(other, |(i, j), (k, l)| {
+                (ps[i] + po[j]).cmp(&(ps[k] + po[l]))
+            });
+        }
+
+        // compare the products in place, without constructing their exponents
+        self.heap_mul_with_cmp(other, |(i, j), (k, l)| {
+            self.order.cmp_products(
+                self.exponents(i),
+                other.exponents(j),
+                self.exponents(k),
+                other.exponents(l),
+            )
+        })
+    }
+
+    /// Johnson's heap multiplication, where `cmp((i, j), (k, l))` compares
+    /// the monomials `self[i] * other[j]` and `self[k] * other[l]` in the monomial order.
+    fn heap_mul_with_cmp<F: Fn((usize, usize), (usize, usize)) -> Ordering>(
+        &self,
+        other: &MultivariatePolynomial<R, E, O>,
+        cmp: F,
+    ) -> MultivariatePolynomial<R, E, O> {
+        let mut res = self.zero_with_capacity(self.nterms.max(other.nterms));
+        let mut h = Vec::with_capacity(self.nterms);
+        let mut popped = Vec::with_capacity(self.nterms);
+
+        // start with the leading monomials and work from the back
+        let (n, m) = (self.nterms - 1, other.nterms - 1);
+        heap_push(&mut h, (n, m), &cmp);
+
+        while let Some((i, j)) = heap_pop(&mut h, &cmp) {
+            popped.push((i, j));
+            while h.first().map(|&x| cmp(x, (i, j)) == Ordering::Equal).unwrap_or(false) {
+                popped.push(heap_pop(&mut h, &cmp).unwrap());
+            }
+
+            let mut c = self.coefficients[i].clone() * other.coefficients[j].clone();
+            for &(i1, j1) in &popped[1..] {
+                c = c + self.coefficients[i1].clone() * other.coefficients[j1].clone();
+            }
+
+            if !c.is_zero() {
+                res.coefficients.push(c);
+                for (e1, e2) in self.exponents(i).iter().zip(other.exponents(j)) {
+                    res.exponents
+                        .push(e1.checked_add(e2).expect("overflow in adding exponents"));
+                }
+                res.nterms += 1;
+            }
+
+            // every pair (i, j) is inserted after its larger neighbour has been popped,
+            // so there is at most one entry per term of self in the heap
+            for (i1, j1) in popped.drain(..) {
+                if j1 == m && i1 > 0 {
+                    heap_push(&mut h, (i1 - 1, m), &cmp);
+                }
+                if j1 > 0 {
+                    heap_push(&mut h, (i1, j1 - 1), &cmp);
+                }
+            }
+        }
+
+        // res has the highest monomials first
+        res.reverse();
+        res
+    }
+
+    /// Multiply two polynomials using `nthreads` threads. The terms of `self`
+    /// are distributed over the threads, and the partial products are summed.
+    pub fn mul_parallel(
+        &self,
+        other: &MultivariatePolynomial<R, E, O>,
+        nthreads: usize,
+    ) -> MultivariatePolynomial<R, E, O>
+    where
+        R: Send + Sync,
+        E: Send + Sync,
+        O: Send + Sync,
+    {
+        if nthreads <= 1 || self.nterms < 2 * nthreads {
+            return self.heap_mul(other);
+        }
+
+        let chunk = (self.nterms + nthreads - 1) / nthreads;
+        let parts: Vec<Self> = crossbeam::scope(|scope| {
+            let handles: Vec<_> = (0..nthreads)
+                .map(|t| (t * chunk, self.nterms.min((t + 1) * chunk)))
+                .filter(|&(start, end)| start < end)
+                .map(|(start, end)| scope.spawn(move || self.terms(start, end).heap_mul(other)))
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        // add the partial products pairwise in parallel, so that every term
+        // takes part in only log2(nthreads) additions
+        let mut parts = parts;
+        while parts.len() > 1 {
+            let odd = if parts.len() % 2 == 1 { parts.pop() } else { None };
+            let mut pairs = Vec::with_capacity(parts.len() / 2);
+            while let Some(b) = parts.pop() {
+                pairs.push((parts.pop().unwrap(), b));
+            }
+
+            parts = crossbeam::scope(|scope| {
+                let handles: Vec<_> = pairs
+                    .into_iter()
+                    .map(|(a, b)| scope.spawn(move || a + b))
+                    .collect();
+
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            parts.extend(odd);
+        }
+
+        parts.pop().unwrap_or_else(|| self.zero_with_capacity(0))
+    }
+
+    /// Get the polynomial made out of the terms `start..end`.
+    pub fn terms(&self, start: usize, end: usize) -> MultivariatePolynomial<R, E, O> {
+        assert!(start <= end && end <= self.nterms);
+        let mut res = self.zero_with_capacity(end - start);
+        res.coefficients
+            .extend_from_slice(&self.coefficients[start..end]);
+        res.exponents
+            .extend_from_slice(&self.exponents[start * self.nvars..end * self.nvars]);
+        res.nterms = end - start;
+        res
+    }
+
     #[inline]
     fn _divexact_monomial(
         dividend_coefficient: &R,
@@ -766,6 +1090,50 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         max
     }
 
+    /// Get the monomial content: the lowest power of every variable
+    /// that occurs in all terms.
+    pub fn monomial_content(&self) -> Vec<E> {
+        if self.is_zero() {
+            return vec![E::zero(); self.nvars];
+        }
+
+        let mut content = self.exponents(0).to_vec();
+        for t in 1..self.nterms {
+            for (c, e) in content.iter_mut().zip(self.exponents(t)) {
+                if *e < *c {
+                    *c = *e;
+                }
+            }
+        }
+        content
+    }
+
+    /// Multiply every term by the monomial `x^exponents`.
+    pub fn mul_exponents(&self, exponents: &[E]) -> MultivariatePolynomial<R, E, O> {
+        debug_assert!(self.nvars == exponents.len());
+        let mut res = self.clone();
+        for i in 0..res.nterms {
+            for (e1, e2) in res.exponents_mut(i).iter_mut().zip(exponents) {
+                *e1 = e1.checked_add(e2).expect("overflow in adding exponents");
+            }
+        }
+        res
+    }
+
+    /// Divide every term by the monomial `x^exponents`. The monomial
+    /// should divide all terms, for example when it is the monomial content.
+    pub fn div_exponents(&self, exponents: &[E]) -> MultivariatePolynomial<R, E, O> {
+        debug_assert!(self.nvars == exponents.len());
+        let mut res = self.clone();
+        for i in 0..res.nterms {
+            for (e1, e2) in res.exponents_mut(i).iter_mut().zip(exponents) {
+                debug_assert!(*e1 >= *e2);
+                *e1 = *e1 - *e2;
+            }
+        }
+        res
+    }
+
     // Get the highest degree of a variable in the leading monomial.
     pub fn ldegree(&self, v: usize) -> E {
         if self.is_zero() {
@@ -793,7 +1161,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
         self.coefficients.last().unwrap().clone()
     }
+}
 
+/// Functions that rely on the lexicographic monomial order.
+impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     /// Get the leading coefficient under a given variable ordering.
     /// This operation is O(n) if the variables are out of order.
     pub fn lcoeff_varorder(&self, vars: &[usize]) -> R {
@@ -901,11 +1272,13 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
         res
     }
+}
 
+impl<R: Ring, E: Exponent, O: MonomialOrder> MultivariatePolynomial<R, E, O> {
     /// Change the order of the variables in the polynomial, using `varmap`.
     /// The map can also be reversed, by setting `inverse` to `true`.
-    pub fn rearrange(&self, varmap: &[usize], inverse: bool) -> MultivariatePolynomial<R, E> {
-        let mut res = MultivariatePolynomial::with_nvars(self.nvars);
+    pub fn rearrange(&self, varmap: &[usize], inverse: bool) -> MultivariatePolynomial<R, E, O> {
+        let mut res = self.zero_with_capacity(0);
         let mut newe = vec![E::zero(); self.nvars];
         for m in self.into_iter() {
             for x in 0..varmap.len() {
@@ -923,8 +1296,8 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
 
     /// Replace a variable `n' in the polynomial by an element from
     /// the ring `v'.
-    pub fn replace(&self, n: usize, v: R) -> MultivariatePolynomial<R, E> {
-        let mut res = MultivariatePolynomial::with_nvars_and_capacity(self.nvars, self.nterms);
+    pub fn replace(&self, n: usize, v: R) -> MultivariatePolynomial<R, E, O> {
+        let mut res = self.zero_with_capacity(self.nterms);
         let mut e = vec![E::zero(); self.nvars];
         for t in 0..self.nterms {
             let mut c = self.coefficients[t].clone() * v.clone().pow(self.exponents(t)[n].as_());
@@ -940,6 +1313,83 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         res
     }
 
+    /// Replace the variable `n` by the polynomial `v`, which should have the same
+    /// number of variables.
+    pub fn substitute(
+        &self,
+        n: usize,
+        v: &MultivariatePolynomial<R, E, O>,
+    ) -> MultivariatePolynomial<R, E, O> {
+        self.substitute_all(&[(n, v.clone())])
+    }
+
+    /// Replace variables by polynomials simultaneously, so that for example
+    /// `x -> y, y -> x` swaps the two variables.
+    pub fn substitute_all(
+        &self,
+        subs: &[(usize, MultivariatePolynomial<R, E, O>)],
+    ) -> MultivariatePolynomial<R, E, O> {
+        for &(_, ref v) in subs {
+            assert_eq!(v.nvars, self.nvars, "nvars mismatched");
+        }
+
+        // cache of the positive powers of the polynomials that are substituted
+        let mut cache: Vec<Vec<MultivariatePolynomial<R, E, O>>> = vec![vec![]; subs.len()];
+
+        let mut res = self.zero_with_capacity(0);
+        let mut e = vec![E::zero(); self.nvars];
+        for t in 0..self.nterms {
+            e.copy_from_slice(self.exponents(t));
+            for &(n, _) in subs {
+                e[n] = E::zero();
+            }
+
+            let mut m = MultivariatePolynomial::from_monomial_with_order(
+                self.coefficients[t].clone(),
+                e.clone(),
+                self.order.clone(),
+            );
+            for (i, &(n, ref v)) in subs.iter().enumerate() {
+                let p = self.exponents(t)[n].as_() as usize;
+                if p > 0 {
+                    while cache[i].len() < p {
+                        let next = match cache[i].last() {
+                            Some(l) => l.clone() * v,
+                            None => v.clone(),
+                        };
+                        cache[i].push(next);
+                    }
+                    m = m * &cache[i][p - 1];
+                }
+            }
+            res = res + m;
+        }
+
+        res
+    }
+
+    /// Evaluate the polynomial at a point, which has a value for every variable.
+    pub fn evaluate(&self, values: &[R]) -> R {
+        assert_eq!(values.len(), self.nvars, "nvars mismatched");
+
+        // avoid `R::zero()`, since for finite fields it does not know the prime
+        let mut res: Option<R> = None;
+        for t in 0..self.nterms {
+            let mut c = self.coefficients[t].clone();
+            for (v, e) in values.iter().zip(self.exponents(t)) {
+                let p = e.as_();
+                if p > 0 {
+                    c *= v.clone().pow(p);
+                }
+            }
+            res = Some(match res {
+                Some(r) => r + c,
+                None => c,
+            });
+        }
+        res.unwrap_or_else(R::zero)
+    }
+
     /// Replace all variables except `v` in the polynomial by elements from
     /// the ring.
     pub fn replace_all_except(
@@ -947,7 +1397,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         v: usize,
         r: &[(usize, R)],
         cache: &mut [Vec<R>],
-    ) -> MultivariatePolynomial<R, E> {
+    ) -> MultivariatePolynomial<R, E, O> {
         let mut tm: HashMap<E, R> = HashMap::new();
 
         for t in 0..self.nterms {
@@ -976,7 +1426,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
             }
         }
 
-        let mut res = MultivariatePolynomial::with_nvars(self.nvars);
+        let mut res = self.zero_with_capacity(0);
         let mut e = vec![E::zero(); self.nvars];
         for (k, c) in tm {
             e[v] = k;
@@ -1001,7 +1451,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
 
     /// Create a univariate polynomial out of a multivariate one.
     /// TODO: allow a MultivariatePolynomial as a coefficient
-    pub fn to_univariate_polynomial(&self, x: usize) -> Vec<(MultivariatePolynomial<R, E>, u32)> {
+    pub fn to_univariate_polynomial(
+        &self,
+        x: usize,
+    ) -> Vec<(MultivariatePolynomial<R, E, O>, u32)> {
         if self.coefficients.is_empty() {
             return vec![];
         }
@@ -1020,7 +1473,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         let mut e = vec![E::zero(); self.nvars];
         for d in 0..maxdeg + 1 {
             // TODO: add bounds estimate
-            let mut a = MultivariatePolynomial::with_nvars(self.nvars);
+            let mut a = self.zero_with_capacity(0);
             for t in 0..self.nterms {
                 if self.exponents(t)[x].as_() == d {
                     for (i, ee) in self.exponents(t).iter().enumerate() {
@@ -1045,12 +1498,12 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         &self,
         xs: &[usize],
         include: bool,
-    ) -> HashMap<Vec<E>, MultivariatePolynomial<R, E>> {
+    ) -> HashMap<Vec<E>, MultivariatePolynomial<R, E, O>> {
         if self.coefficients.is_empty() {
             return HashMap::new();
         }
 
-        let mut tm: HashMap<Vec<E>, MultivariatePolynomial<R, E>> = HashMap::new();
+        let mut tm: HashMap<Vec<E>, MultivariatePolynomial<R, E, O>> = HashMap::new();
         let mut e = vec![E::zero(); self.nvars];
         let mut me = vec![E::zero(); self.nvars];
         for t in 0..self.nterms {
@@ -1077,9 +1530,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                     tm.insert(
                         me.clone(),
                         // TODO: add nterms estimate
-                        MultivariatePolynomial::from_monomial(
+                        MultivariatePolynomial::from_monomial_with_order(
                             self.coefficients[t].clone(),
                             e.clone(),
+                            self.order.clone(),
                         ),
                     );
                 }
@@ -1095,9 +1549,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                 if add {
                     tm.insert(
                         e.clone(),
-                        MultivariatePolynomial::from_monomial(
+                        MultivariatePolynomial::from_monomial_with_order(
                             self.coefficients[t].clone(),
                             me.clone(),
+                            self.order.clone(),
                         ),
                     );
                 }
@@ -1110,14 +1565,16 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     /// Synthetic division for univariate polynomials
     pub fn synthetic_division(
         &self,
-        div: &MultivariatePolynomial<R, E>,
-    ) -> (MultivariatePolynomial<R, E>, MultivariatePolynomial<R, E>) {
+        div: &MultivariatePolynomial<R, E, O>,
+    ) -> (
+        MultivariatePolynomial<R, E, O>,
+        MultivariatePolynomial<R, E, O>,
+    ) {
         let mut dividendpos = self.nterms - 1; // work from the back
         let norm = div.coefficients.last().unwrap();
 
-        let mut q =
-            MultivariatePolynomial::<R, E>::with_nvars_and_capacity(self.nvars, self.nterms);
-        let mut r = MultivariatePolynomial::<R, E>::with_nvars(self.nvars);
+        let mut q = self.zero_with_capacity(self.nterms);
+        let mut r = self.zero_with_capacity(0);
 
         // determine the variable
         let mut var = 0;
@@ -1206,13 +1663,16 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     #[allow(dead_code)]
     fn long_division(
         &self,
-        div: &MultivariatePolynomial<R, E>,
-    ) -> (MultivariatePolynomial<R, E>, MultivariatePolynomial<R, E>) {
+        div: &MultivariatePolynomial<R, E, O>,
+    ) -> (
+        MultivariatePolynomial<R, E, O>,
+        MultivariatePolynomial<R, E, O>,
+    ) {
         if div.is_zero() {
             panic!("Cannot divide by 0 polynomial");
         }
 
-        let mut q = MultivariatePolynomial::with_nvars(self.nvars);
+        let mut q = self.zero_with_capacity(0);
         let mut r = self.clone();
         let divdeg = div.last_exponents();
 
@@ -1249,8 +1709,11 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     /// Divide two multivariate polynomials.
     pub fn divmod(
         &self,
-        div: &MultivariatePolynomial<R, E>,
-    ) -> (MultivariatePolynomial<R, E>, MultivariatePolynomial<R, E>) {
+        div: &MultivariatePolynomial<R, E, O>,
+    ) -> (
+        MultivariatePolynomial<R, E, O>,
+        MultivariatePolynomial<R, E, O>,
+    ) {
         if div.is_zero() {
             panic!("Cannot divide by 0 polynomial");
         }
@@ -1260,12 +1723,12 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         }
 
         if div.is_one() {
-            return (self.clone(), MultivariatePolynomial::with_nvars(self.nvars));
+            return (self.clone(), self.zero_with_capacity(0));
         }
 
         if div.nterms == 1 {
-            let mut q = MultivariatePolynomial::with_nvars_and_capacity(self.nvars, self.nterms);
-            let mut r = MultivariatePolynomial::with_nvars(self.nvars);
+            let mut q = self.zero_with_capacity(self.nterms);
+            let mut r = self.zero_with_capacity(0);
             let dive = div.to_monomial(0);
 
             for i in 0..self.nterms {
@@ -1295,10 +1758,13 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
     /// TODO: implement "Sparse polynomial division using a heap" by Monagan, Pearce (2011)
     fn heap_division(
         &self,
-        div: &MultivariatePolynomial<R, E>,
-    ) -> (MultivariatePolynomial<R, E>, MultivariatePolynomial<R, E>) {
-        let mut q = MultivariatePolynomial::with_nvars_and_capacity(self.nvars, self.nterms);
-        let mut r = MultivariatePolynomial::with_nvars(self.nvars);
+        div: &MultivariatePolynomial<R, E, O>,
+    ) -> (
+        MultivariatePolynomial<R, E, O>,
+        MultivariatePolynomial<R, E, O>,
+    ) {
+        let mut q = self.zero_with_capacity(self.nterms);
+        let mut r = self.zero_with_capacity(0);
         let mut s = div.nterms - 1; // index viewed from the back
         let mut h = BinaryHeap::with_capacity(div.nterms);
         let mut t = Monomial {
@@ -1312,7 +1778,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         };
 
         h.push((
-            Monomial::new(-self.lcoeff(), self.last_exponents().to_vec()),
+            OrderedMonomial::new(
+                Monomial::new(-self.lcoeff(), self.last_exponents().to_vec()),
+                &self.order,
+            ),
             0,                  // index in self/div viewed from the back (due to our poly ordering)
             usize::max_value(), // index in q, we set it out of bounds to signal we need new terms from f
         ));
@@ -1327,9 +1796,9 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                 let (x, i, j) = h.pop().unwrap();
 
                 if t.coefficient.is_zero() {
-                    t = -x;
+                    t = -x.monomial;
                 } else {
-                    t = t - x;
+                    t = t - x.monomial;
                 }
 
                 // TODO: recycle memory from x for new element in h?
@@ -1337,9 +1806,12 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                     if i + 1 < self.nterms {
                         // we need a new term from self
                         h.push((
-                            Monomial::new(
-                                -self.coefficients[self.nterms - i - 2].clone(),
-                                self.exponents(self.nterms - i - 2).to_vec(),
+                            OrderedMonomial::new(
+                                Monomial::new(
+                                    -self.coefficients[self.nterms - i - 2].clone(),
+                                    self.exponents(self.nterms - i - 2).to_vec(),
+                                ),
+                                &self.order,
                             ),
                             i + 1,
                             j,
@@ -1347,7 +1819,10 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                     }
                 } else if j + 1 < q.nterms {
                     h.push((
-                        q.to_monomial(j + 1) * div.to_monomial_view(div.nterms - i - 1),
+                        OrderedMonomial::new(
+                            q.to_monomial(j + 1) * div.to_monomial_view(div.nterms - i - 1),
+                            &self.order,
+                        ),
                         i,
                         j + 1,
                     ));
@@ -1355,7 +1830,7 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                     s += 1;
                 }
 
-                if h.len() == 0 || t != h.peek().unwrap().0 {
+                if h.len() == 0 || t != h.peek().unwrap().0.monomial {
                     break;
                 }
             }
@@ -1368,7 +1843,11 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
                 q.nterms += 1;
 
                 for i in div.nterms - s - 1..div.nterms - 1 {
-                    h.push((div.to_monomial(i) * &t1, div.nterms - i - 1, q.nterms - 1));
+                    h.push((
+                        OrderedMonomial::new(div.to_monomial(i) * &t1, &self.order),
+                        div.nterms - i - 1,
+                        q.nterms - 1,
+                    ));
                 }
 
                 s = 0;
@@ -1397,3 +1876,77 @@ impl<R: Ring, E: Exponent> MultivariatePolynomial<R, E> {
         (q, r)
     }
 }
+
+impl<E: Exponent, O: MonomialOrder> MultivariatePolynomial<Number, E, O> {
+    /// Compute the antiderivative with respect to the variable `x`, with
+    /// the integration constant set to zero.
+    pub fn integrate(&self, x: usize) -> MultivariatePolynomial<Number, E, O> {
+        let mut res = self.zero_with_capacity(self.nterms);
+        let mut e = vec![E::zero(); self.nvars];
+        for t in 0..self.nterms {
+            e.copy_from_slice(self.exponents(t));
+            e[x] = e[x].checked_add(&E::one()).expect("overflow in adding exponents");
+            let p = Number::BigInt(Integer::from(e[x].to_u64().unwrap())).normalized();
+            res.append_monomial(self.coefficients[t].clone() / p, &e);
+        }
+
+        res
+    }
+
+    /// Differentiate the polynomial with respect to the variable `x`.
+    pub fn derivative(&self, x: usize) -> MultivariatePolynomial<Number, E, O> {
+        let mut res = self.zero_with_capacity(self.nterms);
+        let mut e = vec![E::zero(); self.nvars];
+        for t in 0..self.nterms {
+            let p = self.exponents(t)[x];
+            if p.is_zero() {
+                continue;
+            }
+
+            e.copy_from_slice(self.exponents(t));
+            e[x] = p - E::one();
+            // the exponent may not fit in a `ufield`
+            let n = Number::BigInt(Integer::from(p.to_u64().unwrap())).normalized();
+            res.append_monomial(self.coefficients[t].clone() * n, &e);
+        }
+
+        res
+    }
+}
+
+/// A monomial that is compared using a monomial order, for use in heaps.
+struct OrderedMonomial<'a, R: Ring, E: Exponent, O: 'a + MonomialOrder> {
+    monomial: Monomial<R, E>,
+    order: &'a O,
+}
+
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> OrderedMonomial<'a, R, E, O> {
+    #[inline]
+    fn new(monomial: Monomial<R, E>, order: &'a O) -> OrderedMonomial<'a, R, E, O> {
+        OrderedMonomial { monomial, order }
+    }
+}
+
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> PartialOrd for OrderedMonomial<'a, R, E, O> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> Ord for OrderedMonomial<'a, R, E, O> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order
+            .cmp_exponents(&self.monomial.exponents, &other.monomial.exponents)
+    }
+}
+
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> PartialEq for OrderedMonomial<'a, R, E, O> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.monomial.exponents.eq(&other.monomial.exponents)
+    }
+}
+
+impl<'a, R: Ring, E: Exponent, O: MonomialOrder> Eq for OrderedMonomial<'a, R, E, O> {}
@@ -0,0 +1,334 @@
+//! Monomial orders for multivariate polynomials.
+
+use num_traits::cast::ToPrimitive;
+use poly::exponent::Exponent;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Trait for monomial orders. The terms of a `MultivariatePolynomial` are kept
+/// sorted in ascending order, so the leading monomial is the last one.
+pub trait MonomialOrder: Clone + Debug + Default + Eq + Hash {
+    /// Compares the exponent vectors of two monomials.
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering;
+
+    /// Packs an exponent vector into a single word, using `bits` bits per field,
+    /// such that comparing two packed words agrees with `cmp_exponents` and
+    /// the packed word of a product is the sum of the packed words.
+    /// The caller has to make sure that all fields fit in `bits` bits.
+    /// Returns `None` if the order does not support packing or the
+    /// vector does not fit in a word.
+    #[inline]
+    fn pack<E: Exponent>(&self, _a: &[E], _bits: u32) -> Option<u64> {
+        None
+    }
+
+    /// Compares the products of the monomials `a1*a2` and `b1*b2`. The built-in
+    /// orders do this without constructing the exponent vectors of the products.
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        let add = |x: &[E], y: &[E]| -> Vec<E> {
+            x.iter()
+                .zip(y)
+                .map(|(e1, e2)| e1.checked_add(e2).expect("overflow in adding exponents"))
+                .collect()
+        };
+        self.cmp_exponents(&add(a1, a2), &add(b1, b2))
+    }
+}
+
+/// Packs the exponents into consecutive fields, with the first variable
+/// in the most significant field.
+#[inline]
+fn pack_fields<E: Exponent>(a: &[E], bits: u32) -> Option<u64> {
+    if bits == 0 || bits as usize * a.len() > 64 || bits >= 64 {
+        return None;
+    }
+    Some(a.iter().fold(0, |acc, &x| (acc << bits) | x.to_u64().unwrap()))
+}
+
+/// The total degree of a monomial.
+#[inline]
+fn total_degree<E: Exponent>(a: &[E]) -> u64 {
+    a.iter().map(|&x| x.to_u64().unwrap()).sum()
+}
+
+/// The `i`-th exponent of the product of two monomials.
+#[inline]
+fn product_exponent<E: Exponent>(a1: &[E], a2: &[E], i: usize) -> u64 {
+    a1[i].to_u64().unwrap() + a2[i].to_u64().unwrap()
+}
+
+/// Lexicographic comparison of the products `a1*a2` and `b1*b2`.
+#[inline]
+fn cmp_lex_products<E: Exponent>(a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+    for i in 0..a1.len() {
+        match product_exponent(a1, a2, i).cmp(&product_exponent(b1, b2, i)) {
+            Ordering::Equal => {}
+            o => return o,
+        }
+    }
+    Ordering::Equal
+}
+
+/// Reverse lexicographic comparison of the products `a1*a2` and `b1*b2`.
+#[inline]
+fn cmp_revlex_products<E: Exponent>(a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+    for i in (0..a1.len()).rev() {
+        match product_exponent(a1, a2, i).cmp(&product_exponent(b1, b2, i)) {
+            Ordering::Equal => {}
+            o => return o.reverse(),
+        }
+    }
+    Ordering::Equal
+}
+
+/// Reverse lexicographic comparison: the monomial with the smaller exponent
+/// in the last differing variable is the larger one.
+#[inline]
+fn cmp_revlex<E: Exponent>(a: &[E], b: &[E]) -> Ordering {
+    for (ea, eb) in a.iter().zip(b).rev() {
+        match ea.cmp(eb) {
+            Ordering::Equal => {}
+            o => return o.reverse(),
+        }
+    }
+    Ordering::Equal
+}
+
+/// Lexicographic order. This is the order the GCD code relies on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LexOrder;
+
+impl MonomialOrder for LexOrder {
+    #[inline]
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert!(a.len() == b.len());
+        a.cmp(b)
+    }
+
+    #[inline]
+    fn pack<E: Exponent>(&self, a: &[E], bits: u32) -> Option<u64> {
+        pack_fields(a, bits)
+    }
+
+    #[inline]
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        cmp_lex_products(a1, a2, b1, b2)
+    }
+}
+
+/// Graded lexicographic order: the total degree first, ties are broken lexicographically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct GrLexOrder;
+
+impl MonomialOrder for GrLexOrder {
+    #[inline]
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert!(a.len() == b.len());
+        match total_degree(a).cmp(&total_degree(b)) {
+            Ordering::Equal => a.cmp(b),
+            o => o,
+        }
+    }
+
+    #[inline]
+    fn pack<E: Exponent>(&self, a: &[E], bits: u32) -> Option<u64> {
+        // the total degree is stored in the most significant field
+        if bits as usize * (a.len() + 1) > 64 {
+            return None;
+        }
+        let deg = total_degree(a);
+        pack_fields(a, bits).map(|p| {
+            if a.is_empty() {
+                deg
+            } else {
+                (deg << (bits as usize * a.len())) | p
+            }
+        })
+    }
+
+    #[inline]
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        let (da, db) = (
+            total_degree(a1) + total_degree(a2),
+            total_degree(b1) + total_degree(b2),
+        );
+        match da.cmp(&db) {
+            Ordering::Equal => cmp_lex_products(a1, a2, b1, b2),
+            o => o,
+        }
+    }
+}
+
+/// Graded reverse lexicographic order: the total degree first, ties are broken
+/// by the reverse lexicographic order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct GrevLexOrder;
+
+impl MonomialOrder for GrevLexOrder {
+    #[inline]
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert!(a.len() == b.len());
+        match total_degree(a).cmp(&total_degree(b)) {
+            Ordering::Equal => cmp_revlex(a, b),
+            o => o,
+        }
+    }
+
+    #[inline]
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        let (da, db) = (
+            total_degree(a1) + total_degree(a2),
+            total_degree(b1) + total_degree(b2),
+        );
+        match da.cmp(&db) {
+            Ordering::Equal => cmp_revlex_products(a1, a2, b1, b2),
+            o => o,
+        }
+    }
+}
+
+/// Weighted degree order: the degree with respect to `weights` first,
+/// ties are broken lexicographically. Missing weights are taken to be 1.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WeightOrder {
+    pub weights: Vec<u32>,
+}
+
+impl WeightOrder {
+    pub fn new(weights: Vec<u32>) -> WeightOrder {
+        WeightOrder { weights }
+    }
+
+    #[inline]
+    fn weighted_degree<E: Exponent>(&self, a: &[E]) -> u64 {
+        a.iter()
+            .enumerate()
+            .map(|(i, &x)| x.to_u64().unwrap() * *self.weights.get(i).unwrap_or(&1) as u64)
+            .sum()
+    }
+}
+
+impl MonomialOrder for WeightOrder {
+    #[inline]
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert!(a.len() == b.len());
+        match self.weighted_degree(a).cmp(&self.weighted_degree(b)) {
+            Ordering::Equal => a.cmp(b),
+            o => o,
+        }
+    }
+
+    #[inline]
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        let (da, db) = (
+            self.weighted_degree(a1) + self.weighted_degree(a2),
+            self.weighted_degree(b1) + self.weighted_degree(b2),
+        );
+        match da.cmp(&db) {
+            Ordering::Equal => cmp_lex_products(a1, a2, b1, b2),
+            o => o,
+        }
+    }
+}
+
+/// Block (elimination) order: the variables are split into consecutive blocks
+/// of the given sizes, and the blocks are compared one after another using
+/// the graded reverse lexicographic order. Variables not covered by `blocks`
+/// form the last block.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BlockOrder {
+    pub blocks: Vec<usize>,
+}
+
+impl BlockOrder {
+    pub fn new(blocks: Vec<usize>) -> BlockOrder {
+        BlockOrder { blocks }
+    }
+}
+
+impl MonomialOrder for BlockOrder {
+    #[inline]
+    fn cmp_exponents<E: Exponent>(&self, a: &[E], b: &[E]) -> Ordering {
+        debug_assert!(a.len() == b.len());
+        let mut start = 0;
+        for &size in &self.blocks {
+            let end = (start + size).min(a.len());
+            match GrevLexOrder.cmp_exponents(&a[start..end], &b[start..end]) {
+                Ordering::Equal => {}
+                o => return o,
+            }
+            start = end;
+        }
+        GrevLexOrder.cmp_exponents(&a[start..], &b[start..])
+    }
+
+    #[inline]
+    fn cmp_products<E: Exponent>(&self, a1: &[E], a2: &[E], b1: &[E], b2: &[E]) -> Ordering {
+        let mut start = 0;
+        for &size in &self.blocks {
+            let end = (start + size).min(a1.len());
+            match GrevLexOrder.cmp_products(
+                &a1[start..end],
+                &a2[start..end],
+                &b1[start..end],
+                &b2[start..end],
+            ) {
+                Ordering::Equal => {}
+                o => return o,
+            }
+            start = end;
+        }
+        GrevLexOrder.cmp_products(&a1[start..], &a2[start..], &b1[start..], &b2[start..])
+    }
+}
+
+#[test]
+fn test_orders() {
+    // x0*x2^2 vs x1^2 in three variables
+    let a: [u32; 3] = [1, 0, 2];
+    let b: [u32; 3] = [0, 2, 0];
+    assert_eq!(LexOrder.cmp_exponents(&a, &b), Ordering::Greater);
+    assert_eq!(GrLexOrder.cmp_exponents(&a, &b), Ordering::Greater);
+    assert_eq!(GrevLexOrder.cmp_exponents(&a, &b), Ordering::Greater);
+
+    // x0*x2^2 vs x1^3: same degree, revlex prefers a smaller power of x2
+    let b: [u32; 3] = [0, 3, 0];
+    assert_eq!(GrLexOrder.cmp_exponents(&a, &b), Ordering::Greater);
+    assert_eq!(GrevLexOrder.cmp_exponents(&a, &b), Ordering::Less);
+
+    assert_eq!(
+        WeightOrder::new(vec![1, 1, 3]).cmp_exponents(&a, &b),
+        Ordering::Greater
+    );
+    assert_eq!(
+        BlockOrder::new(vec![1]).cmp_exponents(&[0u32, 5, 5], &a),
+        Ordering::Less
+    );
+
+    // packing preserves the order and is additive
+    let c: [u32; 3] = [1, 1, 0];
+    assert!(LexOrder.pack(&a, 8).unwrap() > LexOrder.pack(&b, 8).unwrap());
+    assert!(GrLexOrder.pack(&a, 8).unwrap() > GrLexOrder.pack(&c, 8).unwrap());
+    assert_eq!(
+        LexOrder.pack(&[2u32, 1, 2], 8),
+        Some(LexOrder.pack(&a, 8).unwrap() + LexOrder.pack(&c, 8).unwrap())
+    );
+    assert_eq!(GrevLexOrder.pack(&a, 8), None);
+
+    // comparing products in place agrees with comparing the products
+    let d: [u32; 3] = [2, 1, 2];
+    let e: [u32; 3] = [1, 4, 0];
+    assert_eq!(
+        GrevLexOrder.cmp_products(&a, &c, &b, &c),
+        GrevLexOrder.cmp_exponents(&d, &e)
+    );
+    assert_eq!(
+        BlockOrder::new(vec![1]).cmp_products(&a, &c, &b, &c),
+        BlockOrder::new(vec![1]).cmp_exponents(&d, &e)
+    );
+    assert_eq!(
+        WeightOrder::new(vec![3, 1, 1]).cmp_products(&a, &c, &b, &c),
+        WeightOrder::new(vec![3, 1, 1]).cmp_exponents(&d, &e)
+    );
+}
@@ -0,0 +1,435 @@
+//! Linear solver over Q and Q(x), using solves in Zp, the Chinese remainder theorem
+//! and rational reconstruction.
+
+use ndarray::{Array1, Array2};
+use num_traits::{One, Zero};
+use number;
+use number::Number;
+use poly::raw::finitefield::FiniteField;
+use poly::raw::gcd::{newton_interpolation, LARGE_U32_PRIMES};
+use poly::raw::zp;
+use poly::raw::zp::{ufield, FastModulus, Modulus};
+use poly::raw::zp_matrix::ZpMatrix;
+use poly::raw::zp_solve;
+use poly::raw::zp_solve::LinearSolverError;
+use poly::raw::MultivariatePolynomial;
+use poly::ring::ToFiniteField;
+use std::cmp::Ordering;
+
+/// The number of primes that may fail before a solver error is
+/// considered to be a property of the system instead of an unlucky prime.
+pub const MAX_FAILED_PRIMES: usize = 3;
+
+/// Error from the linear solvers over Q and Q(x).
+#[derive(Debug, Eq, PartialEq)]
+pub enum RationalSolverError {
+    /// The system has no unique solution.
+    Solver(LinearSolverError),
+    /// The solution could not be reconstructed with the available primes.
+    OutOfPrimes,
+    /// The degree of a parameter does not fit in 32 bits.
+    DegreeTooHigh,
+}
+
+impl From<LinearSolverError> for RationalSolverError {
+    fn from(e: LinearSolverError) -> RationalSolverError {
+        RationalSolverError::Solver(e)
+    }
+}
+
+/// Map a rational number to Zp. Returns `None` if the denominator vanishes.
+pub fn number_mod_p(n: &Number, p: ufield) -> Option<ufield> {
+    let (num, den) = match *n {
+        Number::SmallInt(..) | Number::BigInt(..) => return Some(n.to_finite_field(p).n),
+        Number::SmallRat(num, den) => (Number::SmallInt(num), Number::SmallInt(den)),
+        Number::BigRat(ref r) => (
+            Number::BigInt(r.numer().clone()),
+            Number::BigInt(r.denom().clone()),
+        ),
+        Number::Float(..) => unreachable!("Cannot map a float to Zp"),
+        Number::Complex(..) => unreachable!("Cannot map a complex number to Zp"),
+    };
+
+    let den = den.to_finite_field(p).n;
+    if den == 0 {
+        None
+    } else {
+        Some(zp::mul(num.to_finite_field(p).n, zp::inv(den, p), p))
+    }
+}
+
+/// Make a symmetric residue non-negative, as expected by `chinese_remainder`.
+fn to_positive_residue(n: &Number, m: &Number) -> Number {
+    if *n < Number::zero() {
+        n.clone() + m.clone()
+    } else {
+        n.clone()
+    }
+}
+
+/// Solve `a * x = b` over Q. The system is solved modulo several primes and
+/// the solution is obtained using rational reconstruction. It is verified
+/// over Q before it is returned.
+pub fn solve_rational(a: &[Vec<Number>], b: &[Number]) -> Result<Vec<Number>, RationalSolverError> {
+    assert_eq!(a.len(), b.len());
+    let neqs = a.len();
+    let nvars = a.first().map(|r| r.len()).unwrap_or(0);
+
+    let mut residues = vec![Number::zero(); nvars];
+    let mut m = Number::one();
+    let mut last_solution: Option<Vec<Number>> = None;
+    let mut failed_primes = 0;
+
+    'nextprime: for &p in LARGE_U32_PRIMES.iter() {
+        let fastp = FastModulus::from(p);
+
+        let mut am = Array2::<ufield>::zeros((neqs, nvars));
+        let mut bm = Array1::<ufield>::zeros(neqs);
+        for (i, (row, bb)) in a.iter().zip(b).enumerate() {
+            for (j, aa) in row.iter().enumerate() {
+                match number_mod_p(aa, p) {
+                    Some(x) => am[(i, j)] = x,
+                    None => continue 'nextprime,
+                }
+            }
+            match number_mod_p(bb, p) {
+                Some(x) => bm[i] = x,
+                None => continue 'nextprime,
+            }
+        }
+
+        let x = match zp_solve::solve(&am, &bm, &fastp) {
+            Ok(x) => x,
+            Err(e) => {
+                // the error is genuine if no prime gave a solution before
+                if m.is_one() {
+                    failed_primes += 1;
+                    if failed_primes == MAX_FAILED_PRIMES {
+                        return Err(e.into());
+                    }
+                }
+                debug!("Unlucky prime {} in rational solver", p);
+                continue;
+            }
+        };
+
+        let pn = Number::SmallInt(p as isize);
+        for (r, xx) in residues.iter_mut().zip(x.iter()) {
+            *r = number::chinese_remainder(
+                to_positive_residue(r, &m),
+                Number::SmallInt(*xx as isize),
+                m.clone(),
+                pn.clone(),
+            );
+        }
+        m *= pn;
+
+        let mut solution = Vec::with_capacity(nvars);
+        for r in &residues {
+            match number::rational_reconstruction(r, &m) {
+                Some(x) => solution.push(x),
+                None => continue 'nextprime,
+            }
+        }
+
+        // the solution is stable, so we verify it
+        if last_solution.as_ref() == Some(&solution) {
+            let consistent = a.iter().zip(b).all(|(row, bb)| {
+                let mut lhs = Number::zero();
+                for (aa, xx) in row.iter().zip(&solution) {
+                    lhs += aa.clone() * xx.clone();
+                }
+                (lhs - bb.clone()).is_zero()
+            });
+
+            if consistent {
+                return Ok(solution);
+            }
+            debug!("Rational solution failed verification: adding more primes");
+        }
+        last_solution = Some(solution);
+    }
+
+    Err(RationalSolverError::OutOfPrimes)
+}
+
+/// Evaluate a polynomial with integer coefficients at a point in Zp.
+fn evaluate_mod_p(
+    poly: &MultivariatePolynomial<Number, u32>,
+    point: &[ufield],
+    p: ufield,
+) -> ufield {
+    let mut res = 0;
+    for t in 0..poly.nterms {
+        let mut c = poly.coefficients[t].to_finite_field(p).n;
+        for (&x, &e) in point.iter().zip(poly.exponents(t)) {
+            if e > 0 {
+                c = zp::mul(c, zp::pow(x, e, p), p);
+            }
+        }
+        res = zp::add(res, c, p);
+    }
+    res
+}
+
+/// Compute `det(a)` and `det(a_i)` in Zp for the sampled parameter values `point`,
+/// where `a_i` is `a` with the `i`th column replaced by `b`.
+fn sample_determinants(
+    a: &[Vec<MultivariatePolynomial<Number, u32>>],
+    b: &[MultivariatePolynomial<Number, u32>],
+    point: &[ufield],
+    p: &FastModulus,
+) -> Vec<ufield> {
+    let n = a.len();
+    let mut am = Array2::<ufield>::zeros((n, n));
+    let mut bm = Array1::<ufield>::zeros(n);
+    for i in 0..n {
+        for j in 0..n {
+            am[(i, j)] = evaluate_mod_p(&a[i][j], point, p.value());
+        }
+        bm[i] = evaluate_mod_p(&b[i], point, p.value());
+    }
+
+    let det = am.determinant(p);
+    let mut dets = vec![det];
+
+    if det != 0 {
+        // Cramer's rule: det(a_i) = x_i * det(a)
+        let x = zp_solve::solve(&am, &bm, p).unwrap();
+        dets.extend(x.iter().map(|xx| zp::mul(*xx, det, p)));
+    } else {
+        for i in 0..n {
+            let mut ai = am.clone();
+            for k in 0..n {
+                ai[(k, i)] = bm[k];
+            }
+            dets.push(ai.determinant(p));
+        }
+    }
+    dets
+}
+
+/// Reconstruct the determinants as polynomials in Zp using dense Newton
+/// interpolation, one variable at a time.
+fn interpolate_determinants(
+    a: &[Vec<MultivariatePolynomial<Number, u32>>],
+    b: &[MultivariatePolynomial<Number, u32>],
+    bounds: &[u32],
+    point: &mut Vec<ufield>,
+    var: usize,
+    p: &FastModulus,
+) -> Vec<MultivariatePolynomial<FiniteField, u32>> {
+    if var == bounds.len() {
+        return sample_determinants(a, b, point, p)
+            .into_iter()
+            .map(|d| {
+                MultivariatePolynomial::from_constant_with_nvars(
+                    FiniteField::new(d, p.value()),
+                    bounds.len(),
+                )
+            })
+            .collect();
+    }
+
+    let mut samples = vec![];
+    let mut images = vec![vec![]; a.len() + 1];
+    for k in 0..bounds[var] + 1 {
+        point[var] = k + 1;
+        samples.push(FiniteField::new(k + 1, p.value()));
+        for (image, d) in
+            images
+                .iter_mut()
+                .zip(interpolate_determinants(a, b, bounds, point, var + 1, p))
+        {
+            image.push(d);
+        }
+    }
+
+    images
+        .iter()
+        .map(|u| newton_interpolation(&samples, u, p, var))
+        .collect()
+}
+
+/// Merge the image `img` modulo `p` into the polynomial `acc` modulo `m`.
+fn chinese_remainder_poly(
+    acc: &MultivariatePolynomial<Number, u32>,
+    m: &Number,
+    img: &MultivariatePolynomial<FiniteField, u32>,
+    p: ufield,
+) -> MultivariatePolynomial<Number, u32> {
+    let pn = Number::SmallInt(p as isize);
+    let mut res = MultivariatePolynomial::with_nvars(acc.nvars);
+
+    // both polynomials are sorted, so we merge them
+    let (mut i, mut j) = (0, 0);
+    while i < acc.nterms || j < img.nterms {
+        let ord = if i == acc.nterms {
+            Ordering::Greater
+        } else if j == img.nterms {
+            Ordering::Less
+        } else {
+            acc.exponents(i).cmp(img.exponents(j))
+        };
+
+        let (c1, c2, e) = match ord {
+            Ordering::Less => (&acc.coefficients[i], 0, acc.exponents(i)),
+            Ordering::Greater => (
+                &Number::SmallInt(0),
+                img.coefficients[j].n,
+                img.exponents(j),
+            ),
+            Ordering::Equal => (
+                &acc.coefficients[i],
+                img.coefficients[j].n,
+                acc.exponents(i),
+            ),
+        };
+
+        let c = number::chinese_remainder(
+            to_positive_residue(c1, m),
+            Number::SmallInt(c2 as isize),
+            m.clone(),
+            pn.clone(),
+        );
+        res.append_monomial(c, e);
+
+        if ord != Ordering::Greater {
+            i += 1;
+        }
+        if ord != Ordering::Less {
+            j += 1;
+        }
+    }
+    res
+}
+
+/// Solve the square system `a * x = b` over Q(y), where the entries are polynomials
+/// in `y` with integer coefficients. The solution `x_i = det(a_i)/det(a)`, with
+/// `a_i` the matrix `a` with the `i`th column replaced by `b`, is obtained by
+/// sampling the determinants in Zp, dense interpolation in the parameters and
+/// the Chinese remainder theorem. The solution is returned as pairs of
+/// numerator and denominator.
+///
+/// Since the interpolation is dense, every prime requires `prod_y (d_y + 1)`
+/// determinant evaluations, where `d_y` is the degree bound of the parameter `y`.
+/// This is only feasible for systems with few parameters of low degree.
+pub fn solve_rational_polynomial(
+    a: &[Vec<MultivariatePolynomial<Number, u32>>],
+    b: &[MultivariatePolynomial<Number, u32>],
+) -> Result<
+    Vec<(
+        MultivariatePolynomial<Number, u32>,
+        MultivariatePolynomial<Number, u32>,
+    )>,
+    RationalSolverError,
+> {
+    assert_eq!(a.len(), b.len());
+    let n = a.len();
+    if a.iter().any(|r| r.len() != n) {
+        return Err(RationalSolverError::Solver(
+            LinearSolverError::Underdetermined {
+                min_rank: 0,
+                max_rank: n,
+            },
+        ));
+    }
+
+    let nvars = b
+        .iter()
+        .chain(a.iter().flat_map(|r| r.iter()))
+        .map(|x| x.nvars)
+        .max()
+        .unwrap_or(0);
+
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    for x in b.iter_mut().chain(a.iter_mut().flat_map(|r| r.iter_mut())) {
+        if x.nvars < nvars {
+            x.grow_to(nvars - 1);
+        }
+    }
+
+    // every determinant has at most one entry of each row in a product,
+    // which gives a degree bound for every parameter
+    let bounds: Vec<u32> = (0..nvars)
+        .map(|v| {
+            a.iter()
+                .zip(&b)
+                .map(|(row, bb)| {
+                    row.iter()
+                        .map(|x| x.degree(v))
+                        .fold(bb.degree(v), |m, d| m.max(d))
+                })
+                .sum()
+        })
+        .collect();
+
+    let mut dets = vec![MultivariatePolynomial::with_nvars(nvars); n + 1];
+    let mut m = Number::one();
+
+    for &p in LARGE_U32_PRIMES.iter() {
+        let fastp = FastModulus::from(p);
+        let mut point = vec![0; nvars];
+        let images = interpolate_determinants(&a, &b, &bounds, &mut point, 0, &fastp);
+
+        let newdets: Vec<_> = dets
+            .iter()
+            .zip(&images)
+            .map(|(d, img)| chinese_remainder_poly(d, &m, img, p))
+            .collect();
+        m *= Number::SmallInt(p as isize);
+
+        if newdets != dets {
+            dets = newdets;
+            continue;
+        }
+
+        // the reconstruction is stable
+        let det = &dets[0];
+        if det.is_zero() {
+            return Err(RationalSolverError::Solver(
+                LinearSolverError::Underdetermined {
+                    min_rank: 0,
+                    max_rank: n - 1,
+                },
+            ));
+        }
+
+        // verify sum_j a_ij det(a_j) = b_i det(a)
+        let consistent = a.iter().zip(&b).all(|(row, bb)| {
+            let mut lhs = MultivariatePolynomial::with_nvars(nvars);
+            for (aa, d) in row.iter().zip(&dets[1..]) {
+                lhs = lhs + aa.clone() * d;
+            }
+            lhs == bb.clone() * det
+        });
+
+        if consistent {
+            return Ok(dets[1..]
+                .iter()
+                .map(|d| {
+                    let g = MultivariatePolynomial::gcd(d, det);
+                    (d.divmod(&g).0, det.divmod(&g).0)
+                })
+                .collect());
+        }
+        debug!("Determinants failed verification: adding more primes");
+    }
+
+    Err(RationalSolverError::OutOfPrimes)
+}
+
+#[test]
+fn test_solve_rational() {
+    // x + 2y = 1, 3x - y = 1/2
+    let a = vec![
+        vec![Number::SmallInt(1), Number::SmallInt(2)],
+        vec![Number::SmallInt(3), Number::SmallInt(-1)],
+    ];
+    let b = vec![Number::SmallInt(1), Number::SmallRat(1, 2)];
+    assert_eq!(
+        solve_rational(&a, &b),
+        Ok(vec![Number::SmallRat(2, 7), Number::SmallRat(5, 14)])
+    );
+}
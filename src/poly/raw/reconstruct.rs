@@ -0,0 +1,529 @@
+//! Reconstruction of multivariate rational functions from black-box evaluations in Zp.
+//!
+//! The degrees of the numerator and denominator are probed with univariate Thiele
+//! interpolation on random lines. The support is then found one variable at a time,
+//! as in Zippel's sparse interpolation, and confirmed with a second prime. The
+//! coefficients are solved for with this support for every prime, and the images
+//! are combined with the Chinese remainder theorem and rational reconstruction
+//! until the result is stable and passes a check with a new prime.
+
+use ndarray::{Array1, Array2};
+use num_traits::Zero;
+use number;
+use number::Number;
+use poly::raw::gcd::LARGE_U32_PRIMES;
+use poly::raw::rational_solve::{number_mod_p, MAX_FAILED_PRIMES};
+use poly::raw::zp;
+use poly::raw::zp::{ufield, FastModulus};
+use poly::raw::zp_mod::Modulus;
+use poly::raw::zp_solve::{solve, LinearSolverError};
+use poly::raw::MultivariatePolynomial;
+use rand;
+use rand::distributions::{Distribution, Uniform};
+
+/// The number of consecutive agreeing samples needed to terminate
+/// the Thiele interpolation.
+pub const THIELE_CHECKS: usize = 2;
+/// The number of extra samples in the linear system for the coefficients.
+pub const EXTRA_SAMPLES: usize = 2;
+/// The number of samples for which the function may be undefined
+/// before the reconstruction gives up.
+pub const MAX_FAILED_SAMPLES: usize = 100;
+/// The number of random points to check the reconstructed function with.
+pub const CHECK_SAMPLES: usize = 3;
+
+/// Evaluate Thiele's continued fraction `a0 + (t-t0)/(a1 + (t-t1)/(a2 + ...))` at `t`.
+/// Returns `None` if `t` is a pole.
+fn thiele_evaluate(a: &[ufield], ts: &[ufield], t: ufield, p: &FastModulus) -> Option<ufield> {
+    let mut v = a[a.len() - 1];
+    for j in (0..a.len() - 1).rev() {
+        if v == 0 {
+            return None;
+        }
+        v = zp::add(a[j], zp::mul(zp::sub(t, ts[j], p), zp::inv(v, p), p), p);
+    }
+    Some(v)
+}
+
+/// Get the degree of a dense univariate polynomial.
+fn dense_degree(a: &[ufield]) -> usize {
+    a.iter().rposition(|x| *x != 0).unwrap_or(0)
+}
+
+/// Convert a continued fraction to a numerator and denominator in dense form.
+fn thiele_to_fraction(a: &[ufield], ts: &[ufield], p: &FastModulus) -> (Vec<ufield>, Vec<ufield>) {
+    let mut num = vec![a[a.len() - 1]];
+    let mut den = vec![1];
+    for j in (0..a.len() - 1).rev() {
+        // num/den -> a_j + (t - t_j) * den/num
+        let mut newnum = vec![0; num.len().max(den.len() + 1)];
+        for (i, c) in num.iter().enumerate() {
+            newnum[i] = zp::add(newnum[i], zp::mul(a[j], *c, p), p);
+        }
+        for (i, c) in den.iter().enumerate() {
+            newnum[i + 1] = zp::add(newnum[i + 1], *c, p);
+            newnum[i] = zp::sub(newnum[i], zp::mul(ts[j], *c, p), p);
+        }
+        den = num;
+        num = newnum;
+    }
+    (num, den)
+}
+
+/// Interpolate a univariate rational function in Zp using Thiele's continued
+/// fraction and return the degrees of the numerator and denominator.
+fn thiele_degrees<F: FnMut(ufield) -> Option<ufield>>(
+    mut f: F,
+    p: &FastModulus,
+) -> Option<(usize, usize)> {
+    let mut rng = rand::thread_rng();
+    let range = Uniform::new(1, p.value());
+
+    let mut ts = vec![];
+    let mut a = vec![];
+    let mut agreements = 0;
+    let mut failures = 0;
+
+    while agreements < THIELE_CHECKS {
+        if failures == MAX_FAILED_SAMPLES {
+            return None;
+        }
+
+        let t = range.sample(&mut rng);
+        if ts.contains(&t) {
+            continue;
+        }
+
+        let y = match f(t) {
+            Some(y) => y,
+            None => {
+                failures += 1;
+                continue;
+            }
+        };
+
+        if !a.is_empty() && thiele_evaluate(&a, &ts, t, p) == Some(y) {
+            agreements += 1;
+            continue;
+        }
+        agreements = 0;
+
+        // compute the reciprocal differences
+        let mut r = y;
+        let mut valid = true;
+        for (aa, tt) in a.iter().zip(&ts) {
+            let d = zp::sub(r, *aa, p);
+            if d == 0 {
+                valid = false;
+                break;
+            }
+            r = zp::mul(zp::sub(t, *tt, p), zp::inv(d, p), p);
+        }
+
+        if valid {
+            ts.push(t);
+            a.push(r);
+        } else {
+            failures += 1;
+        }
+    }
+
+    let (num, den) = thiele_to_fraction(&a, &ts, p);
+    Some((dense_degree(&num), dense_degree(&den)))
+}
+
+/// Multiply every monomial in `support` by all powers of the variable `var`
+/// up to `bound`, keeping the total degree at most `total`.
+/// The result is sorted in lexicographical order.
+fn extend_support(support: &[Vec<u32>], var: usize, bound: usize, total: usize) -> Vec<Vec<u32>> {
+    let mut res = vec![];
+    for e in support {
+        let degree = e.iter().map(|x| *x as usize).sum::<usize>();
+        if degree > total {
+            continue;
+        }
+
+        for d in 0..bound.min(total - degree) + 1 {
+            let mut ee = e.clone();
+            ee[var] = d as u32;
+            res.push(ee);
+        }
+    }
+    res.sort();
+    res
+}
+
+/// Keep the monomials of `support` that have a non-zero coefficient.
+fn prune_support(support: Vec<Vec<u32>>, coeffs: &[ufield]) -> Vec<Vec<u32>> {
+    support
+        .into_iter()
+        .zip(coeffs)
+        .filter(|(_, c)| **c != 0)
+        .map(|(e, _)| e)
+        .collect()
+}
+
+/// Merge two sorted supports.
+fn merge_supports(mut a: Vec<Vec<u32>>, b: Vec<Vec<u32>>) -> Vec<Vec<u32>> {
+    a.extend(b);
+    a.sort();
+    a.dedup();
+    a
+}
+
+/// Evaluate the monomial with exponents `e` at `point`.
+fn evaluate_monomial(e: &[u32], point: &[ufield], p: &FastModulus) -> ufield {
+    let mut r = 1;
+    for (x, &ee) in point.iter().zip(e) {
+        if ee > 0 {
+            r = zp::mul(r, zp::pow(*x, ee, p), p);
+        }
+    }
+    r
+}
+
+/// Determine the coefficients of the numerator and denominator with the given
+/// support, where the coefficient of the monomial `den_support[norm]` is set to 1.
+fn solve_ansatz<F: Fn(&[ufield], ufield) -> Option<ufield>>(
+    f: &F,
+    nvars: usize,
+    num_support: &[Vec<u32>],
+    den_support: &[Vec<u32>],
+    norm: usize,
+    p: &FastModulus,
+) -> Result<(Vec<ufield>, Vec<ufield>), LinearSolverError> {
+    let mut rng = rand::thread_rng();
+    let range = Uniform::new(1, p.value());
+
+    let nunknowns = num_support.len() + den_support.len() - 1;
+    let nsamples = nunknowns + EXTRA_SAMPLES;
+
+    let mut m = Array2::<ufield>::zeros((nsamples, nunknowns));
+    let mut b = Array1::<ufield>::zeros(nsamples);
+    let mut failures = 0;
+    let mut i = 0;
+    while i < nsamples {
+        let point: Vec<ufield> = (0..nvars).map(|_| range.sample(&mut rng)).collect();
+        let y = match f(&point, p.value()) {
+            Some(y) => y,
+            None => {
+                failures += 1;
+                if failures == MAX_FAILED_SAMPLES {
+                    return Err(LinearSolverError::Underdetermined {
+                        min_rank: i,
+                        max_rank: nunknowns,
+                    });
+                }
+                continue;
+            }
+        };
+
+        // num(x) - y * den(x) = 0
+        for (j, e) in num_support.iter().enumerate() {
+            m[(i, j)] = evaluate_monomial(e, &point, p);
+        }
+        let mut j = num_support.len();
+        for (k, e) in den_support.iter().enumerate() {
+            let v = zp::mul(y, evaluate_monomial(e, &point, p), p);
+            if k == norm {
+                b[i] = v;
+            } else {
+                m[(i, j)] = zp::neg(v, p);
+                j += 1;
+            }
+        }
+        i += 1;
+    }
+
+    let x = solve(&m, &b, p)?;
+
+    let num = x.iter().take(num_support.len()).cloned().collect();
+    let mut den: Vec<ufield> = x.iter().skip(num_support.len()).cloned().collect();
+    den.insert(norm, 1);
+    Ok((num, den))
+}
+
+/// Find the support of the numerator and denominator in Zp. The variables are
+/// added one at a time, as in Zippel's algorithm: the variables that are not yet
+/// added are fixed to random values, and the support in the first `k+1` variables
+/// is obtained by solving an ansatz that multiplies the support in the first `k`
+/// variables with all powers of variable `k` that are allowed by the degree bounds.
+/// Monomials with a vanishing coefficient are dropped after every step, so the
+/// size of the linear systems grows with the number of terms instead of
+/// the product of the degree bounds.
+///
+/// Returns the supports and the exponents of the lowest monomial of the
+/// denominator, or `None` if the degrees could not be determined.
+fn probe_support<F: Fn(&[ufield], ufield) -> Option<ufield>>(
+    f: &F,
+    nvars: usize,
+    p: &FastModulus,
+) -> Option<(Vec<Vec<u32>>, Vec<Vec<u32>>, Vec<u32>)> {
+    let mut rng = rand::thread_rng();
+    let range = Uniform::new(1, p.value());
+
+    // the total degree is the degree on a random line through a random point
+    let c: Vec<ufield> = (0..nvars).map(|_| range.sample(&mut rng)).collect();
+    let s: Vec<ufield> = (0..nvars).map(|_| range.sample(&mut rng)).collect();
+    let (total_num, total_den) = thiele_degrees(
+        |t| {
+            let point: Vec<ufield> = c
+                .iter()
+                .zip(&s)
+                .map(|(cc, ss)| zp::add(zp::mul(*cc, t, p), *ss, p))
+                .collect();
+            f(&point, p.value())
+        },
+        p,
+    )?;
+
+    let mut bounds_num = vec![];
+    let mut bounds_den = vec![];
+    for var in 0..nvars {
+        let (dn, dd) = thiele_degrees(
+            |t| {
+                let mut point = s.clone();
+                point[var] = t;
+                f(&point, p.value())
+            },
+            p,
+        )?;
+        bounds_num.push(dn);
+        bounds_den.push(dd);
+    }
+
+    debug!(
+        "Degree bounds for reconstruction: num={} {:?}, den={} {:?}",
+        total_num, bounds_num, total_den, bounds_den
+    );
+
+    let mut num_support = vec![vec![0; nvars]];
+    let mut den_support = vec![vec![0; nvars]];
+    let mut norm_exp = vec![0; nvars];
+    for var in 0..nvars {
+        num_support = extend_support(&num_support, var, bounds_num[var], total_num);
+        den_support = extend_support(&den_support, var, bounds_den[var], total_den);
+
+        // fix the variables that are not added yet
+        let g = |point: &[ufield], p: ufield| {
+            let mut point = point.to_vec();
+            point[var + 1..].copy_from_slice(&s[var + 1..]);
+            f(&point, p)
+        };
+
+        // find the first monomial of the denominator with a non-zero coefficient
+        let mut solution = None;
+        for norm in 0..den_support.len() {
+            match solve_ansatz(&g, nvars, &num_support, &den_support, norm, p) {
+                Ok((num, den)) => {
+                    solution = Some((norm, num, den));
+                    break;
+                }
+                Err(LinearSolverError::Inconsistent) => {}
+                Err(LinearSolverError::Underdetermined { .. }) => {
+                    debug!(
+                        "Underdetermined system in reconstruction with prime {}",
+                        p.value()
+                    );
+                    return None;
+                }
+            }
+        }
+
+        let (norm, num, den) = solution?;
+        norm_exp = den_support[norm].clone();
+        num_support = prune_support(num_support, &num);
+        den_support = prune_support(den_support, &den);
+    }
+
+    Some((num_support, den_support, norm_exp))
+}
+
+/// Combine the image `image` modulo `p` with the coefficients `acc` modulo `m`.
+fn chinese_remainder_coefficients(acc: &mut [Number], image: &[ufield], m: &Number, p: ufield) {
+    for (c, x) in acc.iter_mut().zip(image) {
+        let cc = if *c < Number::zero() {
+            c.clone() + m.clone()
+        } else {
+            c.clone()
+        };
+        *c = number::chinese_remainder(
+            cc,
+            Number::SmallInt(*x as isize),
+            m.clone(),
+            Number::SmallInt(p as isize),
+        );
+    }
+}
+
+/// Check if `num/den` agrees with `f` in Zp at random points.
+fn check_reconstruction<F: Fn(&[ufield], ufield) -> Option<ufield>>(
+    f: &F,
+    nvars: usize,
+    num: &MultivariatePolynomial<Number, u32>,
+    den: &MultivariatePolynomial<Number, u32>,
+    p: &FastModulus,
+) -> bool {
+    let mut rng = rand::thread_rng();
+    let range = Uniform::new(1, p.value());
+
+    let evaluate = |poly: &MultivariatePolynomial<Number, u32>, point: &[ufield]| {
+        let mut r = 0;
+        for t in 0..poly.nterms {
+            let c = match number_mod_p(&poly.coefficients[t], p.value()) {
+                Some(c) => c,
+                None => return None,
+            };
+            r = zp::add(
+                r,
+                zp::mul(c, evaluate_monomial(poly.exponents(t), point, p), p),
+                p,
+            );
+        }
+        Some(r)
+    };
+
+    let mut checks = 0;
+    let mut failures = 0;
+    while checks < CHECK_SAMPLES {
+        let point: Vec<ufield> = (0..nvars).map(|_| range.sample(&mut rng)).collect();
+        let (n, d) = match (evaluate(num, &point), evaluate(den, &point)) {
+            (Some(n), Some(d)) if d != 0 => (n, d),
+            (None, _) | (_, None) => return false,
+            _ => {
+                failures += 1;
+                if failures == MAX_FAILED_SAMPLES {
+                    return false;
+                }
+                continue;
+            }
+        };
+
+        match f(&point, p.value()) {
+            Some(y) => {
+                if zp::mul(n, zp::inv(d, p), p) != y {
+                    return false;
+                }
+                checks += 1;
+            }
+            None => {
+                failures += 1;
+                if failures == MAX_FAILED_SAMPLES {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Reconstruct a rational function of `nvars` variables from a black box `f`,
+/// which evaluates the function at a point modulo a prime and returns
+/// `None` if the function is undefined at that point.
+///
+/// The numerator and denominator are returned with rational coefficients,
+/// normalized such that the lowest monomial of the denominator in the
+/// lexicographical order that has a non-zero coefficient is 1.
+/// Returns `None` if the reconstruction failed.
+pub fn reconstruct<F: Fn(&[ufield], ufield) -> Option<ufield>>(
+    f: F,
+    nvars: usize,
+) -> Option<(
+    MultivariatePolynomial<Number, u32>,
+    MultivariatePolynomial<Number, u32>,
+)> {
+    // find the support with two primes, so that a monomial whose coefficient
+    // vanishes modulo one of the primes is not lost
+    let mut supports = vec![];
+    let mut failed_primes = 0;
+    for &p in LARGE_U32_PRIMES.iter() {
+        match probe_support(&f, nvars, &FastModulus::from(p)) {
+            Some(support) => {
+                supports.push(support);
+                if supports.len() == 2 {
+                    break;
+                }
+            }
+            None => {
+                failed_primes += 1;
+                if failed_primes == MAX_FAILED_PRIMES {
+                    return None;
+                }
+            }
+        }
+    }
+
+    let (num_support2, den_support2, norm_exp2) = supports.pop()?;
+    let (num_support, den_support, norm_exp) = supports.pop()?;
+    let num_support = merge_supports(num_support, num_support2);
+    let den_support = merge_supports(den_support, den_support2);
+    let norm_exp = norm_exp.min(norm_exp2);
+    let norm = den_support.iter().position(|e| *e == norm_exp).unwrap();
+
+    let to_poly = |support: &[Vec<u32>], coeffs: Vec<Number>| {
+        let mut poly = MultivariatePolynomial::with_nvars(nvars);
+        for (e, c) in support.iter().zip(coeffs) {
+            poly.append_monomial(c, e);
+        }
+        poly
+    };
+
+    let mut num_coeffs = vec![Number::zero(); num_support.len()];
+    let mut den_coeffs = vec![Number::zero(); den_support.len()];
+    let mut m = Number::one();
+    let mut last = None;
+    for &p in LARGE_U32_PRIMES.iter() {
+        let fastp = FastModulus::from(p);
+
+        // check the previous reconstruction with the new prime
+        if let Some((ref num, ref den)) = last {
+            if check_reconstruction(&f, nvars, num, den, &fastp) {
+                return Some((num.clone(), den.clone()));
+            }
+        }
+
+        let (num, den) = match solve_ansatz(&f, nvars, &num_support, &den_support, norm, &fastp) {
+            Ok(x) => x,
+            Err(_) => {
+                debug!("Unlucky prime {} in reconstruction", p);
+                continue;
+            }
+        };
+
+        chinese_remainder_coefficients(&mut num_coeffs, &num, &m, p);
+        chinese_remainder_coefficients(&mut den_coeffs, &den, &m, p);
+        m *= Number::SmallInt(p as isize);
+
+        let rec: Option<Vec<Number>> = num_coeffs
+            .iter()
+            .chain(&den_coeffs)
+            .map(|c| number::rational_reconstruction(c, &m))
+            .collect();
+
+        last = rec.map(|mut r| {
+            let den = r.split_off(num_coeffs.len());
+            (to_poly(&num_support, r), to_poly(&den_support, den))
+        });
+    }
+
+    None
+}
+
+#[test]
+fn test_thiele() {
+    // (x^2+1)/(3x-2)
+    let p = FastModulus::from(LARGE_U32_PRIMES[0]);
+    let degrees = thiele_degrees(
+        |x| {
+            let num = zp::add(zp::mul(x, x, &p), 1, &p);
+            let den = zp::sub(zp::mul(3, x, &p), 2, &p);
+            if den == 0 {
+                None
+            } else {
+                Some(zp::mul(num, zp::inv(den, &p), &p))
+            }
+        },
+        &p,
+    );
+    assert_eq!(degrees, Some((2, 1)));
+}
@@ -0,0 +1,376 @@
+//! Root finding for univariate polynomials with rational coefficients.
+//!
+//! Real roots are isolated exactly with Sturm sequences and refined by bisection.
+//! Complex roots are approximated simultaneously with the Aberth method.
+
+use num_traits::{One, Zero};
+use rug::{Float, Integer, Rational};
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+use number::Number;
+use poly::exponent::Exponent;
+use poly::raw::univariate::UnivariatePolynomial;
+use poly::raw::MultivariatePolynomial;
+
+/// The maximum number of iterations of the Aberth method.
+const ABERTH_MAX_ITERATIONS: usize = 1000;
+/// Extra bits of precision used during the Aberth iterations.
+const ABERTH_GUARD_BITS: u32 = 32;
+
+fn sign(n: &Number) -> Ordering {
+    n.cmp(&Number::zero())
+}
+
+fn to_float(n: &Number, prec: u32) -> Float {
+    match *n {
+        Number::SmallInt(i) => Float::with_val(prec, &Integer::from(i)),
+        Number::BigInt(ref i) => Float::with_val(prec, i),
+        Number::SmallRat(num, den) => Float::with_val(prec, &Rational::from((num, den))),
+        Number::BigRat(ref r) => Float::with_val(prec, &**r),
+        Number::Float(ref f) => Float::with_val(prec, &**f),
+        Number::Complex(..) => unreachable!("Polynomial with complex coefficients"),
+    }
+}
+
+/// Compute the square-free part of a polynomial over the rationals.
+fn square_free(p: &UnivariatePolynomial<Number>) -> UnivariatePolynomial<Number> {
+    let g = p.gcd(&p.derivative());
+    p.divmod(&g).0
+}
+
+/// A Sturm sequence of a square-free polynomial.
+struct SturmSequence {
+    sequence: Vec<UnivariatePolynomial<Number>>,
+}
+
+impl SturmSequence {
+    fn new(p: &UnivariatePolynomial<Number>) -> SturmSequence {
+        let mut sequence = vec![p.clone(), p.derivative()];
+        loop {
+            let r = {
+                let n = sequence.len();
+                sequence[n - 2].rem(&sequence[n - 1])
+            };
+            if r.is_zero() {
+                break;
+            }
+
+            // scaling by a positive constant does not change the signs
+            let lc = r.lcoeff().abs();
+            sequence.push(-r.mul_coeff(&(Number::one() / lc)));
+        }
+        SturmSequence { sequence }
+    }
+
+    /// Count the sign variations of the sequence at `x`, where zeros are skipped.
+    fn variations(&self, x: &Number) -> usize {
+        let mut count = 0;
+        let mut last = Ordering::Equal;
+        for s in &self.sequence {
+            let c = sign(&s.evaluate(x));
+            if c != Ordering::Equal {
+                if last != Ordering::Equal && c != last {
+                    count += 1;
+                }
+                last = c;
+            }
+        }
+        count
+    }
+
+    /// Isolate the roots in the interval `(a, b)`, where `a` and `b`
+    /// are not roots.
+    fn isolate(&self, a: Number, b: Number, res: &mut Vec<(Number, Number)>) {
+        let p = &self.sequence[0];
+        let va = self.variations(&a);
+        let vb = self.variations(&b);
+
+        match va - vb {
+            0 => return,
+            1 => {
+                res.push((a, b));
+                return;
+            }
+            _ => {}
+        }
+
+        let m = (a.clone() + b.clone()) / Number::SmallInt(2);
+        if !p.evaluate(&m).is_zero() {
+            self.isolate(a, m.clone(), res);
+            self.isolate(m, b, res);
+            return;
+        }
+
+        // the midpoint is a root: move away from it until no other roots are
+        // between the new endpoints and the midpoint
+        let vm = self.variations(&m);
+        let mut d = (b.clone() - a.clone()) / Number::SmallInt(4);
+        let l = loop {
+            let l = m.clone() - d.clone();
+            if !p.evaluate(&l).is_zero() && self.variations(&l) == vm + 1 {
+                break l;
+            }
+            d = d / Number::SmallInt(2);
+        };
+
+        let mut d = (b.clone() - a.clone()) / Number::SmallInt(4);
+        let r = loop {
+            let r = m.clone() + d.clone();
+            if !p.evaluate(&r).is_zero() && self.variations(&r) == vm {
+                break r;
+            }
+            d = d / Number::SmallInt(2);
+        };
+
+        self.isolate(a, l, res);
+        res.push((m.clone(), m));
+        self.isolate(r, b, res);
+    }
+}
+
+/// Refine an isolating interval `(a, b)` of the square-free polynomial `p` by
+/// bisection, until its width is at most `eps`.
+fn refine(
+    p: &UnivariatePolynomial<Number>,
+    mut a: Number,
+    mut b: Number,
+    eps: &Number,
+) -> (Number, Number) {
+    let sa = sign(&p.evaluate(&a));
+    while b.clone() - a.clone() > *eps {
+        let m = (a.clone() + b.clone()) / Number::SmallInt(2);
+        let sm = sign(&p.evaluate(&m));
+        if sm == Ordering::Equal {
+            return (m.clone(), m);
+        }
+        if sm == sa {
+            a = m;
+        } else {
+            b = m;
+        }
+    }
+    (a, b)
+}
+
+/// A complex number with floating point components.
+#[derive(Clone, Debug)]
+struct Complex {
+    re: Float,
+    im: Float,
+}
+
+impl Complex {
+    fn add(&self, o: &Complex) -> Complex {
+        Complex {
+            re: self.re.clone() + &o.re,
+            im: self.im.clone() + &o.im,
+        }
+    }
+
+    fn sub(&self, o: &Complex) -> Complex {
+        Complex {
+            re: self.re.clone() - &o.re,
+            im: self.im.clone() - &o.im,
+        }
+    }
+
+    fn mul(&self, o: &Complex) -> Complex {
+        Complex {
+            re: self.re.clone() * &o.re - self.im.clone() * &o.im,
+            im: self.re.clone() * &o.im + self.im.clone() * &o.re,
+        }
+    }
+
+    fn div(&self, o: &Complex) -> Complex {
+        let n = o.norm();
+        Complex {
+            re: (self.re.clone() * &o.re + self.im.clone() * &o.im) / &n,
+            im: (self.im.clone() * &o.re - self.re.clone() * &o.im) / &n,
+        }
+    }
+
+    /// Compute the square of the absolute value.
+    fn norm(&self) -> Float {
+        self.re.clone() * &self.re + self.im.clone() * &self.im
+    }
+
+    fn is_zero(&self) -> bool {
+        self.re.is_zero() && self.im.is_zero()
+    }
+}
+
+/// Evaluate the polynomial with real coefficients `c` and its derivative at `z`.
+fn evaluate_with_derivative(c: &[Float], z: &Complex) -> (Complex, Complex) {
+    let prec = z.re.prec();
+    let zero = Complex {
+        re: Float::with_val(prec, 0),
+        im: Float::with_val(prec, 0),
+    };
+
+    let mut p = zero.clone();
+    let mut dp = zero;
+    for x in c.iter().rev() {
+        dp = dp.mul(z).add(&p);
+        p = p.mul(z);
+        p.re += x;
+    }
+    (p, dp)
+}
+
+impl<E: Exponent> MultivariatePolynomial<Number, E> {
+    /// Convert a polynomial in at most one variable to a dense representation.
+    /// Returns `None` if more than one variable occurs.
+    fn to_univariate(&self) -> Option<UnivariatePolynomial<Number>> {
+        let vars: Vec<usize> = (0..self.nvars)
+            .filter(|&i| !self.degree(i).is_zero())
+            .collect();
+
+        match vars.len() {
+            0 => Some(UnivariatePolynomial::new(self.coefficients.clone())),
+            1 => UnivariatePolynomial::from_multivariate(self, vars[0]),
+            _ => None,
+        }
+    }
+
+    /// Isolate the distinct real roots of a univariate polynomial. Every root is
+    /// given by an open interval `(a, b)` that contains no other roots. Rational
+    /// roots may be found exactly, in which case `a == b`. If `eps` is given,
+    /// the intervals are refined until their width is at most `eps`.
+    /// The roots are sorted in ascending order.
+    ///
+    /// Returns `None` if the polynomial is not univariate or zero.
+    pub fn real_roots(&self, eps: Option<&Number>) -> Option<Vec<(Number, Number)>> {
+        let p = self.to_univariate()?;
+        if p.is_zero() {
+            return None;
+        }
+        if p.degree() == 0 {
+            return Some(vec![]);
+        }
+
+        let p = square_free(&p);
+
+        // Cauchy's bound, rounded up to a power of two
+        let lc = p.lcoeff().abs();
+        let mut bound = Number::one();
+        for c in &p.coefficients {
+            let b = c.abs() / lc.clone() + Number::one();
+            while bound < b {
+                bound = bound * Number::SmallInt(2);
+            }
+        }
+
+        let sturm = SturmSequence::new(&p);
+        let mut res = vec![];
+        sturm.isolate(-bound.clone(), bound, &mut res);
+
+        if let Some(eps) = eps {
+            res = res
+                .into_iter()
+                .map(|(a, b)| {
+                    if a == b {
+                        (a, b)
+                    } else {
+                        refine(&p, a, b, eps)
+                    }
+                })
+                .collect();
+        }
+
+        Some(res)
+    }
+
+    /// Approximate the distinct complex roots of a univariate polynomial with the
+    /// Aberth method. The roots are returned as pairs of the real and imaginary
+    /// part, with `prec` bits of precision.
+    ///
+    /// Returns `None` if the polynomial is not univariate or zero.
+    pub fn complex_roots(&self, prec: u32) -> Option<Vec<(Float, Float)>> {
+        let p = self.to_univariate()?;
+        if p.is_zero() {
+            return None;
+        }
+        if p.degree() == 0 {
+            return Some(vec![]);
+        }
+
+        let p = square_free(&p).make_monic();
+        let n = p.degree();
+        let wprec = prec + ABERTH_GUARD_BITS;
+        let c: Vec<Float> = p.coefficients.iter().map(|x| to_float(x, wprec)).collect();
+
+        // start on a circle with a radius given by Cauchy's bound
+        let mut radius = Float::with_val(wprec, 0);
+        for x in &c[..n] {
+            let a = x.clone().abs();
+            if a > radius {
+                radius = a;
+            }
+        }
+        radius += 1;
+
+        let mut z: Vec<Complex> = (0..n)
+            .map(|k| {
+                let angle = 2. * PI * k as f64 / n as f64 + 0.4;
+                Complex {
+                    re: radius.clone() * Float::with_val(wprec, angle.cos()),
+                    im: radius.clone() * Float::with_val(wprec, angle.sin()),
+                }
+            })
+            .collect();
+
+        // the squared tolerance of a correction, relative to the root
+        let tol = Float::with_val(wprec, 1) >> (2 * prec);
+
+        let one = Complex {
+            re: Float::with_val(wprec, 1),
+            im: Float::with_val(wprec, 0),
+        };
+        let mut converged = vec![false; n];
+        for _ in 0..ABERTH_MAX_ITERATIONS {
+            if converged.iter().all(|x| *x) {
+                break;
+            }
+
+            for k in 0..n {
+                if converged[k] {
+                    continue;
+                }
+
+                let (pz, dpz) = evaluate_with_derivative(&c, &z[k]);
+                if pz.is_zero() {
+                    converged[k] = true;
+                    continue;
+                }
+
+                let ratio = pz.div(&dpz);
+                let mut sum = Complex {
+                    re: Float::with_val(wprec, 0),
+                    im: Float::with_val(wprec, 0),
+                };
+                for j in 0..n {
+                    if j != k {
+                        sum = sum.add(&one.div(&z[k].sub(&z[j])));
+                    }
+                }
+
+                let w = ratio.div(&one.sub(&ratio.mul(&sum)));
+                z[k] = z[k].sub(&w);
+
+                // use an absolute tolerance for roots close to zero
+                let norm = z[k].norm();
+                let scale = if norm > 1 { norm } else { one.norm() };
+                if w.norm() <= scale * &tol {
+                    converged[k] = true;
+                }
+            }
+        }
+
+        Some(
+            z.into_iter()
+                .map(|x| (Float::with_val(prec, &x.re), Float::with_val(prec, &x.im)))
+                .collect(),
+        )
+    }
+}
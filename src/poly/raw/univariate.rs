@@ -0,0 +1,818 @@
+//! Dense univariate polynomials, stored as a list of coefficients from the lowest
+//! to the highest power. For dense polynomials of high degree this representation
+//! is much faster than the sparse `MultivariatePolynomial`: multiplication uses
+//! Karatsuba's algorithm or a number-theoretic transform (NTT) for finite fields,
+//! division uses Newton iteration and the gcd uses the half-gcd algorithm.
+
+use num_traits::cast::AsPrimitive;
+use num_traits::Zero;
+use std::cmp::min;
+use std::fmt;
+use std::mem;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use number::Number;
+use poly::exponent::Exponent;
+use poly::raw::finitefield::{FiniteField, FiniteField64};
+use poly::raw::galoisfield::GaloisField;
+use poly::raw::order::MonomialOrder;
+use poly::raw::zp;
+use poly::raw::zp::ufield;
+use poly::raw::MultivariatePolynomial;
+use poly::ring::Ring;
+
+/// Below this length the schoolbook multiplication is used.
+const KARATSUBA_THRESHOLD: usize = 32;
+/// Below this length Karatsuba's algorithm is used instead of the NTT.
+const NTT_THRESHOLD: usize = 128;
+/// Below this degree of the divisor or quotient the schoolbook division is used.
+const NEWTON_DIVISION_THRESHOLD: usize = 64;
+/// Below this degree Euclid's algorithm is used instead of the half-gcd.
+const HALF_GCD_THRESHOLD: usize = 64;
+/// Below this number of points Horner's scheme is used for every point.
+const MULTIPOINT_THRESHOLD: usize = 32;
+
+/// Primes of the form `c * 2^k + 1` with `k >= 23` and primitive root 3.
+const NTT_PRIMES: [ufield; 3] = [998244353, 167772161, 469762049];
+const NTT_GENERATOR: ufield = 3;
+/// The largest transform size that is supported by all NTT primes.
+const NTT_MAX_SIZE: usize = 1 << 23;
+
+/// The zero of the ring that `x` belongs to. Note that for finite fields
+/// `R::zero()` does not know the prime.
+#[inline]
+fn zero_like<R: Ring>(x: &R) -> R {
+    x.clone() + -x.clone()
+}
+
+/// The unit of the ring that the non-zero `x` belongs to.
+#[inline]
+pub fn one_like<R: Ring>(x: &R) -> R {
+    x.clone() / x.clone()
+}
+
+/// Add `b` to `a`, where `a` is at least as long as `b`.
+fn add_assign<R: Ring>(a: &mut [R], b: &[R]) {
+    debug_assert!(a.len() >= b.len());
+    for (x, y) in a.iter_mut().zip(b) {
+        *x = mem::replace(x, R::zero()) + y.clone();
+    }
+}
+
+/// Subtract `b` from `a`, where `a` is at least as long as `b`.
+fn sub_assign<R: Ring>(a: &mut [R], b: &[R]) {
+    debug_assert!(a.len() >= b.len());
+    for (x, y) in a.iter_mut().zip(b) {
+        *x = mem::replace(x, R::zero()) + -y.clone();
+    }
+}
+
+/// Add two coefficient lists of possibly different length.
+fn add_slices<R: Ring>(a: &[R], b: &[R]) -> Vec<R> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut res = a.to_vec();
+    add_assign(&mut res, b);
+    res
+}
+
+/// Multiply two non-empty coefficient lists using the schoolbook method.
+fn schoolbook<R: Ring>(a: &[R], b: &[R]) -> Vec<R> {
+    let mut res = vec![zero_like(&a[0]); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        if x.is_zero() {
+            continue;
+        }
+        for (r, y) in res[i..].iter_mut().zip(b) {
+            *r = mem::replace(r, R::zero()) + x.clone() * y.clone();
+        }
+    }
+    res
+}
+
+/// Multiply two coefficient lists using Karatsuba's algorithm.
+pub fn karatsuba<R: Ring>(a: &[R], b: &[R]) -> Vec<R> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if b.len() < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b);
+    }
+
+    let mut res = vec![zero_like(&a[0]); a.len() + b.len() - 1];
+    if a.len() >= 2 * b.len() {
+        // split the longer list into blocks of the size of the shorter one
+        for (i, chunk) in a.chunks(b.len()).enumerate() {
+            add_assign(&mut res[i * b.len()..], &karatsuba(chunk, b));
+        }
+        return res;
+    }
+
+    // (a0 + a1 x^m) * (b0 + b1 x^m) with a single multiplication for the cross terms
+    let m = a.len() / 2;
+    let (a0, a1) = a.split_at(m);
+    let (b0, b1) = b.split_at(m);
+    let z0 = karatsuba(a0, b0);
+    let z2 = karatsuba(a1, b1);
+    let mut z1 = karatsuba(&add_slices(a0, a1), &add_slices(b0, b1));
+    sub_assign(&mut z1, &z0);
+    sub_assign(&mut z1, &z2);
+
+    add_assign(&mut res, &z0);
+    add_assign(&mut res[m..], &z1);
+    add_assign(&mut res[2 * m..], &z2);
+    res
+}
+
+/// Compute the number-theoretic transform of `a` in place. The length of `a` must
+/// be a power of two that divides `p - 1`.
+fn ntt(a: &mut [ufield], invert: bool, p: ufield) {
+    let n = a.len();
+
+    // bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j ^= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let mut w = zp::pow(NTT_GENERATOR, (p - 1) / len as ufield, p);
+        if invert {
+            w = zp::inv(w, p);
+        }
+        let half = len / 2;
+        for block in a.chunks_mut(len) {
+            let mut wk = 1;
+            for k in 0..half {
+                let u = block[k];
+                let v = zp::mul(block[k + half], wk, p);
+                block[k] = zp::add(u, v, p);
+                block[k + half] = zp::sub(u, v, p);
+                wk = zp::mul(wk, w, p);
+            }
+        }
+        len <<= 1;
+    }
+
+    if invert {
+        let n_inv = zp::inv(n as ufield % p, p);
+        for x in a.iter_mut() {
+            *x = zp::mul(*x, n_inv, p);
+        }
+    }
+}
+
+/// Multiply two non-empty coefficient lists over a finite field using the NTT.
+/// The integer product is computed modulo three NTT primes and is reconstructed with
+/// Garner's algorithm, so that any prime `p` is supported.
+fn ntt_mul(a: &[FiniteField], b: &[FiniteField]) -> Vec<FiniteField> {
+    let p = a[0].p;
+    let len = a.len() + b.len() - 1;
+    let size = len.next_power_of_two();
+    if size > NTT_MAX_SIZE {
+        return karatsuba(a, b);
+    }
+
+    // the coefficients of the integer product must be smaller than the product of the primes
+    let bound = min(a.len(), b.len()) as u128 * u128::from(p - 1) * u128::from(p - 1);
+    if bound >= NTT_PRIMES.iter().fold(1, |acc, &q| acc * u128::from(q)) {
+        return karatsuba(a, b);
+    }
+
+    let images: Vec<Vec<ufield>> = NTT_PRIMES
+        .iter()
+        .map(|&q| {
+            let mut fa = vec![0; size];
+            let mut fb = vec![0; size];
+            for (x, y) in fa.iter_mut().zip(a) {
+                *x = y.n % q;
+            }
+            for (x, y) in fb.iter_mut().zip(b) {
+                *x = y.n % q;
+            }
+            ntt(&mut fa, false, q);
+            ntt(&mut fb, false, q);
+            for (x, y) in fa.iter_mut().zip(&fb) {
+                *x = zp::mul(*x, *y, q);
+            }
+            ntt(&mut fa, true, q);
+            fa
+        })
+        .collect();
+
+    let (p1, p2, p3) = (NTT_PRIMES[0], NTT_PRIMES[1], NTT_PRIMES[2]);
+    let p12 = u64::from(p1) * u64::from(p2);
+    let inv_p1 = zp::inv(p1 % p2, p2);
+    let inv_p12 = zp::inv((p12 % u64::from(p3)) as ufield, p3);
+    (0..len)
+        .map(|i| {
+            let x1 = images[0][i];
+            let t2 = zp::mul(zp::sub(images[1][i], x1 % p2, p2), inv_p1, p2);
+            let x2 = u64::from(x1) + u64::from(p1) * u64::from(t2);
+            let x2p3 = (x2 % u64::from(p3)) as ufield;
+            let t3 = zp::mul(zp::sub(images[2][i], x2p3, p3), inv_p12, p3);
+            let x = u128::from(x2) + u128::from(p12) * u128::from(t3);
+            FiniteField::new((x % u128::from(p)) as ufield, p)
+        })
+        .collect()
+}
+
+/// Multiplication of dense coefficient lists. The default implementation uses
+/// Karatsuba's algorithm, rings that support a faster method override it.
+pub trait DenseMultiplication: Ring {
+    fn dense_mul(a: &[Self], b: &[Self]) -> Vec<Self> {
+        karatsuba(a, b)
+    }
+}
+
+impl DenseMultiplication for Number {}
+
+impl DenseMultiplication for FiniteField64 {}
+
+impl DenseMultiplication for GaloisField {}
+
+impl DenseMultiplication for FiniteField {
+    fn dense_mul(a: &[FiniteField], b: &[FiniteField]) -> Vec<FiniteField> {
+        if min(a.len(), b.len()) < NTT_THRESHOLD {
+            karatsuba(a, b)
+        } else {
+            ntt_mul(a, b)
+        }
+    }
+}
+
+/// A dense univariate polynomial. The coefficients are stored from the lowest to the
+/// highest power and the leading coefficient is non-zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnivariatePolynomial<R: Ring> {
+    pub coefficients: Vec<R>,
+}
+
+/// A 2x2 matrix of polynomials, stored row by row.
+type Matrix<R> = [UnivariatePolynomial<R>; 4];
+
+impl<R: Ring> UnivariatePolynomial<R> {
+    /// Create a polynomial from a list of coefficients, from the lowest to the
+    /// highest power.
+    pub fn new(coefficients: Vec<R>) -> UnivariatePolynomial<R> {
+        let mut res = UnivariatePolynomial { coefficients };
+        res.trim();
+        res
+    }
+
+    /// Remove the leading zeros.
+    fn trim(&mut self) {
+        while self
+            .coefficients
+            .last()
+            .map(|x| x.is_zero())
+            .unwrap_or(false)
+        {
+            self.coefficients.pop();
+        }
+    }
+
+    /// Get the degree. The zero polynomial has degree 0.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Get the degree, where the zero polynomial has degree -1.
+    fn deg(&self) -> isize {
+        self.coefficients.len() as isize - 1
+    }
+
+    /// Get the leading coefficient.
+    pub fn lcoeff(&self) -> R {
+        self.coefficients.last().cloned().unwrap_or_else(R::zero)
+    }
+
+    /// Divide the leading coefficient out.
+    pub fn make_monic(&self) -> UnivariatePolynomial<R> {
+        if self.is_zero() {
+            return self.clone();
+        }
+        let lc = self.lcoeff();
+        let inv = one_like(&lc) / lc;
+        self.mul_coeff(&inv)
+    }
+
+    /// Multiply every coefficient by `c`.
+    pub fn mul_coeff(&self, c: &R) -> UnivariatePolynomial<R> {
+        UnivariatePolynomial::new(
+            self.coefficients
+                .iter()
+                .map(|x| x.clone() * c.clone())
+                .collect(),
+        )
+    }
+
+    /// Compute `self` divided by `x^k`, dropping the remainder.
+    fn shift_down(&self, k: usize) -> UnivariatePolynomial<R> {
+        UnivariatePolynomial {
+            coefficients: self.coefficients[min(k, self.coefficients.len())..].to_vec(),
+        }
+    }
+
+    /// Compute `self` modulo `x^k`.
+    fn truncate(mut self, k: usize) -> UnivariatePolynomial<R> {
+        self.coefficients.truncate(k);
+        self.trim();
+        self
+    }
+
+    /// Compute `x^(n - 1) * self(1/x)` for a polynomial of degree smaller than `n`.
+    fn reverse(&self, n: usize) -> UnivariatePolynomial<R> {
+        debug_assert!(self.coefficients.len() <= n);
+        if self.is_zero() {
+            return self.clone();
+        }
+        let mut c = vec![zero_like(&self.coefficients[0]); n - self.coefficients.len()];
+        c.extend(self.coefficients.iter().rev().cloned());
+        UnivariatePolynomial::new(c)
+    }
+
+    /// Evaluate the polynomial at `x` using Horner's scheme.
+    pub fn evaluate(&self, x: &R) -> R {
+        let mut it = self.coefficients.iter().rev();
+        let mut res = match it.next() {
+            Some(c) => c.clone(),
+            None => return zero_like(x),
+        };
+        for c in it {
+            res = res * x.clone() + c.clone();
+        }
+        res
+    }
+
+    /// Compute the Taylor shift `p(x + a)`.
+    pub fn taylor_shift(&self, a: &R) -> UnivariatePolynomial<R> {
+        let mut c = self.coefficients.clone();
+        let n = c.len();
+        for i in 0..n {
+            for j in (i..n - 1).rev() {
+                let t = a.clone() * c[j + 1].clone();
+                c[j] = mem::replace(&mut c[j], R::zero()) + t;
+            }
+        }
+        UnivariatePolynomial::new(c)
+    }
+
+    /// Compute the derivative.
+    pub fn derivative(&self) -> UnivariatePolynomial<R> {
+        UnivariatePolynomial::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, c)| c.mul_num(i as ufield))
+                .collect(),
+        )
+    }
+
+    /// Convert a polynomial in which only the variable `x` occurs. Returns `None`
+    /// if other variables occur.
+    pub fn from_multivariate<E: Exponent, O: MonomialOrder>(
+        a: &MultivariatePolynomial<R, E, O>,
+        x: usize,
+    ) -> Option<UnivariatePolynomial<R>> {
+        if a.is_zero() {
+            return Some(UnivariatePolynomial::new(vec![]));
+        }
+
+        let zero = zero_like(&a.coefficients[0]);
+        let mut c = vec![zero; a.degree(x).as_() as usize + 1];
+        for t in 0..a.nterms {
+            let e = a.exponents(t);
+            if e.iter().enumerate().any(|(i, ee)| i != x && !ee.is_zero()) {
+                return None;
+            }
+            c[e[x].as_() as usize] = a.coefficients[t].clone();
+        }
+        Some(UnivariatePolynomial::new(c))
+    }
+
+    /// Convert to a polynomial in the variable `x` out of `nvars` variables.
+    pub fn to_multivariate<E: Exponent>(
+        &self,
+        nvars: usize,
+        x: usize,
+    ) -> MultivariatePolynomial<R, E> {
+        let mut res =
+            MultivariatePolynomial::with_nvars_and_capacity(nvars, self.coefficients.len());
+        let mut e = vec![E::zero(); nvars];
+        for (d, c) in self.coefficients.iter().enumerate() {
+            e[x] = E::from_usize(d).unwrap();
+            res.append_monomial_back(c.clone(), &e);
+        }
+        res
+    }
+}
+
+impl<R: DenseMultiplication> UnivariatePolynomial<R> {
+    /// Compute the power series inverse of `self` modulo `x^n` by Newton iteration.
+    /// The constant term must be invertible.
+    pub fn inverse_series(&self, n: usize) -> UnivariatePolynomial<R> {
+        let f0 = self.coefficients[0].clone();
+        assert!(!f0.is_zero(), "The constant term is not invertible");
+        let one = UnivariatePolynomial::new(vec![one_like(&f0)]);
+
+        let mut g = UnivariatePolynomial::new(vec![one_like(&f0) / f0]);
+        let mut k = 1;
+        while k < n {
+            k = min(2 * k, n);
+            // g <- g + g * (1 - f * g) mod x^k
+            let f = UnivariatePolynomial::new(
+                self.coefficients[..min(k, self.coefficients.len())].to_vec(),
+            );
+            let fg = (&f * &g).truncate(k);
+            let e = &one - &fg;
+            g = &g + &(&g * &e).truncate(k);
+        }
+        g
+    }
+
+    /// Divide `self` by `div` with remainder, using the schoolbook method.
+    fn divmod_schoolbook(
+        &self,
+        div: &UnivariatePolynomial<R>,
+    ) -> (UnivariatePolynomial<R>, UnivariatePolynomial<R>) {
+        let n = div.coefficients.len();
+        let m = self.coefficients.len() - n;
+        let lc = div.lcoeff();
+        let inv = one_like(&lc) / lc;
+
+        let mut r = self.coefficients.clone();
+        let mut q = vec![zero_like(&inv); m + 1];
+        for i in (0..m + 1).rev() {
+            let c = r[i + n - 1].clone() * inv.clone();
+            if !c.is_zero() {
+                for (rr, d) in r[i..].iter_mut().zip(&div.coefficients) {
+                    *rr = mem::replace(rr, R::zero()) + -(c.clone() * d.clone());
+                }
+            }
+            q[i] = c;
+        }
+        r.truncate(n - 1);
+        (UnivariatePolynomial::new(q), UnivariatePolynomial::new(r))
+    }
+
+    /// Divide `self` by `div` with remainder. For large degrees the quotient is
+    /// computed from the power series inverse of the reversed divisor.
+    pub fn divmod(
+        &self,
+        div: &UnivariatePolynomial<R>,
+    ) -> (UnivariatePolynomial<R>, UnivariatePolynomial<R>) {
+        assert!(!div.is_zero(), "Division by zero polynomial");
+        if self.deg() < div.deg() {
+            return (UnivariatePolynomial::new(vec![]), self.clone());
+        }
+
+        let m = self.degree() - div.degree();
+        if m < NEWTON_DIVISION_THRESHOLD || div.degree() < NEWTON_DIVISION_THRESHOLD {
+            return self.divmod_schoolbook(div);
+        }
+
+        // rev(q) = rev(a) / rev(b) mod x^(m + 1)
+        let ra = self.reverse(self.coefficients.len()).truncate(m + 1);
+        let rb = div.reverse(div.coefficients.len());
+        let rq = (&ra * &rb.inverse_series(m + 1)).truncate(m + 1);
+        let q = rq.reverse(m + 1);
+        let r = (self - &(&q * div)).truncate(div.degree());
+        (q, r)
+    }
+
+    /// Compute the remainder of the division of `self` by `div`.
+    pub fn rem(&self, div: &UnivariatePolynomial<R>) -> UnivariatePolynomial<R> {
+        self.divmod(div).1
+    }
+
+    /// Compute the product of two matrices.
+    fn matrix_mul(x: &Matrix<R>, y: &Matrix<R>) -> Matrix<R> {
+        [
+            &(&x[0] * &y[0]) + &(&x[1] * &y[2]),
+            &(&x[0] * &y[1]) + &(&x[1] * &y[3]),
+            &(&x[2] * &y[0]) + &(&x[3] * &y[2]),
+            &(&x[2] * &y[1]) + &(&x[3] * &y[3]),
+        ]
+    }
+
+    /// Apply the matrix to the vector `(a, b)`.
+    fn matrix_apply(
+        m: &Matrix<R>,
+        a: &UnivariatePolynomial<R>,
+        b: &UnivariatePolynomial<R>,
+    ) -> (UnivariatePolynomial<R>, UnivariatePolynomial<R>) {
+        (&(&m[0] * a) + &(&m[1] * b), &(&m[2] * a) + &(&m[3] * b))
+    }
+
+    /// Compute the matrix of the steps of the Euclidean algorithm of `a` and `b`,
+    /// with `deg(a) > deg(b)`, that reduce the degree of the remainder below
+    /// `ceil(deg(a) / 2)`. Only the upper half of the coefficients are used, so that
+    /// the recursion takes `O(M(n) log(n))` operations.
+    fn half_gcd(a: &UnivariatePolynomial<R>, b: &UnivariatePolynomial<R>) -> Matrix<R> {
+        debug_assert!(a.deg() > b.deg());
+        let m = (a.degree() + 1) / 2;
+        let one = UnivariatePolynomial::new(vec![one_like(&a.lcoeff())]);
+        let zero = UnivariatePolynomial::new(vec![]);
+        if b.deg() < m as isize {
+            return [one.clone(), zero.clone(), zero, one];
+        }
+
+        let r = Self::half_gcd(&a.shift_down(m), &b.shift_down(m));
+        let (c, d) = Self::matrix_apply(&r, a, b);
+        if d.deg() < m as isize {
+            return r;
+        }
+
+        let (q, rem) = c.divmod(&d);
+        let step = [zero, one.clone(), one, -q];
+        let r = Self::matrix_mul(&step, &r);
+        let (c, d) = (d, rem);
+        if d.deg() < m as isize {
+            return r;
+        }
+
+        let k = 2 * m - c.degree();
+        let s = Self::half_gcd(&c.shift_down(k), &d.shift_down(k));
+        Self::matrix_mul(&s, &r)
+    }
+
+    /// Compute the composition `self(other)` using Horner's scheme.
+    pub fn compose(&self, other: &UnivariatePolynomial<R>) -> UnivariatePolynomial<R> {
+        let mut it = self.coefficients.iter().rev();
+        let mut res = match it.next() {
+            Some(c) => UnivariatePolynomial::new(vec![c.clone()]),
+            None => return self.clone(),
+        };
+        for c in it {
+            res = &(&res * other) + &UnivariatePolynomial::new(vec![c.clone()]);
+        }
+        res
+    }
+
+    /// Evaluate the polynomial at many points. The polynomial is reduced modulo
+    /// the products of `x - a` for the points `a` in a subproduct tree, so that
+    /// only remainders of low degree are evaluated.
+    pub fn evaluate_multipoint(&self, points: &[R]) -> Vec<R> {
+        if self.is_zero() || points.len() < MULTIPOINT_THRESHOLD {
+            return points.iter().map(|x| self.evaluate(x)).collect();
+        }
+
+        // the leaves are the products over blocks of points
+        let one = one_like(&self.lcoeff());
+        let blocks: Vec<&[R]> = points.chunks(MULTIPOINT_THRESHOLD).collect();
+        let leaves = blocks
+            .iter()
+            .map(|b| {
+                b.iter()
+                    .fold(UnivariatePolynomial::new(vec![one.clone()]), |acc, x| {
+                        &acc * &UnivariatePolynomial::new(vec![-x.clone(), one.clone()])
+                    })
+            })
+            .collect();
+
+        let mut tree: Vec<Vec<UnivariatePolynomial<R>>> = vec![leaves];
+        while tree.last().unwrap().len() > 1 {
+            let next = tree
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|c| {
+                    if c.len() == 2 {
+                        &c[0] * &c[1]
+                    } else {
+                        c[0].clone()
+                    }
+                })
+                .collect();
+            tree.push(next);
+        }
+
+        // descend the tree with the remainders
+        let mut rems = vec![self.rem(&tree.last().unwrap()[0])];
+        for level in tree.iter().rev().skip(1) {
+            rems = level
+                .iter()
+                .enumerate()
+                .map(|(i, m)| rems[i / 2].rem(m))
+                .collect();
+        }
+
+        blocks
+            .iter()
+            .zip(&rems)
+            .flat_map(|(b, r)| b.iter().map(move |x| r.evaluate(x)))
+            .collect()
+    }
+
+    /// Compute the monic gcd using the half-gcd algorithm.
+    pub fn gcd(&self, other: &UnivariatePolynomial<R>) -> UnivariatePolynomial<R> {
+        let (mut a, mut b) = if self.deg() >= other.deg() {
+            (self.clone(), other.clone())
+        } else {
+            (other.clone(), self.clone())
+        };
+
+        while !b.is_zero() {
+            if a.degree() > b.degree() && b.degree() >= HALF_GCD_THRESHOLD {
+                let m = Self::half_gcd(&a, &b);
+                let (c, d) = Self::matrix_apply(&m, &a, &b);
+                a = c;
+                b = d;
+                if b.is_zero() {
+                    break;
+                }
+            }
+
+            let r = a.rem(&b);
+            a = b;
+            b = r;
+        }
+
+        a.make_monic()
+    }
+}
+
+impl<R: Ring> fmt::Display for UnivariatePolynomial<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut first = true;
+        for (d, c) in self.coefficients.iter().enumerate() {
+            if c.is_zero() {
+                continue;
+            }
+            if !first {
+                write!(f, "+")?;
+            }
+            first = false;
+            match d {
+                0 => write!(f, "{}", c)?,
+                1 => write!(f, "{}*x", c)?,
+                _ => write!(f, "{}*x^{}", c, d)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Ring> Zero for UnivariatePolynomial<R> {
+    fn zero() -> Self {
+        UnivariatePolynomial::new(vec![])
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+}
+
+impl<R: Ring> Add for UnivariatePolynomial<R> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self::Output {
+        &self + &other
+    }
+}
+
+impl<'a, 'b, R: Ring> Add<&'b UnivariatePolynomial<R>> for &'a UnivariatePolynomial<R> {
+    type Output = UnivariatePolynomial<R>;
+
+    fn add(self, other: &'b UnivariatePolynomial<R>) -> Self::Output {
+        UnivariatePolynomial::new(add_slices(&self.coefficients, &other.coefficients))
+    }
+}
+
+impl<'a, 'b, R: Ring> Sub<&'b UnivariatePolynomial<R>> for &'a UnivariatePolynomial<R> {
+    type Output = UnivariatePolynomial<R>;
+
+    fn sub(self, other: &'b UnivariatePolynomial<R>) -> Self::Output {
+        let mut res = self.coefficients.clone();
+        if res.len() < other.coefficients.len() {
+            let zero = zero_like(&other.coefficients[0]);
+            res.resize(other.coefficients.len(), zero);
+        }
+        sub_assign(&mut res, &other.coefficients);
+        UnivariatePolynomial::new(res)
+    }
+}
+
+impl<R: Ring> Neg for UnivariatePolynomial<R> {
+    type Output = Self;
+
+    fn neg(mut self) -> Self::Output {
+        for c in &mut self.coefficients {
+            *c = mem::replace(c, R::zero()).neg();
+        }
+        self
+    }
+}
+
+impl<R: DenseMultiplication> Mul for UnivariatePolynomial<R> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self::Output {
+        &self * &other
+    }
+}
+
+impl<'a, 'b, R: DenseMultiplication> Mul<&'b UnivariatePolynomial<R>>
+    for &'a UnivariatePolynomial<R>
+{
+    type Output = UnivariatePolynomial<R>;
+
+    fn mul(self, other: &'b UnivariatePolynomial<R>) -> Self::Output {
+        UnivariatePolynomial::new(R::dense_mul(&self.coefficients, &other.coefficients))
+    }
+}
+
+#[test]
+fn test_univariate_polynomial() {
+    let p = 4293491017;
+    // deterministic pseudo-random coefficients
+    let mut seed: u64 = 12345;
+    let mut random_poly = |n: usize| {
+        UnivariatePolynomial::new(
+            (0..n)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    FiniteField::new((seed >> 33) as ufield, p)
+                })
+                .collect(),
+        )
+    };
+
+    let a = random_poly(300);
+    let b = random_poly(200);
+    let c = random_poly(150);
+
+    let ab = &a * &b;
+    assert_eq!(
+        ab.coefficients,
+        schoolbook(&a.coefficients, &b.coefficients)
+    );
+    assert_eq!(ab.coefficients, karatsuba(&a.coefficients, &b.coefficients));
+
+    // Newton division
+    let (q, r) = (&ab + &c).divmod(&a);
+    assert_eq!(q, b);
+    assert_eq!(r, c);
+
+    // half-gcd
+    let g = random_poly(120).make_monic();
+    assert_eq!((&g * &a).gcd(&(&g * &b)), g);
+    assert_eq!(
+        a.gcd(&g),
+        UnivariatePolynomial::new(vec![FiniteField::new(1, p)])
+    );
+}
+
+#[test]
+fn test_univariate_evaluation() {
+    let p = 4293491017;
+    let a = UnivariatePolynomial::new((0..50u32).map(|i| FiniteField::new(i * i + 3, p)).collect());
+    let b = UnivariatePolynomial::new(vec![
+        FiniteField::new(2, p),
+        FiniteField::new(0, p),
+        FiniteField::new(5, p),
+    ]);
+
+    // evaluate term by term with explicit powers
+    let naive = |f: &UnivariatePolynomial<FiniteField>, x: FiniteField| {
+        let mut res = FiniteField::new(0, p);
+        let mut xi = FiniteField::new(1, p);
+        for c in &f.coefficients {
+            res = res + *c * xi;
+            xi = xi * x;
+        }
+        res
+    };
+
+    // more points than MULTIPOINT_THRESHOLD, so that the subproduct tree is used
+    let points: Vec<_> = (0..100).map(|i| FiniteField::new(7 * i + 1, p)).collect();
+    let values = a.evaluate_multipoint(&points);
+    assert_eq!(values.len(), points.len());
+
+    let shift = FiniteField::new(11, p);
+    let shifted = a.taylor_shift(&shift);
+    let composed = a.compose(&b);
+    assert_eq!(composed.degree(), 98);
+
+    for (x, v) in points.iter().zip(&values) {
+        assert_eq!(*v, naive(&a, *x));
+        assert_eq!(naive(&shifted, *x), naive(&a, *x + shift));
+        assert_eq!(naive(&composed, *x), naive(&a, naive(&b, *x)));
+    }
+}
@@ -0,0 +1,125 @@
+//! Modular arithmetic in Zp for primes `p < 2^63`. Each element x is in the range `0 <= x < p`
+//! and is stored in a `u64`, with `u128` used in intermediate stages.
+//! Using large primes reduces the number of images that are needed for the
+//! Chinese remainder theorem, for example in the modular gcd.
+//! The routines are generic in the modulus: passing a `Montgomery64` instead of the prime
+//! replaces the `u128` division in the reduction by Montgomery reductions.
+
+pub use super::zp_mod::{Modulus, Montgomery64};
+
+/// Computes `x + y` in Zp.
+#[inline]
+pub fn add<M: Modulus<u128, u64>>(x: u64, y: u64, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    debug_assert!(y < p.value());
+    // no overflow, since p < 2^63
+    let z = x + y;
+    if z >= p.value() {
+        z - p.value()
+    } else {
+        z
+    }
+}
+
+/// Computes `x - y` in Zp.
+#[inline]
+pub fn sub<M: Modulus<u128, u64>>(x: u64, y: u64, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    debug_assert!(y < p.value());
+    if x >= y {
+        x - y
+    } else {
+        x + p.value() - y
+    }
+}
+
+/// Computes `x * y` in Zp.
+#[inline]
+pub fn mul<M: Modulus<u128, u64>>(x: u64, y: u64, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    debug_assert!(y < p.value());
+    let z = p.modulus(u128::from(x) * u128::from(y));
+    debug_assert!(z < u128::from(p.value()));
+    z as u64
+}
+
+/// Computes `-x` in Zp.
+#[inline]
+pub fn neg<M: Modulus<u128, u64>>(x: u64, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    if x == 0 {
+        0
+    } else {
+        p.value() - x
+    }
+}
+
+/// Computes `1/x` in Zp.
+#[inline]
+pub fn inv<M: Modulus<u128, u64>>(x: u64, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    assert!(x != 0, "0 is not invertible");
+    // the same extended Euclidean algorithm as in `zp::inv`
+    let mut u1: u64 = 1;
+    let mut u3 = x;
+    let mut v1: u64 = 0;
+    let mut v3 = p.value();
+    let mut even_iter: bool = true;
+    while v3 != 0 {
+        let q = u3 / v3;
+        let t3 = u3 % v3;
+        let t1 = u1 + q * v1;
+        u1 = v1;
+        v1 = t1;
+        u3 = v3;
+        v3 = t3;
+        even_iter = !even_iter;
+    }
+    debug_assert!(u3 == 1);
+    if even_iter {
+        u1
+    } else {
+        p.value() - u1
+    }
+}
+
+/// Computes `x^n` in Zp.
+pub fn pow<M: Modulus<u128, u64>>(x: u64, mut n: u32, p: M) -> u64 {
+    debug_assert!(p.value() > 0);
+    debug_assert!(x < p.value());
+    let mut r: u64 = 1;
+    let mut b = x;
+    while n != 0 {
+        if n & 1 != 0 {
+            r = mul(r, b, p);
+        }
+        b = mul(b, b, p);
+        n >>= 1;
+    }
+    r
+}
+
+#[test]
+fn test_zp64() {
+    // p = 2^63 - 25
+    let p: u64 = 9223372036854775783;
+    let x = p - 2;
+    let y = 1 << 62;
+
+    assert_eq!(add(x, y, p), y - 2);
+    assert_eq!(sub(y, x, p), y + 2);
+    assert_eq!(mul(x, y, p), p - 25);
+    assert_eq!(mul(inv(x, p), x, p), 1);
+    assert_eq!(pow(2, 63, p), 25);
+    assert_eq!(neg(25, p), p - 25);
+
+    let m = Montgomery64::new(p);
+    assert_eq!(mul(x, y, m), p - 25);
+    assert_eq!(mul(inv(x, m), x, m), 1);
+    assert_eq!(pow(2, 63, m), 25);
+}
@@ -0,0 +1,283 @@
+//! Dense linear algebra in Zp.
+
+use ndarray::{Array2, ArrayBase, Data, Ix2};
+
+use super::zp;
+use super::zp::{ucomp, ufield, Modulus};
+use super::zp_solve::LinearSolverError;
+
+/// A matrix in row echelon form, obtained by Gaussian elimination with row swaps.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RowEchelonForm {
+    /// The transformed matrix.
+    pub matrix: Array2<ufield>,
+    /// The column of the pivot of every non-zero row.
+    pub pivots: Vec<usize>,
+    /// The original row index of every row.
+    pub permutation: Vec<usize>,
+    /// The number of row swaps.
+    pub swaps: usize,
+}
+
+impl RowEchelonForm {
+    /// The rank of the matrix.
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+}
+
+/// Transform `m` into row echelon form, using only the first `max_col` columns
+/// as pivot columns. If `reduced` is true, the pivots are normalized to 1
+/// and the entries above the pivots are eliminated.
+fn row_echelon<M: Modulus<ucomp, ufield>>(
+    mut m: Array2<ufield>,
+    max_col: usize,
+    reduced: bool,
+    p: M,
+) -> RowEchelonForm {
+    let nrows = m.shape()[0];
+    let ncols = m.shape()[1];
+
+    let mut pivots = vec![];
+    let mut permutation: Vec<usize> = (0..nrows).collect();
+    let mut swaps = 0;
+
+    let mut i = 0;
+    for j in 0..max_col {
+        if i == nrows {
+            break;
+        }
+
+        let k = match (i..nrows).find(|&k| m[(k, j)] != 0) {
+            Some(k) => k,
+            None => continue,
+        };
+
+        if k != i {
+            for l in j..ncols {
+                m.swap((i, l), (k, l));
+            }
+            permutation.swap(i, k);
+            swaps += 1;
+        }
+
+        let inv_x = zp::inv(m[(i, j)], p);
+        if reduced {
+            for l in j..ncols {
+                m[(i, l)] = zp::mul(m[(i, l)], inv_x, p);
+            }
+        }
+
+        for k in 0..nrows {
+            if k == i || (k < i && !reduced) || m[(k, j)] == 0 {
+                continue;
+            }
+
+            let s = if reduced {
+                m[(k, j)]
+            } else {
+                zp::mul(m[(k, j)], inv_x, p)
+            };
+            m[(k, j)] = 0;
+            for l in j + 1..ncols {
+                if m[(i, l)] != 0 {
+                    m[(k, l)] = zp::sub(m[(k, l)], zp::mul(m[(i, l)], s, p), p);
+                }
+            }
+        }
+
+        pivots.push(j);
+        i += 1;
+    }
+
+    RowEchelonForm {
+        matrix: m,
+        pivots,
+        permutation,
+        swaps,
+    }
+}
+
+/// Linear algebra operations on dense matrices in Zp.
+pub trait ZpMatrix {
+    /// Compute the row echelon form, using the first `max_col` columns as pivot columns.
+    fn row_echelon_form<M: Modulus<ucomp, ufield>>(&self, max_col: usize, p: M) -> RowEchelonForm;
+
+    /// Compute the reduced row echelon form, using the first `max_col` columns
+    /// as pivot columns.
+    fn reduced_row_echelon_form<M: Modulus<ucomp, ufield>>(
+        &self,
+        max_col: usize,
+        p: M,
+    ) -> RowEchelonForm;
+
+    /// Compute the rank.
+    fn rank<M: Modulus<ucomp, ufield>>(&self, p: M) -> usize;
+
+    /// Compute a basis of the nullspace. Every column of the result is a basis vector.
+    fn nullspace<M: Modulus<ucomp, ufield>>(&self, p: M) -> Array2<ufield>;
+
+    /// Compute the determinant of a square matrix.
+    fn determinant<M: Modulus<ucomp, ufield>>(&self, p: M) -> ufield;
+
+    /// Compute the inverse of a square matrix. Returns `None` if the matrix is singular.
+    fn inverse<M: Modulus<ucomp, ufield>>(&self, p: M) -> Option<Array2<ufield>>;
+
+    /// Solve `self * x = b` for every column in `b`.
+    fn solve_multiple<S: Data<Elem = ufield>, M: Modulus<ucomp, ufield>>(
+        &self,
+        b: &ArrayBase<S, Ix2>,
+        p: M,
+    ) -> Result<Array2<ufield>, LinearSolverError>;
+}
+
+impl<S1: Data<Elem = ufield>> ZpMatrix for ArrayBase<S1, Ix2> {
+    fn row_echelon_form<M: Modulus<ucomp, ufield>>(&self, max_col: usize, p: M) -> RowEchelonForm {
+        row_echelon(self.to_owned(), max_col, false, p)
+    }
+
+    fn reduced_row_echelon_form<M: Modulus<ucomp, ufield>>(
+        &self,
+        max_col: usize,
+        p: M,
+    ) -> RowEchelonForm {
+        row_echelon(self.to_owned(), max_col, true, p)
+    }
+
+    fn rank<M: Modulus<ucomp, ufield>>(&self, p: M) -> usize {
+        self.row_echelon_form(self.shape()[1], p).rank()
+    }
+
+    fn nullspace<M: Modulus<ucomp, ufield>>(&self, p: M) -> Array2<ufield> {
+        let ncols = self.shape()[1];
+        let r = self.reduced_row_echelon_form(ncols, p);
+
+        let free: Vec<usize> = (0..ncols).filter(|j| !r.pivots.contains(j)).collect();
+
+        // every free variable gives a basis vector
+        let mut basis = Array2::<ufield>::zeros((ncols, free.len()));
+        for (k, &f) in free.iter().enumerate() {
+            basis[(f, k)] = 1;
+            for (i, &pc) in r.pivots.iter().enumerate() {
+                basis[(pc, k)] = zp::neg(r.matrix[(i, f)], p);
+            }
+        }
+        basis
+    }
+
+    fn determinant<M: Modulus<ucomp, ufield>>(&self, p: M) -> ufield {
+        let n = self.shape()[0];
+        assert_eq!(n, self.shape()[1], "Determinant of a non-square matrix");
+
+        let r = self.row_echelon_form(n, p);
+        if r.rank() < n {
+            return 0;
+        }
+
+        let mut det = 1;
+        for i in 0..n {
+            det = zp::mul(det, r.matrix[(i, i)], p);
+        }
+        if r.swaps % 2 == 1 {
+            det = zp::neg(det, p);
+        }
+        det
+    }
+
+    fn inverse<M: Modulus<ucomp, ufield>>(&self, p: M) -> Option<Array2<ufield>> {
+        let n = self.shape()[0];
+        assert_eq!(n, self.shape()[1], "Inverse of a non-square matrix");
+        self.solve_multiple(&Array2::eye(n), p).ok()
+    }
+
+    fn solve_multiple<S: Data<Elem = ufield>, M: Modulus<ucomp, ufield>>(
+        &self,
+        b: &ArrayBase<S, Ix2>,
+        p: M,
+    ) -> Result<Array2<ufield>, LinearSolverError> {
+        assert_eq!(self.shape()[0], b.shape()[0]);
+
+        let neqs = self.shape()[0];
+        let nvars = self.shape()[1];
+        let nrhs = b.shape()[1];
+
+        // create the augmented matrix
+        let mut m = Array2::<ufield>::zeros((neqs, nvars + nrhs));
+        for ((i, j), e) in self.indexed_iter() {
+            m[(i, j)] = *e;
+        }
+        for ((i, j), e) in b.indexed_iter() {
+            m[(i, nvars + j)] = *e;
+        }
+
+        let r = row_echelon(m, nvars, true, p);
+        let rank = r.rank();
+
+        // check the consistency
+        for i in rank..neqs {
+            if (0..nrhs).any(|j| r.matrix[(i, nvars + j)] != 0) {
+                return Err(LinearSolverError::Inconsistent);
+            }
+        }
+
+        if rank < nvars {
+            return Err(LinearSolverError::Underdetermined {
+                min_rank: rank,
+                max_rank: rank,
+            });
+        }
+
+        let mut x = Array2::<ufield>::zeros((nvars, nrhs));
+        for i in 0..nvars {
+            for j in 0..nrhs {
+                x[(i, j)] = r.matrix[(i, nvars + j)];
+            }
+        }
+        Ok(x)
+    }
+}
+
+#[test]
+fn test_determinant() {
+    use ndarray::arr2;
+    let a: Array2<ufield> = arr2(&[[0, 1, 2], [3, 4, 3], [16, 5, 5]]);
+    let p: ufield = 17;
+    // det = -1*(15-48) + 2*(15-64) = 33 - 98 = -65 = 3 mod 17
+    assert_eq!(a.determinant(p), 3);
+}
+
+#[test]
+fn test_inverse() {
+    use ndarray::arr2;
+    let a: Array2<ufield> = arr2(&[[1, 1, 2], [3, 4, 3], [16, 5, 5]]);
+    let p: ufield = 17;
+    let inv = a.inverse(p).unwrap();
+
+    let mut prod = Array2::<ufield>::zeros((3, 3));
+    for i in 0..3 {
+        for j in 0..3 {
+            for k in 0..3 {
+                prod[(i, j)] = zp::add(prod[(i, j)], zp::mul(a[(i, k)], inv[(k, j)], p), p);
+            }
+        }
+    }
+    assert_eq!(prod, Array2::eye(3));
+}
+
+#[test]
+fn test_nullspace() {
+    use ndarray::arr2;
+    let a: Array2<ufield> = arr2(&[[1, 1, 2], [3, 4, 3], [10, 7, 12]]);
+    let p: ufield = 17;
+    assert_eq!(a.rank(p), 2);
+
+    let n = a.nullspace(p);
+    assert_eq!(n.shape(), &[3, 1]);
+    for i in 0..3 {
+        let mut s = 0;
+        for k in 0..3 {
+            s = zp::add(s, zp::mul(a[(i, k)], n[(k, 0)], p), p);
+        }
+        assert_eq!(s, 0);
+    }
+}
@@ -56,3 +56,124 @@ impl<'a> Modulus<u64, u32> for &'a FastModulus6432 {
         x - division * u64::from(self.n)
     }
 }
+
+impl Modulus<u128, u64> for u64 {
+    #[inline]
+    fn value(&self) -> u64 {
+        *self
+    }
+
+    #[inline]
+    fn modulus(&self, x: u128) -> u128 {
+        x % u128::from(*self)
+    }
+}
+
+/// Montgomery multiplication for odd moduli `p < 2^63`, using `R = 2^64`.
+/// A number `x` is represented by its Montgomery form `x R mod p`, so that
+/// the product of two numbers only requires a single reduction without division.
+/// Numbers should be kept in Montgomery form during a computation and only be
+/// converted at the boundaries: addition and subtraction work on the Montgomery form
+/// as they do on the standard representation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Montgomery64 {
+    p: u64,
+    /// `-p^-1 mod R`.
+    p_inv: u64,
+    /// `R^2 mod p`.
+    r2: u64,
+}
+
+impl Montgomery64 {
+    /// Construct the Montgomery parameters for the odd modulus `p`.
+    pub fn new(p: u64) -> Montgomery64 {
+        assert!(
+            p % 2 == 1 && p < 1 << 63,
+            "Modulus must be odd and smaller than 2^63"
+        );
+
+        // Newton iteration for p^-1 mod 2^64: every step doubles the number of correct bits,
+        // starting with 3 correct bits since p * p = 1 mod 8
+        let mut inv = p;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(p.wrapping_mul(inv)));
+        }
+        debug_assert_eq!(p.wrapping_mul(inv), 1);
+
+        let r = (1u128 << 64) % u128::from(p);
+        Montgomery64 {
+            p,
+            p_inv: inv.wrapping_neg(),
+            r2: (r * r % u128::from(p)) as u64,
+        }
+    }
+
+    /// Compute `x R^-1 mod p` for `x < p R`.
+    #[inline]
+    pub fn reduce(&self, x: u128) -> u64 {
+        debug_assert!(x >> 64 < u128::from(self.p));
+        let m = (x as u64).wrapping_mul(self.p_inv);
+        // x + m p < 2 p R fits in u128, since p < 2^63
+        let t = ((x + u128::from(m) * u128::from(self.p)) >> 64) as u64;
+        if t >= self.p {
+            t - self.p
+        } else {
+            t
+        }
+    }
+
+    /// Convert `x < p` to Montgomery form.
+    #[inline]
+    pub fn to_montgomery(&self, x: u64) -> u64 {
+        self.reduce(u128::from(x) * u128::from(self.r2))
+    }
+
+    /// Convert `x` from Montgomery form to the standard representation.
+    #[inline]
+    pub fn from_montgomery(&self, x: u64) -> u64 {
+        self.reduce(u128::from(x))
+    }
+
+    /// Multiply two numbers in Montgomery form.
+    #[inline]
+    pub fn mul(&self, x: u64, y: u64) -> u64 {
+        self.reduce(u128::from(x) * u128::from(y))
+    }
+
+    /// The Montgomery form of 1.
+    #[inline]
+    pub fn one(&self) -> u64 {
+        self.to_montgomery(1)
+    }
+}
+
+impl Modulus<u128, u64> for Montgomery64 {
+    #[inline]
+    fn value(&self) -> u64 {
+        self.p
+    }
+
+    /// Modulo operation for `x < p R`, such as the product of two numbers smaller than `p`.
+    /// Two reductions replace the division: `reduce(reduce(x) R^2) = x R^-1 R^2 R^-1 = x`.
+    #[inline]
+    fn modulus(&self, x: u128) -> u128 {
+        u128::from(self.mul(self.reduce(x), self.r2))
+    }
+}
+
+#[test]
+fn test_montgomery() {
+    let p = 9223372036854775783;
+    let m = Montgomery64::new(p);
+    for &(x, y) in &[(2, 3), (p - 1, p - 1), (1 << 62, 12345678901234567), (0, 5)] {
+        let expected = (u128::from(x) * u128::from(y) % u128::from(p)) as u64;
+        let xm = m.to_montgomery(x);
+        let ym = m.to_montgomery(y);
+        assert_eq!(m.from_montgomery(m.mul(xm, ym)), expected);
+        assert_eq!(
+            m.modulus(u128::from(x) * u128::from(y)),
+            u128::from(expected)
+        );
+    }
+    assert_eq!(m.from_montgomery(m.one()), 1);
+}
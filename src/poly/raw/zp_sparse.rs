@@ -0,0 +1,216 @@
+//! Sparse linear algebra in Zp, for the very sparse systems that arise from
+//! integration-by-parts relations.
+
+use ndarray::{Array1, Array2};
+use std::mem;
+
+use super::zp;
+use super::zp::{ucomp, ufield, Modulus};
+use super::zp_solve::LinearSolverError;
+
+/// A sparse row, stored as a list of column indices and non-zero values
+/// sorted by column.
+pub type SparseRow = Vec<(usize, ufield)>;
+
+/// A sparse matrix in Zp.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SparseZpMatrix {
+    pub ncols: usize,
+    pub rows: Vec<SparseRow>,
+}
+
+/// Compute `a - s * b` for two sparse rows.
+fn sub_mul_row<M: Modulus<ucomp, ufield>>(
+    a: &[(usize, ufield)],
+    b: &[(usize, ufield)],
+    s: ufield,
+    p: M,
+) -> SparseRow {
+    let mut res = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if j == b.len() || (i < a.len() && a[i].0 < b[j].0) {
+            res.push(a[i]);
+            i += 1;
+        } else if i == a.len() || b[j].0 < a[i].0 {
+            res.push((b[j].0, zp::neg(zp::mul(b[j].1, s, p), p)));
+            j += 1;
+        } else {
+            let v = zp::sub(a[i].1, zp::mul(b[j].1, s, p), p);
+            if v != 0 {
+                res.push((a[i].0, v));
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+    res
+}
+
+impl SparseZpMatrix {
+    /// Create an empty matrix with `ncols` columns.
+    pub fn new(ncols: usize) -> SparseZpMatrix {
+        SparseZpMatrix {
+            ncols,
+            rows: vec![],
+        }
+    }
+
+    /// Add a row, given as a list of column indices and values.
+    pub fn push_row(&mut self, mut row: SparseRow) {
+        row.retain(|x| x.1 != 0);
+        row.sort_by_key(|x| x.0);
+        debug_assert!(row.last().map(|x| x.0 < self.ncols).unwrap_or(true));
+        self.rows.push(row);
+    }
+
+    /// Convert a dense matrix to a sparse matrix.
+    pub fn from_dense(m: &Array2<ufield>) -> SparseZpMatrix {
+        let mut res = SparseZpMatrix::new(m.shape()[1]);
+        for i in 0..m.shape()[0] {
+            res.push_row((0..m.shape()[1]).map(|j| (j, m[(i, j)])).collect());
+        }
+        res
+    }
+
+    /// Convert to a dense matrix.
+    pub fn to_dense(&self) -> Array2<ufield> {
+        let mut m = Array2::zeros((self.rows.len(), self.ncols));
+        for (i, r) in self.rows.iter().enumerate() {
+            for &(j, v) in r {
+                m[(i, j)] = v;
+            }
+        }
+        m
+    }
+
+    /// Transform the matrix into row echelon form using structured Gaussian elimination,
+    /// pivoting only on the first `max_col` columns. For every column the sparsest
+    /// row is chosen as the pivot, to limit the fill-in. The pivots are normalized to 1
+    /// and zero rows are removed. If `reduced` is true, the entries above the pivots
+    /// are eliminated as well.
+    pub fn row_echelon_form<M: Modulus<ucomp, ufield>>(
+        &mut self,
+        max_col: usize,
+        reduced: bool,
+        p: M,
+    ) {
+        // group the rows by their leading column
+        let mut buckets = vec![vec![]; self.ncols];
+        for r in self.rows.drain(..) {
+            if let Some(&(j, _)) = r.first() {
+                buckets[j].push(r);
+            }
+        }
+
+        let mut echelon: Vec<SparseRow> = vec![];
+        let mut remainder = vec![];
+        for j in 0..self.ncols {
+            let mut bucket = mem::replace(&mut buckets[j], vec![]);
+            if bucket.is_empty() {
+                continue;
+            }
+
+            if j >= max_col {
+                remainder.extend(bucket);
+                continue;
+            }
+
+            let pi = (0..bucket.len()).min_by_key(|&i| bucket[i].len()).unwrap();
+            let mut pivot = bucket.swap_remove(pi);
+            let inv_x = zp::inv(pivot[0].1, p);
+            for x in &mut pivot {
+                x.1 = zp::mul(x.1, inv_x, p);
+            }
+
+            for r in bucket {
+                let s = r[0].1;
+                let r = sub_mul_row(&r, &pivot, s, p);
+                if let Some(&(k, _)) = r.first() {
+                    buckets[k].push(r);
+                }
+            }
+            echelon.push(pivot);
+        }
+
+        if reduced {
+            // back substitution, starting from the last pivot
+            for i in (0..echelon.len()).rev() {
+                let (head, tail) = echelon.split_at_mut(i);
+                let pivot = &tail[0];
+                let j = pivot[0].0;
+                for r in head.iter_mut() {
+                    if let Ok(pos) = r.binary_search_by_key(&j, |x| x.0) {
+                        let s = r[pos].1;
+                        *r = sub_mul_row(r, pivot, s, p);
+                    }
+                }
+            }
+        }
+
+        echelon.extend(remainder);
+        self.rows = echelon;
+    }
+
+    /// Compute the rank.
+    pub fn rank<M: Modulus<ucomp, ufield>>(&self, p: M) -> usize {
+        let mut m = self.clone();
+        m.row_echelon_form(self.ncols, false, p);
+        m.rows.len()
+    }
+
+    /// Solve `self * x = b` in Zp.
+    pub fn solve<M: Modulus<ucomp, ufield>>(
+        &self,
+        b: &[ufield],
+        p: M,
+    ) -> Result<Array1<ufield>, LinearSolverError> {
+        assert_eq!(self.rows.len(), b.len());
+
+        // augment the matrix with `b` as an extra column
+        let nvars = self.ncols;
+        let mut m = SparseZpMatrix::new(nvars + 1);
+        for (r, bb) in self.rows.iter().zip(b) {
+            let mut row = r.clone();
+            if *bb != 0 {
+                row.push((nvars, *bb));
+            }
+            m.rows.push(row);
+        }
+
+        m.row_echelon_form(nvars, true, p);
+
+        // a row with only the augmented entry is inconsistent
+        if m.rows.iter().any(|r| r[0].0 == nvars) {
+            return Err(LinearSolverError::Inconsistent);
+        }
+
+        let rank = m.rows.len();
+        if rank < nvars {
+            return Err(LinearSolverError::Underdetermined {
+                min_rank: rank,
+                max_rank: rank,
+            });
+        }
+
+        let mut x = Array1::zeros(nvars);
+        for r in &m.rows {
+            if let Some(&(j, v)) = r.last() {
+                if j == nvars {
+                    x[r[0].0] = v;
+                }
+            }
+        }
+        Ok(x)
+    }
+}
+
+#[test]
+fn test_sparse_solve() {
+    use ndarray::{arr1, arr2};
+    let a: Array2<ufield> = arr2(&[[1, 1, 2], [3, 4, 3], [16, 5, 5]]);
+    let p: ufield = 17;
+    let m = SparseZpMatrix::from_dense(&a);
+    assert_eq!(m.solve(&[3, 15, 8], p), Ok(arr1(&[2, 3, 16])));
+    assert_eq!(m.rank(p), 3);
+}
@@ -1,13 +1,17 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use gmp_mpfr_sys::gmp;
-use num_traits::{One, Zero};
+use num_traits::{FromPrimitive, One, ToPrimitive, Zero};
 use number::Number;
+use poly::exponent::Exponent;
 use poly::polynomial::Polynomial;
+use poly::raw::adaptive::AdaptivePolynomial;
 use poly::raw::MultivariatePolynomial;
-use rug::{Integer, Rational};
+use rug::float::Special;
+use rug::{Float, Integer, Rational};
 use std::cmp::Ordering;
 use std::io::Cursor;
 use std::io::{Error, Read, Seek, SeekFrom, Write};
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
 use structure::*;
@@ -25,6 +29,13 @@ const NUM_SMALLINT_ID: u8 = 1;
 const NUM_BIGINT_ID: u8 = 2;
 const NUM_SMALLRAT_ID: u8 = 3;
 const NUM_BIGRAT_ID: u8 = 4;
+const NUM_FLOAT_ID: u8 = 5;
+const NUM_COMPLEX_ID: u8 = 6;
+const NUM_SPECIAL_FLOAT_ID: u8 = 7;
+
+const FLOAT_NAN: u8 = 0;
+const FLOAT_INFINITY: u8 = 1;
+const FLOAT_MINUS_INFINITY: u8 = 2;
 
 #[derive(Debug, Clone)]
 pub struct SerializedTerm(pub Vec<u8>);
@@ -181,6 +192,34 @@ impl Number {
                 let n = serialize_integer(r.numer(), buffer);
                 1 + n + serialize_integer(r.denom(), buffer)
             }
+            Number::Float(f) => match f.to_integer_exp() {
+                // store the precision, the exponent and the mantissa
+                Some((m, e)) => {
+                    buffer.write_u8(NUM_FLOAT_ID).unwrap();
+                    buffer.write_u32::<LittleEndian>(f.prec()).unwrap();
+                    buffer.write_i32::<LittleEndian>(e).unwrap();
+                    9 + serialize_integer(&m, buffer)
+                }
+                // store the precision and which special value it is
+                None => {
+                    let special = if f.is_nan() {
+                        FLOAT_NAN
+                    } else if f.is_sign_positive() {
+                        FLOAT_INFINITY
+                    } else {
+                        FLOAT_MINUS_INFINITY
+                    };
+                    buffer.write_u8(NUM_SPECIAL_FLOAT_ID).unwrap();
+                    buffer.write_u32::<LittleEndian>(f.prec()).unwrap();
+                    buffer.write_u8(special).unwrap();
+                    6
+                }
+            },
+            Number::Complex(c) => {
+                buffer.write_u8(NUM_COMPLEX_ID).unwrap();
+                let n = c.0.serialize(buffer);
+                1 + n + c.1.serialize(buffer)
+            }
         }
     }
 
@@ -197,6 +236,28 @@ impl Number {
                 let den = deserialize_integer(buffer)?;
                 Number::BigRat(Box::new(Rational::from((num, den))))
             }
+            NUM_FLOAT_ID => {
+                let prec = buffer.read_u32::<LittleEndian>()?;
+                let e = buffer.read_i32::<LittleEndian>()?;
+                let mut f = Float::with_val(prec, deserialize_integer(buffer)?);
+                f <<= e;
+                Number::Float(Box::new(f))
+            }
+            NUM_SPECIAL_FLOAT_ID => {
+                let prec = buffer.read_u32::<LittleEndian>()?;
+                let special = match buffer.read_u8()? {
+                    FLOAT_NAN => Special::Nan,
+                    FLOAT_INFINITY => Special::Infinity,
+                    FLOAT_MINUS_INFINITY => Special::MinusInfinity,
+                    _ => unreachable!(),
+                };
+                Number::Float(Box::new(Float::with_val(prec, special)))
+            }
+            NUM_COMPLEX_ID => {
+                let re = Number::deserialize(buffer)?;
+                let im = Number::deserialize(buffer)?;
+                Number::Complex(Box::new((re, im)))
+            }
             _ => unreachable!(),
         })
     }
@@ -205,12 +266,15 @@ impl Number {
         // TODO: compare bytes?
         let num1 = Number::deserialize(b1).unwrap();
         let num2 = Number::deserialize(b2).unwrap();
-        num1.partial_cmp(&num2).unwrap()
+        // NaN is not ordered
+        num1.cmp(&num2)
     }
 }
 
-impl MultivariatePolynomial<Number, u32> {
+impl<E: Exponent> MultivariatePolynomial<Number, E> {
+    /// Serialize the polynomial. The exponents are written with the size of `E`.
     pub fn serialize(&self, buffer: &mut Write) -> usize {
+        let width = mem::size_of::<E>();
         buffer
             .write_u64::<LittleEndian>(self.nterms as u64)
             .unwrap();
@@ -221,15 +285,16 @@ impl MultivariatePolynomial<Number, u32> {
 
             for e in 0..self.nvars {
                 buffer
-                    .write_u32::<LittleEndian>(self.exponents(t)[e])
+                    .write_uint::<LittleEndian>(self.exponents(t)[e].to_u64().unwrap(), width)
                     .unwrap();
             }
         }
 
-        12 + len + self.nterms * self.nvars * 4
+        12 + len + self.nterms * self.nvars * width
     }
 
-    pub fn deserialize(buffer: &mut Read) -> Result<MultivariatePolynomial<Number, u32>, Error> {
+    pub fn deserialize(buffer: &mut Read) -> Result<MultivariatePolynomial<Number, E>, Error> {
+        let width = mem::size_of::<E>();
         let nterms = buffer.read_u64::<LittleEndian>()? as usize;
         let nvars = buffer.read_u32::<LittleEndian>()? as usize;
 
@@ -240,7 +305,7 @@ impl MultivariatePolynomial<Number, u32> {
             coeffs.push(Number::deserialize(buffer)?);
 
             for _ in 0..nvars {
-                exponents.push(buffer.read_u32::<LittleEndian>()?);
+                exponents.push(E::from_u64(buffer.read_uint::<LittleEndian>(width)?).unwrap());
             }
         }
 
@@ -254,6 +319,29 @@ impl MultivariatePolynomial<Number, u32> {
     }
 }
 
+impl AdaptivePolynomial<Number> {
+    /// Serialize the polynomial, preceded by the number of bits of its exponents.
+    pub fn serialize(&self, buffer: &mut Write) -> usize {
+        buffer.write_u8(self.exponent_bits() as u8).unwrap();
+        1 + match *self {
+            AdaptivePolynomial::U8(ref p) => p.serialize(buffer),
+            AdaptivePolynomial::U16(ref p) => p.serialize(buffer),
+            AdaptivePolynomial::U32(ref p) => p.serialize(buffer),
+            AdaptivePolynomial::U64(ref p) => p.serialize(buffer),
+        }
+    }
+
+    pub fn deserialize(buffer: &mut Read) -> Result<AdaptivePolynomial<Number>, Error> {
+        Ok(match buffer.read_u8()? {
+            8 => AdaptivePolynomial::U8(MultivariatePolynomial::deserialize(buffer)?),
+            16 => AdaptivePolynomial::U16(MultivariatePolynomial::deserialize(buffer)?),
+            32 => AdaptivePolynomial::U32(MultivariatePolynomial::deserialize(buffer)?),
+            64 => AdaptivePolynomial::U64(MultivariatePolynomial::deserialize(buffer)?),
+            _ => unreachable!(),
+        })
+    }
+}
+
 impl Element {
     // convert a normalized Element to a linear representation
     pub fn serialize(&self, buffer: &mut Write) -> usize {
@@ -140,6 +140,11 @@ impl OutputTermStreamer {
             mem::swap(&mut self.mem_buffer, &mut tmp);
             let mut a = Element::SubExpr(true, tmp);
             a.normalize_inplace(var_info);
+            if let Some((x, order)) = var_info.series {
+                if a.truncate_series(x, order) {
+                    a = Element::SubExpr(false, vec![]);
+                }
+            }
 
             match a {
                 Element::SubExpr(_, ref mut x) => mem::swap(&mut self.mem_buffer, x),
@@ -210,6 +215,13 @@ impl OutputTermStreamer {
             mem::swap(&mut self.mem_buffer, &mut tmp);
             let mut a = Element::SubExpr(true, tmp);
             a.normalize_inplace(&var_info.global_info);
+
+            // merged coefficients may contain powers beyond the order of the series
+            if let Some((x, order)) = var_info.global_info.series {
+                if a.truncate_series(x, order) {
+                    a = Element::Num(false, Number::SmallInt(0));
+                }
+            }
             input_streamer.input = None;
 
             // execute the global statements
@@ -355,7 +367,10 @@ impl OutputTermStreamer {
                     mem::swap(self.mem_buffer.last_mut().unwrap(), &mut tmp);
                     match tmp.partial_cmp(&mv, &vi, true) {
                         Some(Ordering::Equal) => {
-                            if merge_terms(&mut tmp, &mut mv, &vi) {
+                            if merge_terms(&mut tmp, &mut mv, &vi) || vi
+                                .series
+                                .map_or(false, |(x, order)| tmp.truncate_series(x, order))
+                            {
                                 self.mem_buffer.pop();
                             } else {
                                 mem::swap(self.mem_buffer.last_mut().unwrap(), &mut tmp);
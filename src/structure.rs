@@ -1,6 +1,6 @@
 use chrono::prelude::*;
-use num_traits::One;
-use number::Number;
+use num_traits::{One, Zero};
+use number::{Number, DEFAULT_FLOAT_PRECISION};
 use poly::polynomial::PolyPrinter;
 use poly::polynomial::Polynomial;
 use std::cmp;
@@ -9,11 +9,14 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Write;
 use std::mem;
+use std::sync::Arc;
 use streaming::InputTermStreamer;
 
 pub const BUILTIN_FUNCTIONS: &'static [&'static str] = &[
     "delta_", "nargs_", "sum_", "prod_", "rat_", "gcd_", "takearg_", "ifelse_", "term_", "list_",
-    "time_", "date_",
+    "time_", "date_", "roots_", "diff_", "der_", "series_", "N_", "eval_", "i_", "conj_", "exp_",
+    "log_", "sin_", "cos_", "sqrt_", "fac_", "binom_", "mod_", "div_", "abs_", "sign_", "min_",
+    "max_", "num_", "den_", "isprime_", "nextprime_", "factorint_", "powmod_", "invmod_",
 ];
 pub const FUNCTION_DELTA: VarName = 0;
 pub const FUNCTION_NARGS: VarName = 1;
@@ -27,6 +30,34 @@ pub const FUNCTION_TERM: VarName = 8;
 pub const FUNCTION_LIST: VarName = 9;
 pub const FUNCTION_TIME: VarName = 10; // used for printing
 pub const FUNCTION_DATE: VarName = 11; // used for printing
+pub const FUNCTION_ROOTS: VarName = 12;
+pub const FUNCTION_DIFF: VarName = 13;
+pub const FUNCTION_DER: VarName = 14;
+pub const FUNCTION_SERIES: VarName = 15;
+pub const FUNCTION_N: VarName = 16;
+pub const FUNCTION_EVAL: VarName = 17;
+pub const SYMBOL_I: VarName = 18; // the imaginary unit
+pub const FUNCTION_CONJ: VarName = 19;
+pub const FUNCTION_EXP: VarName = 20;
+pub const FUNCTION_LOG: VarName = 21;
+pub const FUNCTION_SIN: VarName = 22;
+pub const FUNCTION_COS: VarName = 23;
+pub const FUNCTION_SQRT: VarName = 24;
+pub const FUNCTION_FAC: VarName = 25;
+pub const FUNCTION_BINOM: VarName = 26;
+pub const FUNCTION_MOD: VarName = 27;
+pub const FUNCTION_DIV: VarName = 28;
+pub const FUNCTION_ABS: VarName = 29;
+pub const FUNCTION_SIGN: VarName = 30;
+pub const FUNCTION_MIN: VarName = 31;
+pub const FUNCTION_MAX: VarName = 32;
+pub const FUNCTION_NUM: VarName = 33;
+pub const FUNCTION_DEN: VarName = 34;
+pub const FUNCTION_ISPRIME: VarName = 35;
+pub const FUNCTION_NEXTPRIME: VarName = 36;
+pub const FUNCTION_FACTORINT: VarName = 37;
+pub const FUNCTION_POWMOD: VarName = 38;
+pub const FUNCTION_INVMOD: VarName = 39;
 
 /// Trait for variable ID. Normally `VarName` or `String`.
 pub trait Id: Ord + fmt::Debug {}
@@ -215,6 +246,7 @@ pub enum FunctionAttributes {
     Symmetric,
     Linear,
     NonLocal, // used for persistent dollar variables
+    Real,     // the symbol or function is real under complex conjugation
 }
 
 impl fmt::Display for FunctionAttributes {
@@ -224,6 +256,7 @@ impl fmt::Display for FunctionAttributes {
             FunctionAttributes::Symmetric => write!(f, "Symmetric"),
             FunctionAttributes::Linear => write!(f, "Linear"),
             FunctionAttributes::NonLocal => write!(f, "NonLocal"),
+            FunctionAttributes::Real => write!(f, "Real"),
         }
     }
 }
@@ -235,6 +268,9 @@ pub struct GlobalVarInfo {
     name_map: HashMap<String, VarName>,
     pub func_attribs: HashMap<VarName, Vec<FunctionAttributes>>,
     pub user_functions: HashMap<VarName, (Vec<VarName>, Element)>,
+    pub series: Option<(VarName, isize)>, // truncate all terms beyond this power of the variable
+    pub float_precision: u32,             // the precision in bits of new floats
+    pub algebraic: Option<(VarName, Arc<Vec<Number>>)>, // variable and its minimal polynomial
     pub log_level: usize,
 }
 
@@ -245,6 +281,9 @@ impl GlobalVarInfo {
             name_map: HashMap::new(),
             func_attribs: HashMap::new(),
             user_functions: HashMap::new(),
+            series: None,
+            float_precision: DEFAULT_FLOAT_PRECISION,
+            algebraic: None,
             log_level: 0,
         }
     }
@@ -256,6 +295,11 @@ impl GlobalVarInfo {
     pub fn get_name(&self, var: VarName) -> &str {
         &self.inv_name_map[var as usize]
     }
+
+    /// Get the identifier of a name, if it exists.
+    pub fn find_name(&self, s: &str) -> Option<VarName> {
+        self.name_map.get(s).cloned()
+    }
 }
 
 type DollarVariableTable = HashMap<Vec<Element>, Element>;
@@ -350,6 +394,9 @@ impl VarInfo {
                 name_map,
                 func_attribs: HashMap::new(),
                 user_functions: HashMap::new(),
+                series: None,
+                float_precision: DEFAULT_FLOAT_PRECISION,
+                algebraic: None,
                 log_level: 0,
             },
             local_info: LocalVarInfo {
@@ -575,6 +622,10 @@ pub enum Statement<ID: Id = VarName> {
     Print(PrintMode, Vec<PrintObject<ID>>),
     Multiply(Element<ID>),
     ReplaceBy(Element<ID>),
+    Differentiate(Vec<Element<ID>>), // variable and optional order and derivative function
+    Series(Vec<Element<ID>>),        // variable and order, or empty to disable
+    Precision(Element<ID>),          // number of bits of floats
+    Algebraic(Element<ID>, Element<ID>), // variable and its minimal polynomial
     Symmetrize(ID),
     Collect(ID),
     Extract(Element<ID>, Vec<ID>),
@@ -583,6 +634,7 @@ pub enum Statement<ID: Id = VarName> {
     Maximum(Element<ID>),
     Call(String, Vec<Element<ID>>),
     Attrib(Element<ID>, Vec<FunctionAttributes>),
+    Solve(Vec<Element<ID>>, Vec<Element<ID>>), // equations = 0, unknowns
     Discard,
     // internal commands
     Jump(usize),              // unconditional jump
@@ -650,21 +702,31 @@ impl Element {
             }
             (Element::Var(..), &Element::Num(..)) => Some(cmp::Ordering::Less),
             (Element::Num(..), &Element::Var(..)) => Some(cmp::Ordering::Greater),
+            // numbers of a different kind, such as 0.5 and 1/2, are different
             (&Element::Num(_, ref n1), &Element::Num(_, ref n2)) => if ground_level {
                 Some(cmp::Ordering::Equal)
             } else {
-                n1.partial_cmp(n2)
-            },
-            (&Element::RationalPolynomialCoefficient(..), &Element::Num(..)) => if ground_level {
-                Some(cmp::Ordering::Equal)
-            } else {
-                Some(cmp::Ordering::Less)
-            },
-            (&Element::Num(..), &Element::RationalPolynomialCoefficient(..)) => if ground_level {
-                Some(cmp::Ordering::Equal)
-            } else {
-                Some(cmp::Ordering::Less)
+                Some(n1.cmp(n2))
             },
+            // a float or complex number and a polyratfun are not like terms
+            (&Element::RationalPolynomialCoefficient(..), &Element::Num(_, ref n)) => {
+                if !ground_level {
+                    Some(cmp::Ordering::Less)
+                } else if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Greater)
+                }
+            }
+            (&Element::Num(_, ref n), &Element::RationalPolynomialCoefficient(..)) => {
+                if !ground_level {
+                    Some(cmp::Ordering::Less)
+                } else if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Less)
+                }
+            }
             (_, &Element::Num(..)) => Some(cmp::Ordering::Less),
             (&Element::Num(..), _) => Some(cmp::Ordering::Greater),
             //(&Element::SubExpr(..), &Element::SubExpr(..)) => None,
@@ -700,14 +762,40 @@ impl Element {
         other: &Element,
         var_info: &GlobalVarInfo,
     ) -> Option<cmp::Ordering> {
+        // powers of a variable, function or subexpression are sorted as their base,
+        // so that x^n and x^a, or f(x) and f(x)^(1/2), end up next to each other
+        // and can be merged
+        if let Element::Pow(_, ref be) = *self {
+            if sorts_as_base(&be.0) {
+                return be.0.partial_cmp_factor(other, var_info);
+            }
+        }
+        if let Element::Pow(_, ref be) = *other {
+            if sorts_as_base(&be.0) {
+                return self.partial_cmp_factor(&be.0, var_info);
+            }
+        }
+
         match (self, other) {
             (&Element::Var(ref a, _), &Element::Var(ref b, _)) => a.partial_cmp(b),
-            (&Element::Num(..), &Element::Num(..)) => Some(cmp::Ordering::Equal),
-            (&Element::RationalPolynomialCoefficient(..), &Element::Num(..)) => {
-                Some(cmp::Ordering::Equal)
+            // floats and complex numbers are sorted before the exact coefficient,
+            // since they cannot be merged into a polyratfun
+            (&Element::Num(_, ref n1), &Element::Num(_, ref n2)) => n1
+                .is_polynomial_coefficient()
+                .partial_cmp(&n2.is_polynomial_coefficient()),
+            (&Element::RationalPolynomialCoefficient(..), &Element::Num(_, ref n)) => {
+                if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Greater)
+                }
             }
-            (&Element::Num(..), &Element::RationalPolynomialCoefficient(..)) => {
-                Some(cmp::Ordering::Equal)
+            (&Element::Num(_, ref n), &Element::RationalPolynomialCoefficient(..)) => {
+                if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Less)
+                }
             }
             (_, &Element::Num(..)) => Some(cmp::Ordering::Less),
             (&Element::Num(..), _) => Some(cmp::Ordering::Greater),
@@ -774,6 +862,18 @@ impl Element {
         }
     }
 
+    /// Check if the factor at position `index` of a term with `len` factors is
+    /// its coefficient. At ground level only the last factor can be the
+    /// coefficient: a float or complex number in front of a polyratfun is
+    /// compared by value.
+    #[inline(always)]
+    fn is_coefficient(&self, index: usize, len: usize, ground_level: bool) -> bool {
+        ground_level && index + 1 == len && match *self {
+            Element::Num(..) | Element::RationalPolynomialCoefficient(..) => true,
+            _ => false,
+        }
+    }
+
     #[inline(always)]
     pub fn partial_cmp(
         &self,
@@ -785,36 +885,34 @@ impl Element {
         if let Element::Term(_, ref ta) = self {
             if let Element::Term(_, ref tb) = other {
                 let mut tbi = tb.iter();
-                for taa in ta.iter() {
+                for (i, taa) in ta.iter().enumerate() {
                     if let Some(tbb) = tbi.next() {
-                        let k = taa
-                            .simple_partial_cmp(tbb, var_info, ground_level)
-                            .or_else(|| taa.partial_cmp_full(tbb, var_info, ground_level));
+                        // the coefficients are sorted after all other factors
+                        let k = match (
+                            taa.is_coefficient(i, ta.len(), ground_level),
+                            tbb.is_coefficient(i, tb.len(), ground_level),
+                        ) {
+                            (true, false) => Some(cmp::Ordering::Greater),
+                            (false, true) => Some(cmp::Ordering::Less),
+                            (c, _) => taa
+                                .simple_partial_cmp(tbb, var_info, c)
+                                .or_else(|| taa.partial_cmp_full(tbb, var_info, c)),
+                        };
 
                         match k {
                             Some(cmp::Ordering::Equal) => {}
                             _ => return k,
                         }
                     } else {
-                        if ground_level {
-                            match taa {
-                                Element::Num(..) | Element::RationalPolynomialCoefficient(..) => {
-                                    return Some(cmp::Ordering::Equal);
-                                }
-                                _ => {}
-                            }
+                        if taa.is_coefficient(i, ta.len(), ground_level) {
+                            return Some(cmp::Ordering::Equal);
                         }
                         return Some(cmp::Ordering::Greater);
                     };
                 }
                 if let Some(tbb) = tbi.next() {
-                    if ground_level {
-                        match tbb {
-                            Element::Num(..) | Element::RationalPolynomialCoefficient(..) => {
-                                return Some(cmp::Ordering::Equal);
-                            }
-                            _ => {}
-                        }
+                    if tbb.is_coefficient(ta.len(), tb.len(), ground_level) {
+                        return Some(cmp::Ordering::Equal);
                     }
                     return Some(cmp::Ordering::Less);
                 };
@@ -850,36 +948,34 @@ impl Element {
                 // not be optimal? The for loop avoids this for one iterator.
                 //
                 let mut tbi = tb.iter();
-                for taa in ta.iter() {
+                for (i, taa) in ta.iter().enumerate() {
                     if let Some(tbb) = tbi.next() {
-                        let k = taa
-                            .simple_partial_cmp(tbb, var_info, ground_level)
-                            .or_else(|| taa.partial_cmp(tbb, var_info, ground_level));
+                        // the coefficients are sorted after all other factors
+                        let k = match (
+                            taa.is_coefficient(i, ta.len(), ground_level),
+                            tbb.is_coefficient(i, tb.len(), ground_level),
+                        ) {
+                            (true, false) => Some(cmp::Ordering::Greater),
+                            (false, true) => Some(cmp::Ordering::Less),
+                            (c, _) => taa
+                                .simple_partial_cmp(tbb, var_info, c)
+                                .or_else(|| taa.partial_cmp(tbb, var_info, c)),
+                        };
 
                         match k {
                             Some(cmp::Ordering::Equal) => {}
                             _ => return k,
                         }
                     } else {
-                        if ground_level {
-                            match taa {
-                                Element::Num(..) | Element::RationalPolynomialCoefficient(..) => {
-                                    return Some(cmp::Ordering::Equal);
-                                }
-                                _ => {}
-                            }
+                        if taa.is_coefficient(i, ta.len(), ground_level) {
+                            return Some(cmp::Ordering::Equal);
                         }
                         return Some(cmp::Ordering::Greater);
                     };
                 }
                 if let Some(tbb) = tbi.next() {
-                    if ground_level {
-                        match tbb {
-                            Element::Num(..) | Element::RationalPolynomialCoefficient(..) => {
-                                return Some(cmp::Ordering::Equal);
-                            }
-                            _ => {}
-                        }
+                    if tbb.is_coefficient(ta.len(), tb.len(), ground_level) {
+                        return Some(cmp::Ordering::Equal);
                     }
                     return Some(cmp::Ordering::Less);
                 };
@@ -930,11 +1026,21 @@ impl Element {
                 }
 */
             }
+            // numbers of a different kind, such as 0.5 and 1/2, are different
             (&Element::Num(_, ref n1), &Element::Num(_, ref n2)) => if ground_level {
                 Some(cmp::Ordering::Equal)
             } else {
-                n1.partial_cmp(n2)
+                Some(n1.cmp(n2))
             },
+            // a lone coefficient is sorted after a term, which may start with a number
+            (&Element::Num(..), &Element::Term(..))
+            | (&Element::RationalPolynomialCoefficient(..), &Element::Term(..)) => {
+                Some(cmp::Ordering::Greater)
+            }
+            (&Element::Term(..), &Element::Num(..))
+            | (&Element::Term(..), &Element::RationalPolynomialCoefficient(..)) => {
+                Some(cmp::Ordering::Less)
+            }
             (_, &Element::Term(_, ref t)) => match self
                 .simple_partial_cmp(&t[0], var_info, false)
                 .or_else(|| self.partial_cmp(&t[0], var_info, false))
@@ -971,16 +1077,25 @@ impl Element {
                 }
                 x => x,
             },
-            (&Element::RationalPolynomialCoefficient(..), &Element::Num(..)) => if ground_level {
-                Some(cmp::Ordering::Equal)
-            } else {
-                Some(cmp::Ordering::Less)
-            },
-            (&Element::Num(..), &Element::RationalPolynomialCoefficient(..)) => if ground_level {
-                Some(cmp::Ordering::Equal)
-            } else {
-                Some(cmp::Ordering::Less)
-            },
+            // a float or complex number and a polyratfun are not like terms
+            (&Element::RationalPolynomialCoefficient(..), &Element::Num(_, ref n)) => {
+                if !ground_level {
+                    Some(cmp::Ordering::Less)
+                } else if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Greater)
+                }
+            }
+            (&Element::Num(_, ref n), &Element::RationalPolynomialCoefficient(..)) => {
+                if !ground_level {
+                    Some(cmp::Ordering::Less)
+                } else if n.is_polynomial_coefficient() {
+                    Some(cmp::Ordering::Equal)
+                } else {
+                    Some(cmp::Ordering::Less)
+                }
+            }
             (&Element::Fn(_, ref namea, ref argsa), &Element::Fn(_, ref nameb, ref argsb)) => {
                 let k = namea.partial_cmp(nameb);
                 match k {
@@ -1145,6 +1260,7 @@ impl fmt::Display for Statement {
                 match mode {
                     PrintMode::Form => {} // default
                     PrintMode::Mathematica => write!(f, " Mathematica")?,
+                    PrintMode::Latex => write!(f, " Latex")?,
                 }
 
                 for x in po {
@@ -1154,6 +1270,8 @@ impl fmt::Display for Statement {
             }
             Statement::Multiply(ref x) => writeln!(f, "Multiply {};", x),
             Statement::ReplaceBy(ref x) => writeln!(f, "ReplaceBy {};", x),
+            Statement::Precision(ref x) => writeln!(f, "Precision {};", x),
+            Statement::Algebraic(ref x, ref m) => writeln!(f, "Algebraic {}={};", x, m),
             Statement::Symmetrize(ref x) => writeln!(f, "Symmetrize {};", x),
             Statement::Collect(ref x) => writeln!(f, "Collect {};", x),
             Statement::Extract(ref d, ref xs) => {
@@ -1276,6 +1394,45 @@ impl fmt::Display for Statement {
                 }
                 writeln!(f, ";")
             }
+            Statement::Solve(ref eqs, ref xs) => {
+                write!(f, "Solve ")?;
+                for (i, e) in eqs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                write!(f, " for ")?;
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                writeln!(f, ";")
+            }
+            Statement::Differentiate(ref args) => {
+                write!(f, "Differentiate ")?;
+                for (i, x) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                writeln!(f, ";")
+            }
+            Statement::Series(ref args) => {
+                write!(f, "Series")?;
+                for (i, x) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    } else {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                writeln!(f, ";")
+            }
             Statement::Discard => writeln!(f, "Discard;"),
             Statement::Maximum(ref d) => writeln!(f, "Maximum {};", d),
             Statement::Jump(ref i) => writeln!(f, "JMP {}", i),
@@ -1286,6 +1443,15 @@ impl fmt::Display for Statement {
     }
 }
 
+/// Returns true if a power with base `b` is sorted as `b` among the factors of a term.
+pub fn sorts_as_base(b: &Element) -> bool {
+    match *b {
+        Element::Var(_, ref e) => e.is_one(),
+        Element::Fn(..) | Element::SubExpr(..) => true,
+        _ => false,
+    }
+}
+
 pub fn fmt_varname(v: &VarName, f: &mut fmt::Formatter, var_info: &GlobalVarInfo) -> fmt::Result {
     if var_info.inv_name_map.len() == 0 {
         write!(f, "var_{}", v)
@@ -1294,6 +1460,43 @@ pub fn fmt_varname(v: &VarName, f: &mut fmt::Formatter, var_info: &GlobalVarInfo
     }
 }
 
+/// Returns the name of a built-in function in Mathematica or LaTeX,
+/// if it differs from the reFORM name.
+pub fn builtin_print_name(name: VarName, print_mode: PrintMode) -> Option<&'static str> {
+    match (print_mode, name) {
+        (PrintMode::Mathematica, FUNCTION_EXP) => Some("Exp"),
+        (PrintMode::Mathematica, FUNCTION_LOG) => Some("Log"),
+        (PrintMode::Mathematica, FUNCTION_SIN) => Some("Sin"),
+        (PrintMode::Mathematica, FUNCTION_COS) => Some("Cos"),
+        (PrintMode::Mathematica, FUNCTION_SQRT) => Some("Sqrt"),
+        (PrintMode::Mathematica, FUNCTION_CONJ) => Some("Conjugate"),
+        (PrintMode::Mathematica, FUNCTION_FAC) => Some("Factorial"),
+        (PrintMode::Mathematica, FUNCTION_BINOM) => Some("Binomial"),
+        (PrintMode::Mathematica, FUNCTION_MOD) => Some("Mod"),
+        (PrintMode::Mathematica, FUNCTION_DIV) => Some("Quotient"),
+        (PrintMode::Mathematica, FUNCTION_ABS) => Some("Abs"),
+        (PrintMode::Mathematica, FUNCTION_SIGN) => Some("Sign"),
+        (PrintMode::Mathematica, FUNCTION_MIN) => Some("Min"),
+        (PrintMode::Mathematica, FUNCTION_MAX) => Some("Max"),
+        (PrintMode::Mathematica, FUNCTION_NUM) => Some("Numerator"),
+        (PrintMode::Mathematica, FUNCTION_DEN) => Some("Denominator"),
+        (PrintMode::Mathematica, FUNCTION_ISPRIME) => Some("PrimeQ"),
+        (PrintMode::Mathematica, FUNCTION_NEXTPRIME) => Some("NextPrime"),
+        (PrintMode::Mathematica, FUNCTION_FACTORINT) => Some("FactorInteger"),
+        (PrintMode::Mathematica, FUNCTION_POWMOD) => Some("PowerMod"),
+        (PrintMode::Mathematica, FUNCTION_INVMOD) => Some("ModularInverse"),
+        (PrintMode::Latex, FUNCTION_EXP) => Some("\\exp"),
+        (PrintMode::Latex, FUNCTION_LOG) => Some("\\log"),
+        (PrintMode::Latex, FUNCTION_SIN) => Some("\\sin"),
+        (PrintMode::Latex, FUNCTION_COS) => Some("\\cos"),
+        (PrintMode::Latex, FUNCTION_SQRT) => Some("\\sqrt"),
+        (PrintMode::Latex, FUNCTION_CONJ) => Some("\\overline"),
+        (PrintMode::Latex, FUNCTION_MIN) => Some("\\min"),
+        (PrintMode::Latex, FUNCTION_MAX) => Some("\\max"),
+        _ => None,
+    }
+}
+
 impl fmt::Display for Ordering {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -1326,6 +1529,7 @@ impl fmt::Display for IdentityStatementMode {
 pub enum PrintMode {
     Form,
     Mathematica,
+    Latex,
 }
 
 pub struct ElementPrinter<'a> {
@@ -1368,11 +1572,20 @@ impl Element {
                 write!(f, "}}")
             },
             &Element::Var(ref name, ref e) => {
-                if !e.is_one() {
-                    fmt_varname(name, f, var_info)?;
-                    write!(f, "^{}", e)
-                } else {
-                    fmt_varname(name, f, var_info)
+                match (print_mode, *name) {
+                    (PrintMode::Mathematica, SYMBOL_I) => write!(f, "I")?,
+                    (PrintMode::Latex, SYMBOL_I) => write!(f, "i")?,
+                    _ => fmt_varname(name, f, var_info)?,
+                }
+                match (print_mode, e) {
+                    _ if e.is_one() => Ok(()),
+                    (PrintMode::Latex, _) => {
+                        write!(f, "^{{")?;
+                        Element::Num(false, e.clone()).fmt_output(f, print_mode, var_info)?;
+                        write!(f, "}}")
+                    }
+                    (_, &Number::SmallRat(..)) | (_, &Number::BigRat(..)) => write!(f, "^({})", e),
+                    _ => write!(f, "^{}", e),
                 }
             }
             &Element::Dollar(ref name, ref inds) => {
@@ -1389,21 +1602,56 @@ impl Element {
                 }
                 write!(f, "")
             }
-            &Element::Num(_, ref n) => write!(f, "{}", n),
+            &Element::Num(_, ref n) => match print_mode {
+                PrintMode::Form => write!(f, "{}", n),
+                PrintMode::Mathematica => n.fmt_complex(f, "I"),
+                PrintMode::Latex => match *n {
+                    Number::SmallRat(..) | Number::BigRat(..) => {
+                        let s = n.to_string();
+                        let (num, den) = s.split_at(s.find('/').unwrap());
+                        if num.starts_with('-') {
+                            write!(f, "-\\frac{{{}}}{{{}}}", &num[1..], &den[1..])
+                        } else {
+                            write!(f, "\\frac{{{}}}{{{}}}", num, &den[1..])
+                        }
+                    }
+                    _ => n.fmt_complex(f, "i"),
+                },
+            },
             &Element::NumberRange(ref num, ref rel) => write!(f, "{}{}", num, rel),
             &Element::Comparison(_, ref b, ref rel) => write!(f, "{} {} {}", b.0, rel, b.1),
             &Element::Pow(_, ref be) => {
                 let (ref b, ref e) = **be;
-                match *b {
-                    Element::SubExpr(..) | Element::Term(..) => {
-                        write!(f, "(")?;
-                        b.fmt_output(f, print_mode, var_info)?;
-                        write!(f, ")")?
-                    }
-                    _ => b.fmt_output(f, print_mode, var_info)?,
+                let (open, close) = match print_mode {
+                    PrintMode::Latex => ("\\left(", "\\right)"),
+                    _ => ("(", ")"),
+                };
+                let paren = match *b {
+                    Element::SubExpr(..) | Element::Term(..) => true,
+                    Element::Num(_, Number::SmallRat(..))
+                    | Element::Num(_, Number::BigRat(..)) => true,
+                    Element::Num(_, Number::Complex(..)) => false,
+                    Element::Num(_, ref n) => *n < Number::zero(),
+                    Element::Var(_, ref n) => !n.is_one(),
+                    _ => false,
                 };
-                match *e {
-                    Element::SubExpr(..) | Element::Term(..) => {
+                if paren {
+                    write!(f, "{}", open)?;
+                    b.fmt_output(f, print_mode, var_info)?;
+                    write!(f, "{}", close)?
+                } else {
+                    b.fmt_output(f, print_mode, var_info)?
+                }
+                match (print_mode, e) {
+                    (PrintMode::Latex, _) => {
+                        write!(f, "^{{")?;
+                        e.fmt_output(f, print_mode, var_info)?;
+                        write!(f, "}}")
+                    }
+                    (_, &Element::SubExpr(..))
+                    | (_, &Element::Term(..))
+                    | (_, &Element::Num(_, Number::SmallRat(..)))
+                    | (_, &Element::Num(_, Number::BigRat(..))) => {
                         write!(f, "^(")?;
                         e.fmt_output(f, print_mode, var_info)?;
                         write!(f, ")")
@@ -1415,13 +1663,19 @@ impl Element {
                 }
             }
             &Element::Fn(_, ref name, ref args) => {
-                fmt_varname(&name, f, var_info)?;
-
-                match print_mode {
-                    PrintMode::Form => write!(f, "(")?,
-                    PrintMode::Mathematica => write!(f, "[")?,
+                match builtin_print_name(*name, print_mode) {
+                    Some(s) => write!(f, "{}", s)?,
+                    None => fmt_varname(&name, f, var_info)?,
                 }
 
+                let open = match (print_mode, *name) {
+                    (PrintMode::Form, _) => "(",
+                    (PrintMode::Mathematica, _) => "[",
+                    (PrintMode::Latex, FUNCTION_SQRT) | (PrintMode::Latex, FUNCTION_CONJ) => "{",
+                    (PrintMode::Latex, _) => "\\left(",
+                };
+                write!(f, "{}", open)?;
+
                 match args.first() {
                     Some(x) => x.fmt_output(f, print_mode, var_info)?,
                     None => {}
@@ -1432,9 +1686,13 @@ impl Element {
                     x.fmt_output(f, print_mode, var_info)?;
                 }
 
-                match print_mode {
-                    PrintMode::Form => write!(f, ")"),
-                    PrintMode::Mathematica => write!(f, "]"),
+                match (print_mode, *name) {
+                    (PrintMode::Form, _) => write!(f, ")"),
+                    (PrintMode::Mathematica, _) => write!(f, "]"),
+                    (PrintMode::Latex, FUNCTION_SQRT) | (PrintMode::Latex, FUNCTION_CONJ) => {
+                        write!(f, "}}")
+                    }
+                    (PrintMode::Latex, _) => write!(f, "\\right)"),
                 }
             }
             &Element::FnWildcard(ref name, ref b) => {
@@ -1456,7 +1714,7 @@ impl Element {
                 }
 
                 match print_mode {
-                    PrintMode::Form => write!(f, "(")?,
+                    PrintMode::Form | PrintMode::Latex => write!(f, "(")?,
                     PrintMode::Mathematica => write!(f, "[")?,
                 }
 
@@ -1471,7 +1729,7 @@ impl Element {
                 }
 
                 match print_mode {
-                    PrintMode::Form => write!(f, ")"),
+                    PrintMode::Form | PrintMode::Latex => write!(f, ")"),
                     PrintMode::Mathematica => write!(f, "]"),
                 }
             }
@@ -1491,14 +1749,16 @@ impl Element {
                             Some(x) => x.fmt_output(f, print_mode, var_info)?,
                             None => {}
                         }
-                        for t in factors.iter().skip(1) {
+                        // only the last number is the coefficient: a float or complex
+                        // number in front of a polyratfun is printed as a factor
+                        for (i, t) in factors.iter().enumerate().skip(1) {
                             match t {
                                 s @ &Element::SubExpr(..) => {
                                     write!(f, "*(")?;
                                     s.fmt_output(f, print_mode, var_info)?;
                                     write!(f, ")")?
                                 }
-                                Element::Num(..) => {}
+                                Element::Num(..) if i + 1 == factors.len() => {}
                                 _ => {
                                     write!(f, "*")?;
                                     t.fmt_output(f, print_mode, var_info)?
@@ -1506,19 +1766,23 @@ impl Element {
                             }
                         }
                     }
-                    PrintMode::Mathematica => {
+                    PrintMode::Mathematica | PrintMode::Latex => {
                         if let Some(n @ Element::Num(..)) = factors.last() {
                             n.fmt_output(f, print_mode, var_info)?
                         }
 
-                        for t in factors.iter() {
+                        let (open, close) = match print_mode {
+                            PrintMode::Latex => ("\\left(", "\\right)"),
+                            _ => ("(", ")"),
+                        };
+                        for (i, t) in factors.iter().enumerate() {
                             match t {
                                 s @ &Element::SubExpr(..) => {
-                                    write!(f, "(")?;
+                                    write!(f, "{}", open)?;
                                     s.fmt_output(f, print_mode, var_info)?;
-                                    write!(f, ")")?
+                                    write!(f, "{}", close)?
                                 }
-                                &Element::Num(..) => {}
+                                &Element::Num(..) if i + 1 == factors.len() => {}
                                 _ => {
                                     write!(f, " ")?;
                                     t.fmt_output(f, print_mode, var_info)?
@@ -1565,6 +1829,18 @@ impl Element {
                         var_info: var_info
                     }
                 ),
+                PrintMode::Latex => write!(
+                    f,
+                    "\\frac{{{}}}{{{}}}",
+                    PolyPrinter {
+                        poly: &p.0,
+                        var_info: var_info
+                    },
+                    PolyPrinter {
+                        poly: &p.1,
+                        var_info: var_info
+                    }
+                ),
             },
         }
     }
@@ -1968,6 +2244,16 @@ impl Statement<String> {
             ),
             Statement::Multiply(ref mut e) => Statement::Multiply(e.to_element(var_info)),
             Statement::ReplaceBy(ref mut e) => Statement::ReplaceBy(e.to_element(var_info)),
+            Statement::Precision(ref mut e) => Statement::Precision(e.to_element(var_info)),
+            Statement::Algebraic(ref mut x, ref mut m) => {
+                Statement::Algebraic(x.to_element(var_info), m.to_element(var_info))
+            }
+            Statement::Differentiate(ref mut es) => {
+                Statement::Differentiate(es.iter_mut().map(|e| e.to_element(var_info)).collect())
+            }
+            Statement::Series(ref mut es) => {
+                Statement::Series(es.iter_mut().map(|e| e.to_element(var_info)).collect())
+            }
             Statement::Expand => Statement::Expand,
             Statement::Print(ref mode, ref mut pos) => Statement::Print(
                 mode.clone(),
@@ -1984,6 +2270,10 @@ impl Statement<String> {
             Statement::Attrib(ref mut f, ref mut l) => {
                 Statement::Attrib(f.to_element(var_info), mem::replace(l, vec![]))
             }
+            Statement::Solve(ref mut eqs, ref mut xs) => Statement::Solve(
+                eqs.iter_mut().map(|e| e.to_element(var_info)).collect(),
+                xs.iter_mut().map(|x| x.to_element(var_info)).collect(),
+            ),
             Statement::Jump(x) => Statement::Jump(x),
             Statement::Eval(ref c, ref i) => Statement::Eval(c.clone(), *i),
             Statement::JumpIfChanged(i) => Statement::JumpIfChanged(i),
@@ -2035,8 +2325,13 @@ impl Statement {
                 }
                 false
             }
-            Statement::Multiply(ref e) | Statement::ReplaceBy(ref e) => e.contains_dollar(),
-            Statement::Call(_, ref es) => {
+            Statement::Multiply(ref e)
+            | Statement::ReplaceBy(ref e)
+            | Statement::Precision(ref e)
+            | Statement::Algebraic(_, ref e) => e.contains_dollar(),
+            Statement::Call(_, ref es)
+            | Statement::Differentiate(ref es)
+            | Statement::Series(ref es) => {
                 for s in es {
                     if s.contains_dollar() {
                         return true;
@@ -2092,6 +2387,9 @@ impl Statement {
                 false
             }
             Statement::Eval(ref c, _) => c.contains_dollar(),
+            Statement::Solve(ref eqs, ref xs) => {
+                eqs.iter().any(|e| e.contains_dollar()) || xs.iter().any(|x| x.contains_dollar())
+            }
             _ => false,
         }
     }
@@ -2138,10 +2436,15 @@ impl Statement {
                     changed |= s.replace_dollar(map);
                 }
             }
-            Statement::Multiply(ref mut e) | Statement::ReplaceBy(ref mut e) => {
+            Statement::Multiply(ref mut e)
+            | Statement::ReplaceBy(ref mut e)
+            | Statement::Precision(ref mut e)
+            | Statement::Algebraic(_, ref mut e) => {
                 changed |= e.replace_dollar(map);
             }
-            Statement::Call(_, ref mut es) => for s in es {
+            Statement::Call(_, ref mut es)
+            | Statement::Differentiate(ref mut es)
+            | Statement::Series(ref mut es) => for s in es {
                 changed |= s.replace_dollar(map);
             },
             Statement::Assign(ref mut d, ref mut e) => {
@@ -2165,6 +2468,11 @@ impl Statement {
                     changed |= s.replace_dollar(map);
                 }
             }
+            Statement::Solve(_, ref mut xs) => {
+                for x in xs {
+                    changed |= x.replace_dollar(map);
+                }
+            }
             Statement::ForIn(_, ref mut l, ref mut ss) => {
                 for e in l {
                     changed |= e.replace_dollar(map);
@@ -2207,6 +2515,11 @@ impl Statement {
                 changed |= lhs.replace_elements(map);
                 changed |= rhs.replace_elements(map);
             }
+            Statement::Solve(ref mut eqs, ref mut xs) => {
+                for e in eqs.iter_mut().chain(xs.iter_mut()) {
+                    changed |= e.replace_elements(map);
+                }
+            }
             Statement::Repeat(ref mut ss) => for s in ss {
                 changed |= s.replace_elements(map);
             },
@@ -2247,10 +2560,15 @@ impl Statement {
                     }
                 }
             }
-            Statement::Multiply(ref mut e) | Statement::ReplaceBy(ref mut e) => {
+            Statement::Multiply(ref mut e)
+            | Statement::ReplaceBy(ref mut e)
+            | Statement::Precision(ref mut e)
+            | Statement::Algebraic(_, ref mut e) => {
                 changed |= e.replace_elements(map);
             }
-            Statement::Call(_, ref mut es) => for s in es {
+            Statement::Call(_, ref mut es)
+            | Statement::Differentiate(ref mut es)
+            | Statement::Series(ref mut es) => for s in es {
                 changed |= s.replace_elements(map);
             },
             Statement::Assign(ref mut d, ref mut e) => {
@@ -2323,7 +2641,10 @@ impl Statement {
             Statement::Eval(ref mut e, _) => {
                 e.normalize_inplace(var_info);
             }
-            Statement::Multiply(ref mut e) | Statement::ReplaceBy(ref mut e) => {
+            Statement::Multiply(ref mut e)
+            | Statement::ReplaceBy(ref mut e)
+            | Statement::Precision(ref mut e)
+            | Statement::Algebraic(_, ref mut e) => {
                 e.normalize_inplace(var_info);
             }
             Statement::Assign(ref mut d, ref mut e) => {
@@ -2341,7 +2662,9 @@ impl Statement {
             Statement::Inside(_, ref mut ss) => for s in ss {
                 s.normalize(var_info);
             },
-            Statement::Call(_, ref mut ss) => for s in ss {
+            Statement::Call(_, ref mut ss)
+            | Statement::Differentiate(ref mut ss)
+            | Statement::Series(ref mut ss) => for s in ss {
                 s.normalize_inplace(var_info);
             },
             Statement::ForIn(ref mut d, ref mut l, ref mut ss) => {
@@ -2371,6 +2694,9 @@ impl Statement {
             | Statement::NewFunction(_, _, ref mut rhs) => {
                 rhs.normalize_inplace(var_info);
             }
+            Statement::Solve(_, ref mut xs) => for x in xs {
+                x.normalize_inplace(var_info);
+            },
             _ => {}
         }
     }
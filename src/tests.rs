@@ -4,12 +4,22 @@ mod tests {
     use number::Number;
     use number::Number::*;
     use parser;
+    use poly::raw::adaptive::AdaptivePolynomial;
+    use poly::raw::algebraicnumber::AlgebraicNumber;
     use poly::raw::finitefield::FiniteField;
+    use poly::raw::galoisfield::GaloisField;
+    use poly::raw::gcd::LARGE_U32_PRIMES;
+    use poly::raw::laurent::LaurentPolynomial;
+    use poly::raw::order::{GrevLexOrder, LexOrder, WeightOrder};
+    use poly::raw::rational_solve;
+    use poly::raw::reconstruct;
+    use poly::raw::zp;
     use poly::raw::MultivariatePolynomial;
-    use rug::{Integer, Rational};
+    use rug::{self, Integer, Rational};
     use std::cmp::Ordering;
     use std::io::Cursor;
-    use structure::Element;
+    use std::sync::Arc;
+    use structure::{Element, Program};
     use tools;
 
     #[test]
@@ -67,6 +77,173 @@ mod tests {
         assert_eq!(a.divmod(&b), (q, r));
     }
 
+    #[test]
+    fn monomial_order() {
+        // x^2+x*y^2+y in grevlex: y < x^2 < x*y^2
+        let mut a = MultivariatePolynomial::from_monomial(1, vec![2, 0]);
+        a.append_monomial(1, &[1, 2]);
+        a.append_monomial(1, &[0, 1]);
+
+        let b = a.to_order(GrevLexOrder);
+        b.check_consistency();
+        assert_eq!(b.exponents(0), &[0, 1]);
+        assert_eq!(b.last_exponents(), &[1, 2]);
+
+        let (q, r) = (b.clone() * &b).divmod(&b);
+        assert_eq!(q, b);
+        assert_eq!(r.nterms(), 0);
+
+        assert_eq!(b.to_order(LexOrder), a);
+    }
+
+    #[test]
+    #[should_panic(expected = "monomial orders mismatched")]
+    fn monomial_order_mismatch() {
+        // the same terms sorted by two different weight orders cannot be merged
+        let a = MultivariatePolynomial::from_monomial(1, vec![1, 0])
+            .to_order(WeightOrder::new(vec![1, 2]));
+        let b = MultivariatePolynomial::from_monomial(1, vec![0, 1])
+            .to_order(WeightOrder::new(vec![2, 1]));
+        let _ = a + b;
+    }
+
+    #[test]
+    fn heap_mul() {
+        // (x+y+1)*(x-y) = x^2+x-y^2-y
+        let mut a = MultivariatePolynomial::from_monomial(1, vec![1, 0]);
+        a.append_monomial(1, &[0, 1]);
+        a.append_monomial(1, &[0, 0]);
+
+        let mut b = MultivariatePolynomial::from_monomial(1, vec![1, 0]);
+        b.append_monomial(-1, &[0, 1]);
+
+        let mut res = MultivariatePolynomial::from_monomial(1, vec![2, 0]);
+        res.append_monomial(1, &[1, 0]);
+        res.append_monomial(-1, &[0, 2]);
+        res.append_monomial(-1, &[0, 1]);
+
+        assert_eq!(a.heap_mul(&b), res);
+        assert_eq!(
+            a.to_order(GrevLexOrder).heap_mul(&b.to_order(GrevLexOrder)),
+            res.to_order(GrevLexOrder)
+        );
+
+        let a2 = a.clone() * &a;
+        assert_eq!(a2.mul_parallel(&a2, 3), a2.heap_mul(&a2));
+    }
+
+    #[test]
+    fn exponent_widening() {
+        // x^200 * x^100 does not fit in u8 exponents
+        let a = AdaptivePolynomial::new(MultivariatePolynomial::from_monomial(1, vec![200, 0]));
+        let b = AdaptivePolynomial::new(MultivariatePolynomial::from_monomial(1, vec![100, 0]));
+        assert_eq!(a.exponent_bits(), 8);
+
+        let r = a * b;
+        assert_eq!(r.exponent_bits(), 16);
+        assert_eq!(
+            r.to_poly(),
+            Some(MultivariatePolynomial::from_monomial(1, vec![300, 0]))
+        );
+
+        // x^(2^31) * x^(2^31) does not fit in u32 exponents
+        let a = MultivariatePolynomial::from_monomial(1, vec![1u32 << 31]);
+        let a = AdaptivePolynomial::new(a);
+        assert_eq!(a.exponent_bits(), 32);
+
+        let r = a.clone() * a;
+        assert_eq!(r.exponent_bits(), 64);
+        assert_eq!(r.to_poly(), None);
+        assert_eq!(
+            r.to_wide(),
+            MultivariatePolynomial::from_monomial(1, vec![1u64 << 32])
+        );
+
+        let mut c = MultivariatePolynomial::from_monomial(1, vec![1, 2]);
+        c.grow_to(3);
+        assert_eq!(c.nvars, 4);
+        assert_eq!(
+            c,
+            MultivariatePolynomial::from_monomial(1, vec![1, 2, 0, 0])
+        );
+    }
+
+    #[test]
+    fn rat_large_exponents() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = rat_(x^3000000000+1,1)*rat_(x^3000000000,1);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "rat_(x^3000000000+x^6000000000,1)");
+    }
+
+    #[test]
+    fn rat_algebraic() {
+        let mut program = parser::parse_string(
+            r#"
+            algebraic a = a^2-3;
+            expr F = rat_(a^2,1) - 3;
+            expr G = rat_(1,1+a) - rat_(a-1,2);
+            expr H = rat_(x^2-3,x-a) - rat_(x+a,1);
+            expr K = rat_(a^3,1);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "0");
+        assert_eq!(program.get_result("H"), "0");
+        assert_eq!(program.get_result("K"), "rat_(3*a,1)");
+    }
+
+    #[test]
+    fn laurent() {
+        // a = 1/x + y = (1 + x*y)/x
+        let mut p = MultivariatePolynomial::from_monomial(SmallInt(1), vec![0, 0]);
+        p.append_monomial(SmallInt(1), &[1, 1]);
+        let a = LaurentPolynomial::new(p.clone(), vec![1, 0]);
+        let x = LaurentPolynomial::from_poly(MultivariatePolynomial::from_monomial(
+            SmallInt(1),
+            vec![1, 0],
+        ));
+
+        assert_eq!(
+            a.clone() * x.clone(),
+            LaurentPolynomial::from_poly(p.clone())
+        );
+
+        // the monomial content is cancelled against the offset: x^2/x = x
+        let b = LaurentPolynomial::new(
+            MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]),
+            vec![1, 0],
+        );
+        assert_eq!(b, x);
+
+        // (1/x + y) - y = 1/x
+        let y = LaurentPolynomial::from_poly(MultivariatePolynomial::from_monomial(
+            SmallInt(1),
+            vec![0, 1],
+        ));
+        assert_eq!(
+            a.clone() - y,
+            LaurentPolynomial::new(
+                MultivariatePolynomial::from_monomial(SmallInt(1), vec![0, 0]),
+                vec![1, 0]
+            )
+        );
+
+        // monomials are units: a / x = (1 + x*y)/x^2
+        let (q, r) = a.divmod(&x);
+        assert_eq!(q, LaurentPolynomial::new(p.clone(), vec![2, 0]));
+        assert!(r.poly.is_zero());
+
+        assert_eq!(
+            LaurentPolynomial::gcd(&a, &x).poly,
+            MultivariatePolynomial::from_monomial(SmallInt(1), vec![0, 0])
+        );
+    }
+
     #[test]
     fn univariate_gcd() {
         // gcd(x^3-2x^2-4,x-3) = 1
@@ -126,6 +303,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn substitute() {
+        // x^2+x*y ; x -> y, y -> x+1
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]);
+        a.append_monomial(SmallInt(1), &[1, 1]);
+
+        let y = MultivariatePolynomial::from_monomial(SmallInt(1), vec![0, 1]);
+        let mut x1 = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1, 0]);
+        x1.append_monomial(SmallInt(1), &[0, 0]);
+
+        // y^2+x*y+y
+        let mut res = MultivariatePolynomial::from_monomial(SmallInt(1), vec![0, 2]);
+        res.append_monomial(SmallInt(1), &[1, 1]);
+        res.append_monomial(SmallInt(1), &[0, 1]);
+
+        let b = a.substitute_all(&[(0, y), (1, x1)]);
+        assert_eq!(b, res);
+        assert_eq!(b.evaluate(&[SmallInt(2), SmallInt(3)]), SmallInt(18));
+    }
+
+    #[test]
+    fn derivative() {
+        // x^2+3*x*y^2
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]);
+        a.append_monomial(SmallInt(3), &[1, 2]);
+
+        // 2*x+3*y^2
+        let mut da = MultivariatePolynomial::from_monomial(SmallInt(2), vec![1, 0]);
+        da.append_monomial(SmallInt(3), &[0, 2]);
+        assert_eq!(a.derivative(0), da);
+        assert_eq!(da.integrate(0), a);
+
+        // x^2*y+x*y^3
+        let mut ia = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 1]);
+        ia.append_monomial(SmallInt(1), &[1, 3]);
+        assert_eq!(a.integrate(1), ia);
+        assert_eq!(ia.derivative(1), a);
+
+        // 1/3*x^3
+        let x2 = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]);
+        let x3 = MultivariatePolynomial::from_monomial(SmallRat(1, 3), vec![3, 0]);
+        assert_eq!(x2.integrate(0), x3);
+
+        // the exponent 2^32+1 does not fit in a `ufield`
+        let x = MultivariatePolynomial::from_monomial(SmallInt(1), vec![(1u64 << 32) + 1]);
+        let dx = MultivariatePolynomial::from_monomial(SmallInt((1 << 32) + 1), vec![1u64 << 32]);
+        assert_eq!(x.derivative(0), dx);
+        assert_eq!(dx.integrate(0), x);
+    }
+
+    #[test]
+    fn roots() {
+        // x^4-x^3-x^2+x = x*(x+1)*(x-1)^2
+        let mut a = MultivariatePolynomial::<Number, u32>::from_monomial(SmallInt(1), vec![4]);
+        a.append_monomial(SmallInt(-1), &[3]);
+        a.append_monomial(SmallInt(-1), &[2]);
+        a.append_monomial(SmallInt(1), &[1]);
+
+        assert_eq!(
+            a.real_roots(None),
+            Some(vec![
+                (SmallInt(-2), SmallRat(-1, 2)),
+                (SmallInt(0), SmallInt(0)),
+                (SmallRat(1, 2), SmallInt(2)),
+            ])
+        );
+        assert_eq!(
+            a.real_roots(Some(&SmallRat(1, 10))),
+            Some(vec![
+                (SmallRat(-17, 16), SmallRat(-31, 32)),
+                (SmallInt(0), SmallInt(0)),
+                (SmallRat(31, 32), SmallRat(17, 16)),
+            ])
+        );
+
+        // x^2+1
+        let mut b = MultivariatePolynomial::<Number, u32>::from_monomial(SmallInt(1), vec![2]);
+        b.append_monomial(SmallInt(1), &[0]);
+        assert_eq!(b.real_roots(None), Some(vec![]));
+
+        let mut r: Vec<_> = b
+            .complex_roots(64)
+            .unwrap()
+            .into_iter()
+            .map(|(re, im)| (re.to_f64(), im.to_f64()))
+            .collect();
+        r.sort_by(|x, y| x.1.partial_cmp(&y.1).unwrap());
+        assert_eq!(r.len(), 2);
+        assert!(r[0].0.abs() < 1e-15 && (r[0].1 + 1.).abs() < 1e-15);
+        assert!(r[1].0.abs() < 1e-15 && (r[1].1 - 1.).abs() < 1e-15);
+    }
+
     #[test]
     fn chinese_remainder() {
         assert_eq!(
@@ -314,6 +583,484 @@ mod tests {
         assert_eq!(MultivariatePolynomial::gcd(&a, &b), res);
     }
 
+    #[test]
+    fn gcd_heuristic() {
+        // gcd((x+y)^2*(x-1),(x+y)*(x+2))=x+y
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(1), vec![3, 0]);
+        a.append_monomial(SmallInt(-1), &[2, 0]);
+        a.append_monomial(SmallInt(2), &[2, 1]);
+        a.append_monomial(SmallInt(-2), &[1, 1]);
+        a.append_monomial(SmallInt(1), &[1, 2]);
+        a.append_monomial(SmallInt(-1), &[0, 2]);
+
+        let mut b = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]);
+        b.append_monomial(SmallInt(2), &[1, 0]);
+        b.append_monomial(SmallInt(1), &[1, 1]);
+        b.append_monomial(SmallInt(2), &[0, 1]);
+
+        let mut res = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1, 0]);
+        res.append_monomial(SmallInt(1), &[0, 1]);
+
+        assert_eq!(
+            MultivariatePolynomial::gcd_heuristic(&a, &b, &[0, 1]),
+            Some(res.clone())
+        );
+        assert_eq!(MultivariatePolynomial::gcd(&a, &b), res);
+    }
+
+    #[test]
+    fn gcd_large_primes() {
+        // gcd((3x+n)*(x-2),(3x+n)*(x+5))=3x+n with n=10^25+7
+        let n = Integer::from(10).pow(25) + 7;
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(3), vec![2]);
+        a.append_monomial(BigInt(n.clone() - 6), &[1]);
+        a.append_monomial(BigInt(n.clone() * -2), &[0]);
+
+        let mut b = MultivariatePolynomial::from_monomial(SmallInt(3), vec![2]);
+        b.append_monomial(BigInt(n.clone() + 15), &[1]);
+        b.append_monomial(BigInt(n.clone() * 5), &[0]);
+
+        let mut res = MultivariatePolynomial::from_monomial(SmallInt(3), vec![1]);
+        res.append_monomial(BigInt(n.clone()), &[0]);
+
+        assert_eq!(
+            MultivariatePolynomial::gcd_large_primes(&a, &b, &[0]),
+            Some(res)
+        );
+
+        // gcd((x*y+n)*(x-y),(x*y+n)*(x+y+1))=x*y+n
+        let mut g = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1, 1]);
+        g.append_monomial(BigInt(n), &[0, 0]);
+
+        let mut f1 = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1, 0]);
+        f1.append_monomial(SmallInt(-1), &[0, 1]);
+
+        let mut f2 = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1, 0]);
+        f2.append_monomial(SmallInt(1), &[0, 1]);
+        f2.append_monomial(SmallInt(1), &[0, 0]);
+
+        let a = g.clone() * f1;
+        let b = g.clone() * f2;
+        assert_eq!(
+            MultivariatePolynomial::gcd_large_primes(&a, &b, &[0, 1]),
+            Some(g)
+        );
+    }
+
+    #[test]
+    fn gcd_galois_field() {
+        // gcd((x+a*y)*(x+y),(x+a*y)*(x-y))=x+a*y in GF(3^2) with a^2=-1
+        let modulus = Arc::new(vec![1, 0, 1]);
+        let gf = |c: Vec<u32>| GaloisField::new(c, 3, modulus.clone());
+
+        let mut a = MultivariatePolynomial::from_monomial(gf(vec![1]), vec![2, 0]);
+        a.append_monomial(gf(vec![1, 1]), &[1, 1]);
+        a.append_monomial(gf(vec![0, 1]), &[0, 2]);
+
+        let mut b = MultivariatePolynomial::from_monomial(gf(vec![1]), vec![2, 0]);
+        b.append_monomial(gf(vec![2, 1]), &[1, 1]);
+        b.append_monomial(gf(vec![0, 2]), &[0, 2]);
+
+        let mut res = MultivariatePolynomial::from_monomial(gf(vec![1]), vec![1, 0]);
+        res.append_monomial(gf(vec![0, 1]), &[0, 1]);
+
+        assert_eq!(MultivariatePolynomial::gcd(&a, &b), res);
+    }
+
+    #[test]
+    fn gcd_small_prime() {
+        // the gcd in GF(5) is computed in an extension field, since GF(5) does
+        // not have enough evaluation points
+        let ff = |n: i64| FiniteField::from_i64(n, 5);
+
+        let mut g = MultivariatePolynomial::from_monomial(ff(1), vec![1, 1, 0]);
+        g.append_monomial(ff(1), &[0, 0, 1]);
+        g.append_monomial(ff(2), &[0, 0, 0]);
+
+        let mut f1 = MultivariatePolynomial::from_monomial(ff(1), vec![1, 0, 0]);
+        f1.append_monomial(ff(-1), &[0, 1, 1]);
+
+        let mut f2 = MultivariatePolynomial::from_monomial(ff(1), vec![1, 0, 0]);
+        f2.append_monomial(ff(1), &[0, 1, 0]);
+        f2.append_monomial(ff(1), &[0, 0, 0]);
+
+        let a = g.clone() * f1;
+        let b = g.clone() * f2;
+        assert_eq!(MultivariatePolynomial::gcd(&a, &b), g);
+    }
+
+    #[test]
+    fn gcd_algebraic_number() {
+        // gcd((x+a)*(x-1),(x+a)*(x+2))=x+a in Q(a) with a=sqrt(3)
+        let minpoly = Arc::new(vec![SmallInt(-3), SmallInt(0), SmallInt(1)]);
+        let q = |c: Vec<Number>| AlgebraicNumber::new(c, minpoly.clone());
+
+        let mut a = MultivariatePolynomial::from_monomial(q(vec![SmallInt(1)]), vec![2]);
+        a.append_monomial(q(vec![SmallInt(-1), SmallInt(1)]), &[1]);
+        a.append_monomial(q(vec![SmallInt(0), SmallInt(-1)]), &[0]);
+
+        let mut b = MultivariatePolynomial::from_monomial(q(vec![SmallInt(1)]), vec![2]);
+        b.append_monomial(q(vec![SmallInt(2), SmallInt(1)]), &[1]);
+        b.append_monomial(q(vec![SmallInt(0), SmallInt(2)]), &[0]);
+
+        let mut res = MultivariatePolynomial::from_monomial(q(vec![SmallInt(1)]), vec![1]);
+        res.append_monomial(q(vec![SmallInt(0), SmallInt(1)]), &[0]);
+
+        assert_eq!(
+            MultivariatePolynomial::gcd_modular_algebraic(&a, &b),
+            Some(res.clone())
+        );
+        assert_eq!(MultivariatePolynomial::gcd_prs(&a, &b, &[0]), res);
+    }
+
+    #[test]
+    fn solve_rational_polynomial() {
+        // x*u + v = 1, u - v = 0
+        let x = MultivariatePolynomial::from_monomial(SmallInt(1), vec![1]);
+        let one = MultivariatePolynomial::from_monomial(SmallInt(1), vec![0]);
+        let a = vec![
+            vec![x.clone(), one.clone()],
+            vec![one.clone(), -one.clone()],
+        ];
+        let b = vec![one.clone(), MultivariatePolynomial::with_nvars(1)];
+
+        let den = x + one.clone();
+        assert_eq!(
+            rational_solve::solve_rational_polynomial(&a, &b),
+            Ok(vec![(one.clone(), den.clone()), (one, den)])
+        );
+    }
+
+    #[test]
+    fn reconstruct_rational_function() {
+        // (x^2+y)/(2x-3y)
+        let f = |point: &[u32], p: u32| {
+            let (x, y) = (point[0], point[1]);
+            let num = zp::add(zp::mul(x, x, p), y, p);
+            let den = zp::sub(zp::mul(2, x, p), zp::mul(3, y, p), p);
+            if den == 0 {
+                None
+            } else {
+                Some(zp::mul(num, zp::inv(den, p), p))
+            }
+        };
+
+        let (num, den) = reconstruct::reconstruct(f, 2).unwrap();
+
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(1), vec![2, 0]);
+        a.append_monomial(SmallInt(1), &[0, 1]);
+        let mut b = MultivariatePolynomial::from_monomial(SmallInt(2), vec![1, 0]);
+        b.append_monomial(SmallInt(-3), &[0, 1]);
+
+        assert_eq!(num * &b, den * &a);
+
+        // (p0*x^2+y)/(2x-3y), where the coefficient of x^2 vanishes modulo
+        // the first prime used for finding the support
+        let p0 = LARGE_U32_PRIMES[0];
+        let f = |point: &[u32], p: u32| {
+            let (x, y) = (point[0], point[1]);
+            let num = zp::add(zp::mul(p0 % p, zp::mul(x, x, p), p), y, p);
+            let den = zp::sub(zp::mul(2, x, p), zp::mul(3, y, p), p);
+            if den == 0 {
+                None
+            } else {
+                Some(zp::mul(num, zp::inv(den, p), p))
+            }
+        };
+
+        let (num, den) = reconstruct::reconstruct(f, 2).unwrap();
+
+        let mut a = MultivariatePolynomial::from_monomial(SmallInt(p0 as isize), vec![2, 0]);
+        a.append_monomial(SmallInt(1), &[0, 1]);
+
+        assert_eq!(num * &b, den * &a);
+    }
+
+    #[test]
+    fn solve_statement() {
+        let mut program = parser::parse_string(
+            r#"
+            expr E1 = f(1) + 2*f(2) - 1;
+            expr E2 = 3*f(1) - f(2) - 1/2;
+            expr F = f(1) + f(2);
+            solve E1, E2 for f(1), f(2);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("E1"), "0");
+        assert_eq!(program.get_result("F"), "9/14");
+
+        // systems without a unique solution and nonlinear equations are not solved
+        let mut program = parser::parse_string(
+            r#"
+            expr E1 = f(1) + f(2) - 1;
+            expr E2 = 2*f(1) + 2*f(2) - 2;
+            expr E3 = f(1)^2 - 1;
+            expr F = f(1);
+            solve E1, E2 for f(1), f(2);
+            solve E3 for f(1);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "f(1)");
+    }
+
+    #[test]
+    fn differentiate() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = diff_(x^3*y, x, 2);
+            expr G = x^2*f(x,y);
+            apply {
+                differentiate y;
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "6*x");
+        assert_eq!(program.get_result("G"), "der_(f(x,y),2)*x^2");
+
+        // a negative order cannot be computed, so the term is kept
+        let mut program = parser::parse_string(
+            r#"
+            expr F = x^2*y;
+            apply {
+                differentiate y, -1;
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "x^2*y");
+    }
+
+    #[test]
+    fn series() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = series_((1+ep)^-1, ep, 3) - 1 + ep - ep^2 + ep^3;
+            expr G = ep^-1*(1+ep)^4;
+            apply {
+                series ep, -1;
+                expand;
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "ep^-1");
+
+        // the series statement only applies to whole modules
+        let nested = "expr F = ep; apply { if match(ep) { series ep, 1; } }";
+        assert!(nested.parse::<Program>().is_err());
+        let rational = "expr F = ep; apply { series ep, 1/2; }";
+        assert!(rational.parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn floats() {
+        let mut program = parser::parse_string(
+            r#"
+            precision 100;
+            expr F = N_(1/4) - 0.25;
+            expr G = 1.5*x - 3/2*x + 2.5e-1*y - y/4;
+            expr H = 2.0*rat_(1, 1+x) + 2.0*rat_(x, 1+x) - 2.0*rat_(1, 1);
+            expr K = 2.0*rat_(1, 1+x) - rat_(2, 1+x);
+            expr L = 2.0*x*rat_(1, 1+x);
+            expr M = 0e99999999999 + 1.5e2 - 150;
+            expr N = f(0.5) + f(1/2) - f(1/2);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "0");
+        assert_eq!(program.get_result("H"), "0");
+        // the float is not converted to an exact polyratfun coefficient
+        assert_ne!(program.get_result("K"), "0");
+        assert!(program.get_result("L").starts_with("x*2."));
+        assert_eq!(program.get_result("M"), "0");
+        // a float and an exact number with the same value are not like terms
+        assert_ne!(program.get_result("N"), "0");
+
+        // exponents that are too large for an exact value give a float
+        assert!("expr F = 1e99999999999;".parse::<Program>().is_ok());
+        assert!("expr F = 1e2000000000*x + 1e-9223372036854775809;"
+            .parse::<Program>()
+            .is_ok());
+
+        // NaN is sorted after all other numbers
+        let nan = Number::Float(Box::new(rug::Float::with_val(53, rug::float::Special::Nan)));
+        let mut v = vec![nan.clone(), SmallInt(1), SmallRat(-1, 2)];
+        v.sort();
+        assert_eq!(&v[..2], &[SmallRat(-1, 2), SmallInt(1)]);
+        assert_eq!(nan.cmp(&SmallInt(1)), Ordering::Greater);
+        assert_eq!(nan.cmp(&nan), Ordering::Equal);
+        assert_eq!(nan, nan);
+
+        // numbers only compare as equal if they are equal
+        let half = Number::Float(Box::new(rug::Float::with_val(53, 0.5)));
+        assert_ne!(half, SmallRat(1, 2));
+        assert_ne!(half.cmp(&SmallRat(1, 2)), Ordering::Equal);
+        assert_eq!(half.partial_cmp(&SmallRat(1, 2)), Some(Ordering::Equal));
+        assert_eq!(SmallInt(0).cmp(&half), Ordering::Less);
+    }
+
+    #[test]
+    fn evaluate() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = eval_(x^2*y + 4^(1/2)*y, x=1/2, y=4);
+            expr G = eval_(rat_(1, 1+x) + 8^(2/3), x=1/3) - 19/4;
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "9");
+        assert_eq!(program.get_result("G"), "0");
+
+        let assign = "expr F = f(x=1);";
+        assert!(assign.parse::<Program>().is_err());
+
+        // exponents that do not fit in 32 bits and huge results are not evaluated
+        assert_eq!(SmallInt(2).pow_prec(&SmallInt(-3), 53), Some(SmallRat(1, 8)));
+        assert_eq!(SmallInt(2).pow_prec(&SmallInt(1 << 32), 53), None);
+        assert_eq!(SmallInt(1).pow_prec(&SmallInt(isize::min_value()), 53), None);
+        assert_eq!(SmallInt(2).pow_prec(&SmallInt(4294967295), 53), None);
+        assert_eq!(SmallInt(-1).pow_prec(&SmallInt(4294967295), 53), Some(SmallInt(-1)));
+        let big = BigInt("1000000000000000000000000".parse::<Integer>().unwrap());
+        assert_eq!(SmallInt(2).pow_prec(&big, 53), None);
+    }
+
+    #[test]
+    fn complex() {
+        let mut program = parser::parse_string(
+            r#"
+            attrib g = real;
+            attrib y = real;
+            expr F = (1+i_)^2;
+            expr G = i_^3*x + x*i_ + 1/(1+i_) - 1/2 + i_/2;
+            expr H = conj_(i_*g(y) + 2) + i_*g(y) + conj_(conj_(x)) - x - 2;
+            expr K = i_*rat_(x, 1);
+            expr L = i_*rat_(1, 1+x) + i_*rat_(x, 1+x) - rat_(2+x, 1+x)*i_ + i_*rat_(1, 1+x);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "2*i_");
+        assert_eq!(program.get_result("G"), "0");
+        assert_eq!(program.get_result("H"), "0");
+        assert_eq!(program.get_result("K"), "i_*rat_(x,1)");
+        assert_eq!(program.get_result("L"), "0");
+    }
+
+    #[test]
+    fn elementary_functions() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = exp_(x)*exp_(y)*exp_(-x) - exp_(y) + log_(1) + sqrt_(4) - 2 + sqrt_(x)^2 - x;
+            expr G = diff_(sin_(x)^2 + cos_(x)^2 + x^x, x) - x^x*log_(x) - x^x;
+            apply {
+                expand;
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "0");
+    }
+
+    #[test]
+    fn symbolic_exponents() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = x^n*x^m*x^-1 - x^(n+m-1);
+            expr G = (x^a)^2/x^a - x^a + x^(n-n) - 1 + 2^(n+1) - 2*2^n;
+            expr H = x^(n+2)*y;
+            expr K = 2^(n+5000000000) - 2^n;
+            apply {
+                id x^k?*y = k? - n;
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "0");
+        assert_eq!(program.get_result("H"), "2");
+        // the power is too large to pull out, so the exponent is kept
+        assert_ne!(program.get_result("K"), "0");
+    }
+
+    #[test]
+    fn rational_exponents() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = x^(1/2)*x^(1/2) - x + f(x)^(1/2)*g(y)*f(x)^(3/2) - f(x)^2*g(y);
+            expr G = 4^(1/2) + (9/4)^(-1/2) + 8^(2/3) + 2^(3/2) - 2*2^(1/2) - 20/3
+                + 2^n*2 - 2^(n+1);
+            expr H = y*x^(1/2)*(-2)^(1/2);
+            expr K = (x+y)^(1/2)*g(y);
+            expr L = 2^(100000000000000000001/2);
+            expr M = f(x)*f(x)^(1/2) - f(x)^(1/2)*f(x) + (x+y)*(x+y)^n - (x+y)^(n+1);
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "0");
+        assert_eq!(program.get_result("G"), "0");
+        assert_eq!(program.get_result("H"), "(-2)^(1/2)*x^(1/2)*y");
+        // the integer part of the exponent is too large to pull out exactly
+        assert_eq!(program.get_result("L"), "2^(100000000000000000001/2)");
+        // powers of subexpressions are sorted as their base, after functions;
+        // all powers used to come first: (x+y)^(1/2)*g(y)
+        assert_eq!(program.get_result("K"), "g(y)*(x+y)^(1/2)");
+        // the base is written before its power
+        assert_eq!(program.get_result("M"), "0");
+    }
+
+    #[test]
+    fn number_functions() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = fac_(5) + binom_(5, 2) + mod_(-7, 3) + div_(-7, 3) + abs_(-3/2) + sign_(-2)
+                + min_(3, -1, 1/2) + max_(3, -1, 1/2) + num_(-6/4) + den_(-6/4);
+            expr G = fac_(25) + binom_(100, 50);
+            expr H = fac_(n)*mod_(x, 2);
+            expr K = f(1) + f(2) + f(3) + f(4);
+            apply {
+                id n = 4;
+                id f(k?) = ifelse_(mod_(k?, 2) == 0, k?, 0);
+            }
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "261/2");
+        assert_eq!(program.get_result("G"), "100906855755607524320796497256");
+        assert_eq!(program.get_result("H"), "24*mod_(x,2)");
+        assert_eq!(program.get_result("K"), "6");
+    }
+
+    #[test]
+    fn prime_functions() {
+        let mut program = parser::parse_string(
+            r#"
+            expr F = isprime_(97) + isprime_(91) + nextprime_(97) + powmod_(2, 100, 1000)
+                + invmod_(3, 7) + powmod_(3, -1, 7);
+            expr G = factorint_(-360);
+            expr H = factorint_(11978932403705051890967679564);
+            expr K = f(isprime_(n), invmod_(2, 4));
+            expr L = f(isprime_(-7), isprime_(1), isprime_(0), isprime_(2));
+"#,
+        );
+        program.do_program(false, 0, 1);
+        assert_eq!(program.get_result("F"), "488");
+        assert_eq!(
+            program.get_result("G"),
+            "list_(list_(-1,1),list_(2,3),list_(3,2),list_(5,1))"
+        );
+        assert_eq!(
+            program.get_result("H"),
+            "list_(list_(2,2),list_(3,1),list_(998244353,1),list_(1000000007,2))"
+        );
+        assert_eq!(program.get_result("K"), "f(isprime_(n),invmod_(2,4))");
+        assert_eq!(program.get_result("L"), "f(0,0,0,1)");
+    }
+
     #[test]
     fn serialize1() {
         let a = Element::Term(
@@ -358,4 +1105,32 @@ mod tests {
         assert_eq!(a, res);
     }
 
+    #[test]
+    fn serialize_float() {
+        let a = Number::SmallRat(1, 3).to_float(100);
+
+        let mut buffer = vec![];
+        a.serialize(&mut buffer);
+
+        let res = Number::deserialize(&mut Cursor::new(&buffer)).unwrap();
+
+        assert_eq!(a, res);
+
+        // infinite and undefined floats are stored as special values
+        for special in &[
+            rug::float::Special::Infinity,
+            rug::float::Special::MinusInfinity,
+            rug::float::Special::Nan,
+        ] {
+            let a = Number::Float(Box::new(rug::Float::with_val(100, *special)));
+
+            let mut buffer = vec![];
+            a.serialize(&mut buffer);
+
+            let res = Number::deserialize(&mut Cursor::new(&buffer)).unwrap();
+
+            assert_eq!(a, res);
+        }
+    }
+
 }
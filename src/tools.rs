@@ -2,6 +2,8 @@ use num_traits::sign::abs;
 use num_traits::{One, Signed, Zero};
 use number::Number;
 use poly::polynomial::Polynomial;
+use rug::integer::IsPrime;
+use rug::Integer;
 use std::mem;
 use std::ops::Rem;
 use structure::Ordering;
@@ -297,6 +299,80 @@ pub fn ncr(n: u64, mut k: u64) -> u64 {
     res
 }
 
+/// Factor a positive integer into primes, using trial division for the small
+/// factors and Pollard's rho algorithm for the rest. The prime factors are
+/// returned in ascending order with their multiplicities.
+pub fn factor_integer(n: &Integer) -> Vec<(Integer, u32)> {
+    let mut factors = vec![];
+    let mut n = n.clone();
+
+    let mut p = 2u32;
+    while p < 1000 && n >= p * p {
+        let mut k = 0;
+        while n.is_divisible_u(p) {
+            n /= p;
+            k += 1;
+        }
+        if k > 0 {
+            factors.push((Integer::from(p), k));
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+
+    // the remaining factors are larger than the trial divisors
+    let mut primes = vec![];
+    let mut rest = vec![n];
+    while let Some(m) = rest.pop() {
+        if m == 1 {
+            continue;
+        }
+        if m.is_probably_prime(30) != IsPrime::No {
+            primes.push(m);
+            continue;
+        }
+        let d = pollard_rho(&m);
+        rest.push(Integer::from(&m / &d));
+        rest.push(d);
+    }
+
+    primes.sort();
+    for q in primes {
+        let mut merged = false;
+        if let Some(&mut (ref last, ref mut k)) = factors.last_mut() {
+            if *last == q {
+                *k += 1;
+                merged = true;
+            }
+        }
+        if !merged {
+            factors.push((q, 1));
+        }
+    }
+    factors
+}
+
+/// Find a non-trivial divisor of an odd composite number with Pollard's rho algorithm.
+fn pollard_rho(n: &Integer) -> Integer {
+    let mut c = 1u32;
+    loop {
+        let f = |x: &Integer| (Integer::from(x * x) + c) % n;
+        let mut x = Integer::from(2);
+        let mut y = x.clone();
+        let mut d = Integer::from(1);
+        while d == 1 {
+            x = f(&x);
+            y = f(&f(&y));
+            d = Integer::from(&x - &y).abs().gcd(n);
+        }
+
+        // the cycle did not split n, so try a different polynomial
+        if d != *n {
+            return d;
+        }
+        c += 1;
+    }
+}
+
 #[derive(Debug)]
 pub struct CombinationsWithReplacement<T> {
     data: Vec<T>,
@@ -389,5 +465,9 @@ pub fn add_num_poly(n: &mut Number, num: &mut Polynomial, den: &mut Polynomial)
                 + den.clone() * Number::BigInt(nd.numer().clone());
             *den = newden;
         }
+        Number::Float(_) | Number::Complex(_) => {
+            // such numbers and rational polynomials are never considered like terms
+            unreachable!("{} cannot be added to a rational polynomial", n)
+        }
     }
 }